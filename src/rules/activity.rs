@@ -1,23 +1,52 @@
 use pest::iterators::Pair;
 use crate::grammar_parser::Rule;
 use svg::node::element::{SVG, Rectangle};
-use crate::rules::path;
-use path::Path;
+use crate::rules::{path, shapes};
+use crate::rules::graph_layout::Rect;
+use crate::rules::theme::Theme;
+use path::{Path, Jumps, GOTO_TRACK, LANE_PADDING};
+
+const LANE_HEADER: usize = 60;
 
 pub struct Activity {
-    path: Path
+    path: Path,
+    gotos: usize,
+    theme: Theme
 }
 
 impl Activity {
     pub fn new(value: Pair<Rule>) -> Activity {
         let mut inner = value.clone().into_inner();
+        let mut lane = None;
+        if inner.peek().unwrap().as_rule() == Rule::SWIMLANE {
+            lane = Some(inner.next().unwrap().into_inner().next().unwrap().as_str().trim().to_owned());
+        }
         inner.next();
         let p_body =  inner.next().unwrap();
 
-        let path = Path::new(p_body, true);
+        let path = match lane {
+            Some(lane) => Path::in_lane(p_body, lane),
+            None => Path::new(p_body, true)
+        };
+
+        let mut labels = Vec::new();
+        let mut gotos = Vec::new();
+        path.collect_jumps(&mut labels, &mut gotos);
+        for (i, label) in labels.iter().enumerate() {
+            if labels[..i].contains(label) {
+                panic!("Duplicate name/alias found: {}", label);
+            }
+        }
+        for goto in &gotos {
+            if !labels.contains(goto) {
+                panic!("Alias not found: {}", goto);
+            }
+        }
 
         return Activity{
-            path
+            path,
+            gotos: gotos.len(),
+            theme: Theme::default()
         }
     }
 
@@ -25,25 +54,67 @@ impl Activity {
         self.path.print()
     }
 
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+    }
+
     pub fn draw(&self, svg: &mut SVG) {
-        let left = self.path.max_left()*250;
+        let left = self.path.max_left();
 
-        let rect = Rectangle::new()
-            .set("width", "100%")
-            .set("height", "100%")
-            .set("fill", "white");
-        *svg = svg.clone().add(rect);
+        let margin = self.margin();
+        let mut jumps = Jumps::default();
+        if self.has_lanes() {
+            self.draw_lanes(svg);
+            self.path.draw(margin+self.path.lane_column(0), LANE_HEADER+45, svg, &self.theme, &mut jumps);
+        } else {
+            self.path.draw(margin+left+LANE_PADDING, 25, svg, &self.theme, &mut jumps)
+        }
+        jumps.draw(self.gotos*GOTO_TRACK, svg, &self.theme);
+    }
 
-        self.path.draw(left+100, 25, svg)
+    // Space left of the flow for the tracks of the goto jumps.
+    fn margin(&self) -> usize {
+        if self.gotos == 0 {
+            return 0;
+        }
+        (self.gotos+1)*GOTO_TRACK
+    }
+
+    fn has_lanes(&self) -> bool {
+        !self.path.get_lane_names().is_empty()
+    }
+
+    // Borders of the swimlanes with their names in the headers.
+    fn draw_lanes(&self, svg: &mut SVG) {
+        let mut x = self.margin();
+        for (name, width) in self.path.get_lane_names().iter().zip(self.path.lane_widths()) {
+            let header = Rect::new(x as i32, 0, width as i32, LANE_HEADER as i32);
+            let lane = Rectangle::new()
+                .set("x", x)
+                .set("y", 0)
+                .set("width", width)
+                .set("height", self.height())
+                .set("fill", "none")
+                .set("stroke", self.theme.border_color.as_str())
+                .set("stroke-width", self.theme.line_width);
+            *svg = svg.clone().add(lane);
+            *svg = svg.clone().add(shapes::rounded_box(header, 0, self.theme.fill_color.as_str(), &self.theme));
+            *svg = svg.clone().add(shapes::centered_label(std::slice::from_ref(name), header.center(), path::LANE_TEXT_SIZE, &self.theme));
+            x += width;
+        }
     }
 
     pub fn width(&self) -> usize {
-        let left = self.path.max_left()*250;
-        let right = self.path.max_right()*400;
-        left+right+200
+        if self.has_lanes() {
+            return self.margin()+self.path.lane_widths().iter().sum::<usize>();
+        }
+        self.margin()+self.path.max_left()+self.path.max_right()+2*LANE_PADDING
     }
 
     pub fn height(&self) -> usize {
+        if self.has_lanes() {
+            return self.path.get_height()+LANE_HEADER+70;
+        }
         self.path.get_height()+50
     }
 
@@ -1,11 +1,13 @@
+use crate::rules::text_utils;
+use crate::rules::theme::Theme;
 use svg::node::{
     element::{
-        SVG, Definitions, Line, Marker, Polygon, Text as TextElement
+        SVG, Definitions, Line, Marker, Polygon, Polyline, Text as TextElement
     },
     Text
 };
 
-pub fn draw_line(x1: usize, y1: usize, x2: usize, y2: usize, svg: &mut SVG, label: String) {
+fn arrowhead(theme: &Theme) -> Definitions {
     let arrowhead = Marker::new()
         .set("id", "arrowhead")
         .set("markerWidth", "5")
@@ -16,19 +18,22 @@ pub fn draw_line(x1: usize, y1: usize, x2: usize, y2: usize, svg: &mut SVG, labe
         .add(
             Polygon::new()
                 .set("points", "-5 1.5, 0 3.5, -5 5.5")
+                .set("fill", theme.line_color.as_str())
         );
+    Definitions::new().add(arrowhead)
+}
 
+pub fn draw_line(x1: usize, y1: usize, x2: usize, y2: usize, svg: &mut SVG, label: String, theme: &Theme) {
     let mut line = Line::new()
         .set("x1", x1)
         .set("y1", y1)
         .set("x2", x2)
         .set("y2", y2)
-        .set("stroke", "#000")
+        .set("stroke", theme.line_color.as_str())
         .set("stroke-width", 8);
 
     line = line.set("marker-end", "url(#arrowhead)");
-    let defs = Definitions::new().add(arrowhead);
-    *svg = svg.clone().add(defs);
+    *svg = svg.clone().add(arrowhead(theme));
 
     // Calculate the angle of the line
     let angle = ((y2 as f32 - y1 as f32) / (x2 as f32 - x1 as f32)).atan();
@@ -37,7 +42,7 @@ pub fn draw_line(x1: usize, y1: usize, x2: usize, y2: usize, svg: &mut SVG, labe
     let center_x = (x1 + x2) / 2;
     let center_y = (y1 + y2) / 2;
 
-    let text = Text::new(label.as_str());
+    let text = Text::new(text_utils::escape(&label));
 
     // Create a text element
     let text_element = TextElement::new()
@@ -45,7 +50,7 @@ pub fn draw_line(x1: usize, y1: usize, x2: usize, y2: usize, svg: &mut SVG, labe
         .set("y", center_y - 20)
         .set("text-anchor", "middle")
         .set("dominant-baseline", "central")
-        .set("fill", "black")
+        .set("fill", theme.font_color.as_str())
         .set("font-size", 25)
         .set("transform",
              format!("rotate({} {} {})", angle.to_degrees(), center_x, center_y))
@@ -56,11 +61,62 @@ pub fn draw_line(x1: usize, y1: usize, x2: usize, y2: usize, svg: &mut SVG, labe
     *svg = svg.clone().add(line);
 }
 
+// Arrow going through the given points, used for the edges which have to go around other nodes.
+// The label is put next to the longest segment.
+pub fn draw_polyline(points: &[(usize, usize)], svg: &mut SVG, label: String, theme: &Theme) {
+    let path = Polyline::new()
+        .set("points", points.iter().map(|(x, y)| format!("{},{}", x, y)).collect::<Vec<_>>().join(" "))
+        .set("fill", "none")
+        .set("stroke", theme.line_color.as_str())
+        .set("stroke-width", 8)
+        .set("marker-end", "url(#arrowhead)");
+    *svg = svg.clone().add(arrowhead(theme));
+    *svg = svg.clone().add(path);
+
+    if label.is_empty() {
+        return;
+    }
+    let length = |(a, b): (&(usize, usize), &(usize, usize))| a.0.abs_diff(b.0) + a.1.abs_diff(b.1);
+    let (from, to) = points.iter().zip(points.iter().skip(1))
+        .max_by_key(|segment| length(*segment))
+        .unwrap();
+    let (center_x, center_y) = ((from.0 + to.0) / 2, (from.1 + to.1) / 2);
+    let vertical = from.0 == to.0;
+    let text_element = TextElement::new()
+        .set("x", if vertical { center_x - 15 } else { center_x })
+        .set("y", if vertical { center_y } else { center_y - 20 })
+        .set("text-anchor", if vertical { "end" } else { "middle" })
+        .set("dominant-baseline", "central")
+        .set("fill", theme.font_color.as_str())
+        .set("font-size", 25)
+        .add(Text::new(text_utils::escape(&label)));
+    *svg = svg.clone().add(text_element);
+}
+
 #[derive(PartialEq)]
 #[derive(Copy, Clone)]
 pub enum Type {
     STEP,
     IF,
     START,
-    END
+    END,
+    // flow final, ends only the flow which reaches it
+    FLOWEND,
+    KILL,
+    DETACH,
+    LOOP,
+    FORK,
+    SEND,
+    RECEIVE,
+    OBJECT,
+    TIMER,
+    CONNECTOR,
+    GOTO
+}
+
+impl Type {
+    // The flow doesn't go on below the node.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Type::END | Type::FLOWEND | Type::KILL | Type::DETACH | Type::GOTO)
+    }
 }
@@ -1,17 +1,33 @@
 use pest::iterators::Pair;
 use crate::grammar_parser::Rule;
-use svg::node::{
-    element::{
-        SVG, Line, Circle, Text as TextElement
-    },
-    Text
+use crate::rules::graph_layout::Rect;
+use crate::rules::text_utils;
+use crate::rules::theme::{self, Theme};
+use svg::node::element::{
+    SVG, Line, Circle, Rectangle, Text as TextElement
 };
 
+const LABEL_MAX_WIDTH: f64 = 250.0;
+const TEXT_SIZE: i32 = 30;
+const BOX_TEXT_SIZE: i32 = 24;
+const STEREOTYPE_SIZE: i32 = 18;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Left,
+    Right
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct Actor {
     // actor ?
     label: String,
     alias: String,
+    color: Option<String>,
+    // external systems are drawn as boxes instead of stick figures
+    system: bool,
+    side: Option<Side>,
+    theme: Theme,
     x: i32,
     y: i32
 }
@@ -27,24 +43,41 @@ impl Actor {
 
         match l.as_rule() {
             Rule::label => {
-                label = l.as_str().to_owned();
-                alias = String::from(l.as_str().to_owned());
+                label = text_utils::unquote(l.as_str());
+                alias = label.clone();
             },
             Rule::ALIAS => {
                 let mut inner2 = l.into_inner();
-                label = inner2.next().unwrap().as_str().to_owned();
+                label = text_utils::unquote(inner2.next().unwrap().as_str());
 
                 inner2.next(); // skip 'as'
                 alias = inner2.next().unwrap().as_str().to_owned();
             }
             _ => unreachable!()
         }
+        let mut color = None;
+        let mut system = false;
+        let mut side = None;
+        for pair in inner {
+            match pair.as_rule() {
+                Rule::system_kw => system = true,
+                Rule::color => color = Some(theme::parse_color(pair.as_str())),
+                Rule::actor_side => {
+                    side = Some(if pair.as_str() == "right" { Side::Right } else { Side::Left });
+                }
+                _ => unreachable!()
+            }
+        }
         let x = -1;
         let y = -1;
 
         Actor {
             label,
             alias,
+            color,
+            system,
+            side,
+            theme: Theme::default(),
             x,
             y
         }
@@ -54,6 +87,17 @@ impl Actor {
 
     pub fn get_actor_alias(&self) -> &String {&self.alias}
 
+    pub fn get_color(&self) -> &Option<String> {&self.color}
+
+    pub fn is_system(&self) -> bool {self.system}
+
+    // Side of the diagram given in the declaration.
+    pub fn get_side(&self) -> Option<Side> {self.side}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+    }
+
     pub fn get_x(&self) -> i32 {
         self.x
     }
@@ -63,36 +107,123 @@ impl Actor {
     }
 
 
+    // Box taken by the drawn actor, from the head to the name below the legs.
+    pub fn get_bounds(&self, r: i32) -> Rect {
+        let width = if self.system { self.box_width(r) } else { 3 * r };
+        Rect::new(self.x - width / 2, self.y - r, width, self.get_height(r))
+    }
+
+    // Height of the stick figure and all the lines of the name, or of the box of a system.
+    pub fn get_height(&self, r: i32) -> i32 {
+        if self.system {
+            let text_height = text_utils::text_height(self.box_lines().len(), BOX_TEXT_SIZE)
+                + text_utils::line_height(STEREOTYPE_SIZE);
+            return std::cmp::max(6 * r + 40, text_height.ceil() as i32 + 40);
+        }
+        let lines = text_utils::wrap(&self.label, LABEL_MAX_WIDTH, TEXT_SIZE);
+        // the first line is centered TEXT_SIZE below the legs
+        let text_height = text_utils::text_height(lines.len(), TEXT_SIZE)
+            + TEXT_SIZE as f64 - text_utils::line_height(TEXT_SIZE) / 2.0;
+        6 * r + text_height.ceil() as i32
+    }
+
+    // Width taken by the actor together with its name.
+    pub fn get_width(&self, r: i32) -> i32 {
+        if self.system {
+            return self.box_width(r);
+        }
+        let lines = text_utils::wrap(&self.label, LABEL_MAX_WIDTH, TEXT_SIZE);
+        std::cmp::max(text_utils::text_width(&lines.join("\n"), TEXT_SIZE) as i32, 3 * r)
+    }
+
     pub fn print(&self) {
-        tracing::info!("Actor name: {:?} Actor alias: {:?}",
-                 self.label, self.alias);
+        tracing::info!("Actor name: {:?} Actor alias: {:?} System: {} Side: {:?}",
+                 self.label, self.alias, self.system, self.side);
+    }
+
+    fn box_lines(&self) -> Vec<String> {
+        text_utils::wrap(&self.label, LABEL_MAX_WIDTH, BOX_TEXT_SIZE)
+    }
+
+    fn box_width(&self, r: i32) -> i32 {
+        let text_width = text_utils::text_width(&self.box_lines().join("\n"), BOX_TEXT_SIZE);
+        std::cmp::max(text_width as i32 + 30, 6 * r)
+    }
+
+    // The system takes the place of the stick figure and its name.
+    fn draw_box(&self, svg: &mut SVG, r: i32) {
+        let bounds = self.get_bounds(r);
+        let line_color = self.color.clone().unwrap_or(self.theme.line_color.clone());
+        let (cx, cy) = bounds.center();
+        let lines = self.box_lines();
+        let label_height = text_utils::text_height(lines.len(), BOX_TEXT_SIZE);
+        let stereotype_y = cy - label_height / 2.0;
+        let label_y = cy + text_utils::line_height(STEREOTYPE_SIZE) / 2.0;
+
+        let rectangle = Rectangle::new()
+            .set("x", bounds.x)
+            .set("y", bounds.y)
+            .set("width", bounds.width)
+            .set("height", bounds.height)
+            .set("fill", "none")
+            .set("stroke", line_color.as_str())
+            .set("stroke-width", self.theme.line_width.to_string());
+
+        let stereotype = TextElement::new()
+            .set("x", cx)
+            .set("y", stereotype_y)
+            .set("text-anchor", "middle")
+            .set("dominant-baseline", "central")
+            .set("fill", self.theme.font_color.as_str())
+            .set("font-size", STEREOTYPE_SIZE)
+            .add(svg::node::Text::new("«system»"));
+
+        let mut text_element = TextElement::new()
+            .set("x", cx)
+            .set("y", label_y)
+            .set("text-anchor", "middle")
+            .set("dominant-baseline", "central")
+            .set("fill", self.theme.font_color.as_str())
+            .set("font-size", BOX_TEXT_SIZE);
+        text_element = text_utils::add_lines(text_element, &lines, cx, label_y, BOX_TEXT_SIZE);
+
+        *svg = svg.clone().add(rectangle);
+        *svg = svg.clone().add(stereotype);
+        *svg = svg.clone().add(text_element);
     }
 
     pub fn draw(&mut self, svg: &mut SVG, x: i32, y: i32, r: i32) {
         self.x = x; // middle of the head
         self.y = y; // middle of the head
+        if self.system {
+            self.draw_box(svg, r);
+            return;
+        }
         // r being the size - whole actor has (6r + text_size) height and 3r width
         // (or more if the name is longer).
 
-        let line_weigth = 3; // thickness of lines
-        let text_size = 30;
+        let line_weigth = self.theme.line_width; // thickness of lines
+        let line_color = self.color.clone().unwrap_or(self.theme.line_color.clone());
+        let text_size = TEXT_SIZE;
 
-        let text_element = TextElement::new()
+        let lines = text_utils::wrap(&self.label, LABEL_MAX_WIDTH, text_size);
+        let mut text_element = TextElement::new()
             .set("x", x)
             .set("y", y + 5 * r + text_size)
             .set("text-anchor", "middle")
             .set("dominant-baseline", "central")
-            .set("fill", "black")
-            .set("font-family", "Arial")
-            .set("font-size", text_size.to_string())
-            .add(Text::new(self.label.clone().as_str()));
+            .set("fill", self.theme.font_color.as_str())
+            .set("font-size", text_size.to_string());
+        // the first line stays below the legs, the others go down
+        let text_y = (y + 5 * r + text_size) as f64 + (lines.len() - 1) as f64 * text_utils::line_height(text_size) / 2.0;
+        text_element = text_utils::add_lines(text_element, &lines, x as f64, text_y, text_size);
 
         let circle = Circle::new()
             .set("cx", x.to_string())
             .set("cy", y.to_string())
             .set("r", r.to_string())
             .set("fill", "none")
-            .set("stroke", "#000")
+            .set("stroke", line_color.as_str())
             .set("stroke-width", line_weigth.to_string());
 
         let body_line = Line::new()
@@ -100,7 +231,7 @@ impl Actor {
             .set("y1", (y + r).to_string())
             .set("x2", x.to_string())
             .set("y2", (y + 3 * r).to_string())
-            .set("stroke", "#000")
+            .set("stroke", line_color.as_str())
             .set("stroke-width", line_weigth.to_string());
 
         let left_arm = Line::new()
@@ -108,7 +239,7 @@ impl Actor {
             .set("y1", (y + r).to_string())
             .set("x2", x.to_string())
             .set("y2", (y + 2 * r).to_string())
-            .set("stroke", "#000")
+            .set("stroke", line_color.as_str())
             .set("stroke-width", line_weigth.to_string());
 
         let right_arm = Line::new()
@@ -116,7 +247,7 @@ impl Actor {
             .set("y1", (y + r).to_string())
             .set("x2", x.to_string())
             .set("y2", (y + 2 * r).to_string())
-            .set("stroke", "#000")
+            .set("stroke", line_color.as_str())
             .set("stroke-width", line_weigth.to_string());
 
         let left_leg = Line::new()
@@ -124,7 +255,7 @@ impl Actor {
             .set("y1", (y + 5 * r).to_string())
             .set("x2", x.to_string())
             .set("y2", (y + 3 * r).to_string())
-            .set("stroke", "#000")
+            .set("stroke", line_color.as_str())
             .set("stroke-width", line_weigth.to_string());
 
         let right_leg = Line::new()
@@ -132,7 +263,7 @@ impl Actor {
             .set("y1", (y + 5 * r).to_string())
             .set("x2", x.to_string())
             .set("y2", (y + 3 * r).to_string())
-            .set("stroke", "#000")
+            .set("stroke", line_color.as_str())
             .set("stroke-width", line_weigth.to_string());
 
         *svg = svg.clone().add(text_element);
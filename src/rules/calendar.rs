@@ -0,0 +1,141 @@
+use std::collections::HashSet;
+use std::fmt;
+
+// Calendar arithmetic for the gantt charts, everything is computed locally
+// with the proleptic Gregorian calendar.
+
+pub const WEEKDAYS: [&str; 7] = ["monday", "tuesday", "wednesday", "thursday", "friday", "saturday", "sunday"];
+pub const MONTHS: [&str; 12] = ["January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December"];
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31
+    }
+}
+
+// Day of the calendar, stored as the number of days since 1970-01-01.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    days: i64
+}
+
+impl Date {
+    pub fn from_ymd(year: i64, month: u32, day: u32) -> Option<Date> {
+        if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+            return None;
+        }
+        // counts from March, so the leap day is the last day of the year
+        let y = if month <= 2 { year - 1 } else { year };
+        let era = y.div_euclid(400);
+        let year_of_era = y.rem_euclid(400);
+        let m = month as i64;
+        let day_of_year = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        Some(Date { days: era * 146097 + day_of_era - 719468 })
+    }
+
+    // Accepts dates written as YYYY-MM-DD.
+    pub fn parse(value: &str) -> Result<Date, String> {
+        let parts: Vec<&str> = value.trim().split('-').collect();
+        if let [year, month, day] = parts[..] {
+            if let (Ok(year), Ok(month), Ok(day)) = (year.parse(), month.parse(), day.parse()) {
+                if let Some(date) = Date::from_ymd(year, month, day) {
+                    return Ok(date);
+                }
+            }
+        }
+        Err(format!("Invalid date: {}", value.trim()))
+    }
+
+    pub fn ymd(&self) -> (i64, u32, u32) {
+        let days = self.days + 719468;
+        let era = days.div_euclid(146097);
+        let day_of_era = days.rem_euclid(146097);
+        let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let mp = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+        let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        (year, month, day)
+    }
+
+    // Index into WEEKDAYS, 1970-01-01 was a thursday.
+    pub fn weekday(&self) -> usize {
+        (self.days + 3).rem_euclid(7) as usize
+    }
+
+    pub fn add_days(&self, days: i64) -> Date {
+        Date { days: self.days + days }
+    }
+
+    pub fn days_until(&self, other: Date) -> i64 {
+        other.days - self.days
+    }
+}
+
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (year, month, day) = self.ymd();
+        write!(f, "{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+// Working days of the project, the tasks don't progress on the closed ones.
+#[derive(Debug, Default, Clone)]
+pub struct Calendar {
+    closed_weekdays: HashSet<usize>,
+    closed_dates: HashSet<Date>
+}
+
+impl Calendar {
+    pub fn close_weekday(&mut self, weekday: usize) {
+        self.closed_weekdays.insert(weekday);
+        if self.closed_weekdays.len() == WEEKDAYS.len() {
+            panic!("All the days of the week are closed");
+        }
+    }
+
+    // Both ends of the range are closed.
+    pub fn close_dates(&mut self, from: Date, to: Date) {
+        if to < from {
+            panic!("Closed range ends before it starts: {} to {}", from, to);
+        }
+        for offset in 0..=from.days_until(to) {
+            self.closed_dates.insert(from.add_days(offset));
+        }
+    }
+
+    pub fn is_closed(&self, date: Date) -> bool {
+        self.closed_weekdays.contains(&date.weekday()) || self.closed_dates.contains(&date)
+    }
+
+    pub fn next_open(&self, date: Date) -> Date {
+        let mut date = date;
+        while self.is_closed(date) {
+            date = date.add_days(1);
+        }
+        date
+    }
+
+    // Day after the last one of the work started on the given day.
+    pub fn add_working_days(&self, begin: Date, days: i64) -> Date {
+        let mut date = begin;
+        let mut left = days;
+        while left > 0 {
+            if !self.is_closed(date) {
+                left -= 1;
+            }
+            date = date.add_days(1);
+        }
+        date
+    }
+}
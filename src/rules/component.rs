@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::graph_layout::Rect;
+use crate::rules::{shapes, text_utils};
+use crate::rules::theme::{self, Theme};
+use svg::node::element::{
+    SVG, Circle, Line, Path, Rectangle, Text as TextElement
+};
+
+const TEXT_SIZE: i32 = 20;
+const INTERFACE_TEXT_SIZE: i32 = 16;
+const LABEL_MAX_WIDTH: f64 = 220.0;
+// length of the line between the box and the lollipop or socket
+const STUB_LENGTH: i32 = 30;
+const INTERFACE_RADIUS: i32 = 8;
+const PORT_SIZE: i32 = 12;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum InterfaceKind {
+    Provided,
+    Required
+}
+
+pub struct Component {
+    label: String,
+    alias: String,
+    color: Option<String>,
+    interfaces: Vec<(String, InterfaceKind)>,
+    ports: Vec<String>,
+    theme: Theme
+}
+
+impl Component {
+    pub fn new(value: Pair<Rule>) -> Component {
+        let mut inner = value.into_inner();
+        let label;
+        let alias;
+
+        let l = inner.next().unwrap();
+        match l.as_rule() {
+            Rule::label => {
+                label = text_utils::unquote(l.as_str());
+                alias = label.clone();
+            },
+            Rule::ALIAS => {
+                let mut inner2 = l.into_inner();
+                label = text_utils::unquote(inner2.next().unwrap().as_str());
+
+                inner2.next(); // skip 'as'
+                alias = inner2.next().unwrap().as_str().to_owned();
+            }
+            _ => unreachable!()
+        }
+
+        let mut color = None;
+        let mut interfaces = Vec::new();
+        let mut ports = Vec::new();
+        for inner_pair in inner {
+            match inner_pair.as_rule() {
+                Rule::color => color = Some(theme::parse_color(inner_pair.as_str())),
+                Rule::COMPONENT_FEATURE => {
+                    let mut feature = inner_pair.into_inner();
+                    let keyword = feature.next().unwrap().as_rule();
+                    let name = feature.next().unwrap().as_str().to_owned();
+                    match keyword {
+                        Rule::provides_kw => interfaces.push((name, InterfaceKind::Provided)),
+                        Rule::requires_kw => interfaces.push((name, InterfaceKind::Required)),
+                        Rule::port_kw => ports.push(name),
+                        _ => unreachable!()
+                    }
+                }
+                _ => unreachable!()
+            }
+        }
+
+        Component {
+            label,
+            alias,
+            color,
+            interfaces,
+            ports,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_label(&self) -> &String {&self.label}
+
+    pub fn get_alias(&self) -> &String {&self.alias}
+
+    pub fn get_color(&self) -> &Option<String> {&self.color}
+
+    pub fn get_interfaces(&self) -> &Vec<(String, InterfaceKind)> {&self.interfaces}
+
+    pub fn get_ports(&self) -> &Vec<String> {&self.ports}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+    }
+
+    // Ids which links can point at: the component itself, its interfaces and ports.
+    pub fn contains(&self, id: &String) -> bool {
+        self.alias == *id || self.ports.contains(id) || self.interfaces.iter().any(|(name, _)| name == id)
+    }
+
+    fn interfaces_of(&self, kind: InterfaceKind) -> Vec<&String> {
+        self.interfaces.iter()
+            .filter(|(_, k)| *k == kind)
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    // Space taken by the interfaces on one side of the box.
+    fn side_width(&self, kind: InterfaceKind) -> i32 {
+        self.interfaces_of(kind).iter()
+            .map(|name| STUB_LENGTH + INTERFACE_RADIUS + 3 + text_utils::text_width(name, INTERFACE_TEXT_SIZE) as i32 / 2)
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn lines(&self) -> Vec<String> {
+        text_utils::wrap(&self.label, LABEL_MAX_WIDTH, TEXT_SIZE)
+    }
+
+    fn box_width(&self) -> i32 {
+        let ports = self.ports.iter()
+            .map(|p| text_utils::text_width(p, INTERFACE_TEXT_SIZE) as i32 + 20)
+            .sum::<i32>();
+        *[text_utils::text_width(&self.lines().join("\n"), TEXT_SIZE) as i32 + 70, 140, ports].iter().max().unwrap()
+    }
+
+    fn box_height(&self) -> i32 {
+        let label = 70 + (text_utils::text_height(self.lines().len(), TEXT_SIZE) - text_utils::line_height(TEXT_SIZE)) as i32;
+        let side = |kind| self.interfaces_of(kind).len() as i32 * 40 + 10;
+        *[label, side(InterfaceKind::Provided), side(InterfaceKind::Required)].iter().max().unwrap()
+    }
+
+    // The whole element with the interfaces around and the names of the ports below.
+    pub fn get_width(&self) -> i32 {
+        self.side_width(InterfaceKind::Required) + self.box_width() + self.side_width(InterfaceKind::Provided)
+    }
+
+    pub fn get_height(&self) -> i32 {
+        let ports = if self.ports.is_empty() { 0 } else { PORT_SIZE / 2 + 25 };
+        self.box_height() + ports
+    }
+
+    fn stroke(&self) -> &str {
+        self.theme.border_color.as_str()
+    }
+
+    fn draw_text(&self, svg: &mut SVG, content: &str, x: i32, y: i32) {
+        let text = TextElement::new()
+            .set("x", x)
+            .set("y", y)
+            .set("text-anchor", "middle")
+            .set("dominant-baseline", "central")
+            .set("fill", self.theme.font_color.as_str())
+            .set("font-size", INTERFACE_TEXT_SIZE)
+            .add(svg::node::Text::new(text_utils::escape(content)));
+        *svg = svg.clone().add(text);
+    }
+
+    // Provided interfaces are lollipops on the right, required ones sockets on the left.
+    fn draw_interfaces(&self, svg: &mut SVG, bounds: Rect, rects: &mut HashMap<String, Rect>) {
+        for kind in [InterfaceKind::Provided, InterfaceKind::Required] {
+            let names = self.interfaces_of(kind);
+            let step = bounds.height / (names.len() as i32 + 1);
+            for (i, name) in names.iter().enumerate() {
+                let y = bounds.y + step * (i as i32 + 1);
+                let (edge, direction) = match kind {
+                    InterfaceKind::Provided => (bounds.x + bounds.width, 1),
+                    InterfaceKind::Required => (bounds.x, -1)
+                };
+                // sockets are a bit bigger so a ball would fit inside
+                let r = if kind == InterfaceKind::Provided { INTERFACE_RADIUS } else { INTERFACE_RADIUS + 3 };
+                let center = edge + direction * (STUB_LENGTH + r);
+                let stub = Line::new()
+                    .set("x1", edge)
+                    .set("y1", y)
+                    .set("x2", edge + direction * STUB_LENGTH)
+                    .set("y2", y)
+                    .set("stroke", self.stroke())
+                    .set("stroke-width", 2);
+                *svg = svg.clone().add(stub);
+
+                if kind == InterfaceKind::Provided {
+                    let ball = Circle::new()
+                        .set("cx", center)
+                        .set("cy", y)
+                        .set("r", INTERFACE_RADIUS)
+                        .set("fill", self.theme.fill_color.as_str())
+                        .set("stroke", self.stroke())
+                        .set("stroke-width", 2);
+                    *svg = svg.clone().add(ball);
+                } else {
+                    // half circle opened away from the component
+                    let socket = Path::new()
+                        .set("d", format!("M {} {} A {} {} 0 0 1 {} {}", center, y - r, r, r, center, y + r))
+                        .set("fill", "none")
+                        .set("stroke", self.stroke())
+                        .set("stroke-width", 2);
+                    *svg = svg.clone().add(socket);
+                }
+                self.draw_text(svg, name, center, y - r - 12);
+                // interfaces are shared by name, the qualified id tells the ends of an assembly apart
+                let bounds = Rect::new(center - r, y - r, 2 * r, 2 * r);
+                rects.insert(format!("{}.{}", self.alias, name), bounds);
+                if kind == InterfaceKind::Provided || !rects.contains_key(*name) {
+                    rects.insert((*name).clone(), bounds);
+                }
+            }
+        }
+    }
+
+    // (x, y) is the top left corner of the element, rects collects the bounds of everything links can point at.
+    pub fn draw(&self, svg: &mut SVG, x: i32, y: i32, rects: &mut HashMap<String, Rect>) {
+        let bounds = Rect::new(x + self.side_width(InterfaceKind::Required), y, self.box_width(), self.box_height());
+        rects.insert(self.alias.clone(), bounds);
+
+        let rect = Rectangle::new()
+            .set("x", bounds.x)
+            .set("y", bounds.y)
+            .set("width", bounds.width)
+            .set("height", bounds.height)
+            .set("fill", self.color.clone().unwrap_or(self.theme.fill_color.clone()))
+            .set("stroke", self.stroke())
+            .set("stroke-width", 2);
+        *svg = svg.clone().add(rect);
+
+        // component icon in the top right corner
+        let (icon_x, icon_y) = (bounds.x + bounds.width - 30, bounds.y + 8);
+        let icon = Rectangle::new()
+            .set("x", icon_x)
+            .set("y", icon_y)
+            .set("width", 18)
+            .set("height", 22)
+            .set("fill", "none")
+            .set("stroke", self.stroke())
+            .set("stroke-width", 2);
+        *svg = svg.clone().add(icon);
+        for tab_y in [icon_y + 4, icon_y + 12] {
+            let tab = Rectangle::new()
+                .set("x", icon_x - 5)
+                .set("y", tab_y)
+                .set("width", 10)
+                .set("height", 6)
+                .set("fill", self.theme.fill_color.as_str())
+                .set("stroke", self.stroke())
+                .set("stroke-width", 2);
+            *svg = svg.clone().add(tab);
+        }
+
+        *svg = svg.clone().add(shapes::centered_label(&self.lines(), bounds.center(), TEXT_SIZE, &self.theme));
+
+        self.draw_interfaces(svg, bounds, rects);
+
+        let step = bounds.width / (self.ports.len() as i32 + 1);
+        for (i, port) in self.ports.iter().enumerate() {
+            let port_x = bounds.x + step * (i as i32 + 1);
+            let port_y = bounds.y + bounds.height;
+            let square = Rectangle::new()
+                .set("x", port_x - PORT_SIZE / 2)
+                .set("y", port_y - PORT_SIZE / 2)
+                .set("width", PORT_SIZE)
+                .set("height", PORT_SIZE)
+                .set("fill", self.theme.fill_color.as_str())
+                .set("stroke", self.stroke())
+                .set("stroke-width", 2);
+            *svg = svg.clone().add(square);
+            self.draw_text(svg, port, port_x, port_y + PORT_SIZE / 2 + 12);
+            rects.insert(port.clone(), Rect::new(port_x - PORT_SIZE / 2, port_y - PORT_SIZE / 2, PORT_SIZE, PORT_SIZE));
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::{HashMap, HashSet};
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::component::InterfaceKind;
+use crate::rules::graph_layout::Rect;
+use crate::rules::link::Link;
+use crate::rules::subsystem::Subsystem;
+use crate::rules::theme::Theme;
+use svg::node::element::{SVG, Line};
+
+const MARGIN: i32 = 40;
+
+pub struct ComponentDiagram {
+    root: Subsystem,
+    links: Vec<Link>,
+    theme: Theme
+}
+
+impl ComponentDiagram {
+    // Accepts the COMPONENT_DIAGRAM pair.
+    pub fn new(value: Pair<Rule>) -> ComponentDiagram {
+        let mut root = None;
+        let mut links = Vec::new();
+
+        for inner_pair in value.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::COMPONENT_ELEMENTS => root = Some(Subsystem::root(inner_pair)),
+                Rule::LINK => links.push(Link::new(inner_pair)),
+                _ => {}
+            }
+        }
+        let root = root.unwrap();
+
+        let mut ids = HashSet::new();
+        for component in root.components() {
+            let names = std::iter::once(component.get_alias())
+                .chain(component.get_ports());
+            for name in names {
+                if !ids.insert(name.clone()) {
+                    panic!("Duplicate name/alias found: {}", name);
+                }
+            }
+        }
+        // interfaces are shared between components, but can't take the name of a component or a port
+        for component in root.components() {
+            for (name, _) in component.get_interfaces() {
+                if ids.contains(name) {
+                    panic!("Duplicate name/alias found: {}", name);
+                }
+            }
+        }
+        for link in &links {
+            for id in [link.get_left_id(), link.get_right_id()] {
+                if !root.contains(id) {
+                    panic!("Alias not found: {}", id);
+                }
+            }
+        }
+
+        let mut diagram = ComponentDiagram {
+            root,
+            links,
+            theme: Theme::default()
+        };
+        let edges = diagram.edges();
+        diagram.root.layout(&edges);
+        diagram
+    }
+
+    pub fn get_root(&self) -> &Subsystem {&self.root}
+
+    pub fn get_links(&self) -> &Vec<Link> {&self.links}
+
+    // Pairs of a required and a provided interface with the same name, drawn as assembly connectors.
+    pub fn assemblies(&self) -> Vec<(String, String, String)> {
+        let components = self.root.components();
+        let mut assemblies = Vec::new();
+        for requirer in &components {
+            for (name, kind) in requirer.get_interfaces() {
+                if *kind != InterfaceKind::Required {
+                    continue;
+                }
+                let provider = components.iter().find(|c| {
+                    c.get_interfaces().iter().any(|(n, k)| n == name && *k == InterfaceKind::Provided)
+                });
+                if let Some(provider) = provider {
+                    assemblies.push((requirer.get_alias().clone(), provider.get_alias().clone(), name.clone()));
+                }
+            }
+        }
+        assemblies
+    }
+
+    // The dependent element goes above the one it depends on.
+    fn edges(&self) -> Vec<(String, String)> {
+        let mut edges: Vec<(String, String)> = self.links.iter()
+            .map(|l| (l.get_left_id().clone(), l.get_right_id().clone()))
+            .collect();
+        for (requirer, provider, _) in self.assemblies() {
+            edges.push((requirer, provider));
+        }
+        edges
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        self.root.set_theme(theme);
+        for link in &mut self.links {
+            link.set_theme(theme);
+        }
+    }
+
+    pub fn draw(&self, svg: &mut SVG) {
+        let mut rects: HashMap<String, Rect> = HashMap::new();
+        self.root.draw(svg, MARGIN, MARGIN, &mut rects);
+
+        // the socket of the required interface is joined with the ball of the provided one
+        for (requirer, provider, name) in self.assemblies() {
+            let (x1, y1) = rects[&format!("{}.{}", requirer, name)].center();
+            let (x2, y2) = rects[&format!("{}.{}", provider, name)].center();
+            let line = Line::new()
+                .set("x1", x1)
+                .set("y1", y1)
+                .set("x2", x2)
+                .set("y2", y2)
+                .set("stroke", self.theme.line_color.as_str())
+                .set("stroke-width", 2)
+                .set("stroke-dasharray", "4 4");
+            *svg = svg.clone().add(line);
+        }
+
+        for link in &self.links {
+            let from = rects[link.get_left_id()];
+            let to = rects[link.get_right_id()];
+            let (x1, y1) = from.border_point(to.center());
+            let (x2, y2) = to.border_point(from.center());
+            link.draw(svg, x1 as i32, y1 as i32, x2 as i32, y2 as i32);
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.root.get_width() + 2 * MARGIN) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.root.get_height() + 2 * MARGIN) as usize
+    }
+}
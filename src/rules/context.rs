@@ -1,20 +1,33 @@
 use pest::iterators::Pair;
 use crate::grammar_parser::Rule;
-use crate::rules::use_case::UseCase;
-use std::collections::HashMap;
-use svg::node::{
-    element::{
-        SVG, Rectangle, Text as TextElement
-    },
-    Text
+use crate::rules::use_case::{self, UseCase};
+use crate::rules::graph_layout::{self, Rect};
+use crate::rules::text_utils;
+use crate::rules::theme::{self, Theme};
+use svg::node::element::{
+    SVG, Rectangle, Text as TextElement
 };
 
+const PADDING: i32 = 30;
+// room for the labels of the links between the columns
+const GAP_X: i32 = 100;
+const GAP_Y: i32 = 40;
+// between the groups of use cases which aren't linked with each other
+const GROUP_GAP: i32 = 60;
+
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct Context {
     label: String,
     use_cases: Vec<UseCase>,
-    width_number: i32
+    color: Option<String>,
+    // use cases declared outside of any context are grouped without a frame
+    framed: bool,
+    theme: Theme,
+    // middles of the use cases, relative to the top left corner of the context
+    positions: Vec<(i32, i32)>,
+    content_width: i32,
+    content_height: i32
 }
 
 impl Context {
@@ -23,19 +36,47 @@ impl Context {
         let mut inner = value.into_inner();
 
         inner.next(); // skip 'context'
-        let label = inner.next().unwrap().as_str().trim().to_owned();
+        let label = text_utils::unquote(inner.next().unwrap().as_str());
+        let mut color = None;
+        let mut use_case_list = inner.next().unwrap();
+        if use_case_list.as_rule() == Rule::color {
+            color = Some(theme::parse_color(use_case_list.as_str()));
+            use_case_list = inner.next().unwrap();
+        }
 
-        for use_case in inner.next().unwrap().into_inner(){
+        for use_case in use_case_list.into_inner(){
             use_cases.push(UseCase::new(use_case));
         }
-        let width_number = 1;
 
-        Context {
+        let mut context = Context {
             label,
             use_cases,
-            width_number
-        }
+            color,
+            framed: true,
+            theme: Theme::default(),
+            positions: Vec::new(),
+            content_width: 0,
+            content_height: 0
+        };
+        context.layout(&[], &[]);
+        context
     }
+
+    pub fn unframed(use_cases: Vec<UseCase>) -> Context {
+        let mut context = Context {
+            label: String::new(),
+            use_cases,
+            color: None,
+            framed: false,
+            theme: Theme::default(),
+            positions: Vec::new(),
+            content_width: 0,
+            content_height: 0
+        };
+        context.layout(&[], &[]);
+        context
+    }
+
     pub fn get_context_label(&self) -> &String {&self.label}
 
     pub fn get_use_cases(&self) -> &Vec<UseCase> {
@@ -46,79 +87,164 @@ impl Context {
         &mut self.use_cases
     }
 
+    pub fn get_color(&self) -> &Option<String> {&self.color}
+
+    pub fn is_framed(&self) -> bool {self.framed}
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.use_cases.iter().any(|use_case| use_case.get_use_case_alias() == id)
+    }
+
+    // Middle of the use case relative to the top left corner of the context.
+    pub fn get_position(&self, id: &str) -> Option<(i32, i32)> {
+        self.use_cases.iter()
+            .position(|use_case| use_case.get_use_case_alias() == id)
+            .map(|index| self.positions[index])
+    }
+
+    // The theme is passed to the use cases too.
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        for use_case in &mut self.use_cases {
+            use_case.set_theme(theme);
+        }
+    }
+
     pub fn print(&self) {
-        tracing::info!("Context name {}. Width: {}. Use cases: ", self.label, self.width_number);
+        tracing::info!("Context name {}. Width: {}. Height: {}. Use cases: ", self.label, self.get_width(), self.get_height());
         for use_case in &self.use_cases {
             use_case.print();
         }
     }
 
-    pub fn get_width_number(&self) -> i32 {
-        self.width_number
+    pub fn get_width(&self) -> i32 {
+        let title = if self.framed { text_utils::text_width(&self.label, 20) as i32 + 20 } else { 0 };
+        std::cmp::max(self.content_width + 2 * PADDING, title)
     }
 
-    pub fn set_width_number(&mut self, width: i32) {
-        self.width_number = width;
+    pub fn get_height(&self) -> i32 {
+        self.content_height + 2 * PADDING
     }
 
-    pub fn draw(&mut self, mut svg: &mut SVG, x: i32, y: i32, width: i32, height: i32) {
-        let corner_radius = 10;
-        let text_size = 20;
-        let mut heights: HashMap<i32, i32> = (1..=self.width_number).map(|key| (key, 1)).collect();
-        let mut max_heights: HashMap<i32, i32> = (1..=self.width_number).map(|key| (key, 0)).collect();
-
-        let text_element = TextElement::new()
-            .set("x", (x as f64 + 0.5 * (width as f64)).to_string())
-            .set("y", y - text_size / 2)
-            .set("text-anchor", "middle")
-            .set("dominant-baseline", "central")
-            .set("fill", "black")
-            .set("font-family", "Arial")
-            .set("font-size", text_size)
-            .add(Text::new(self.label.clone().as_str()));
-
-        let rectangle = Rectangle::new()
-            .set("x", x)
-            .set("y", y)
-            .set("width", width)
-            .set("height", height)
-            .set("rx", corner_radius)
-            .set("ry", corner_radius)
-            .set("fill", "transparent")
-            .set("fill-opacity", "0.3")
-            .set("stroke", "gray")
-            .set("stroke-width", "2");
-
-        *svg = svg.clone().add(text_element);
-        *svg = svg.clone().add(rectangle);
-
-        let uc_width = 100;
-        let uc_height = f64::min(0.8 * (height as f64) / self.use_cases.len() as f64, 50.0);
-        let mut y_in_column = y;
-        let _use_cases_length = self.get_use_cases().len();
-
-        for use_case in &mut self.use_cases {
-            if let Some(value) = max_heights.get_mut(&use_case.get_width_number()) {
-                *value += 1;
+    // Use cases linked with each other are placed in columns from the source of the links to their target,
+    // every group of linked use cases gets its own rows. The groups linked with the earlier actors go first
+    // so the links from the actors don't cross.
+    pub fn layout(&mut self, edges: &[(String, String)], actors: &[String]) {
+        let count = self.use_cases.len();
+        let indices: Vec<(usize, usize)> = edges.iter().filter_map(|(from, to)| {
+            let from = self.use_cases.iter().position(|u| u.get_use_case_alias() == from)?;
+            let to = self.use_cases.iter().position(|u| u.get_use_case_alias() == to)?;
+            Some((from, to))
+        }).collect();
+
+        let mut group = vec![usize::MAX; count];
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for start in 0..count {
+            if group[start] != usize::MAX {
+                continue;
             }
-        }
-
-        for use_case in &mut self.use_cases {
-            if let Some(value) = heights.get(&use_case.get_width_number()) {
-                if let Some(value2) = max_heights.get(&use_case.get_width_number()) {
-                    y_in_column = y + (*value as i32) * std::cmp::min(100, height / (value2 + 1) as i32);
+            let mut members = vec![start];
+            group[start] = groups.len();
+            let mut next = 0;
+            while next < members.len() {
+                let node = members[next];
+                for (from, to) in &indices {
+                    let other = if *from == node { *to } else if *to == node { *from } else { continue };
+                    if group[other] == usize::MAX {
+                        group[other] = groups.len();
+                        members.push(other);
+                    }
                 }
+                next += 1;
             }
-            if let Some(value) = heights.get_mut(&use_case.get_width_number()) {
-                *value += 1;
+            members.sort();
+            groups.push(members);
+        }
+
+        let actor_rank = |members: &Vec<usize>| -> usize {
+            edges.iter().filter_map(|(from, to)| {
+                let linked = |id: &String| members.iter().any(|m| self.use_cases[*m].get_use_case_alias() == id);
+                let actor = if linked(from) { to } else if linked(to) { from } else { return None };
+                actors.iter().position(|a| a == actor)
+            }).min().unwrap_or(actors.len())
+        };
+        groups.sort_by_key(actor_rank);
+
+        // the layout goes from top to bottom, so the axes are swapped
+        let mut blocks = Vec::new();
+        let mut y = 0;
+        for members in &groups {
+            let sizes: Vec<(i32, i32)> = members.iter()
+                .map(|m| (self.use_cases[*m].get_height(), self.use_cases[*m].get_width()))
+                .collect();
+            let local: Vec<(usize, usize)> = indices.iter().filter_map(|(from, to)| {
+                Some((members.iter().position(|m| m == from)?, members.iter().position(|m| m == to)?))
+            }).collect();
+            let layout = graph_layout::layout(&sizes, &local, GAP_Y, GAP_X);
+            blocks.push((y, layout));
+            y += blocks.last().unwrap().1.width + GROUP_GAP;
+        }
+
+        self.content_width = blocks.iter().map(|(_, layout)| layout.height).max().unwrap_or(0);
+        self.content_height = std::cmp::max(y - GROUP_GAP, 0);
+        let left = (self.get_width() - self.content_width) / 2;
+
+        self.positions = vec![(0, 0); count];
+        for (members, (top, layout)) in groups.iter().zip(&blocks) {
+            let offset = left + (self.content_width - layout.height) / 2;
+            for (member, (along, across)) in members.iter().zip(&layout.positions) {
+                let use_case = &self.use_cases[*member];
+                self.positions[*member] = (
+                    offset + across + use_case.get_width() / 2,
+                    PADDING + top + along + use_case.get_height() / 2
+                );
             }
-            use_case.draw(
-                &mut svg,
-                (x + 350 / 2 + (use_case.get_width_number() - 1) * 350) as i32,
-                (y_in_column) as i32,
-                uc_width as i32,
-                uc_height as i32,
-            );
         }
     }
+
+    // (x, y) is the top left corner of the frame.
+    pub fn draw(&mut self, svg: &mut SVG, x: i32, y: i32) {
+        if self.framed {
+            draw_frame(svg, &self.label, Rect::new(x, y, self.get_width(), self.get_height()), &self.color, &self.theme);
+        }
+
+        for (use_case, (dx, dy)) in self.use_cases.iter_mut().zip(&self.positions) {
+            use_case.draw(svg, x + dx, y + dy, use_case::MIN_WIDTH, use_case::MIN_HEIGHT);
+        }
+    }
+}
+
+// Draws a rounded frame with the label above it, used by the containers of the other diagrams too.
+pub fn draw_frame(svg: &mut SVG, label: &str, bounds: Rect, color: &Option<String>, theme: &Theme) {
+    let Rect { x, y, width, height } = bounds;
+    let corner_radius = 10;
+    let text_size = 20;
+
+    let title_x = x as f64 + 0.5 * (width as f64);
+    let lines = text_utils::wrap(label, width as f64, text_size);
+    let mut text_element = TextElement::new()
+        .set("x", title_x.to_string())
+        .set("y", y - text_size / 2)
+        .set("text-anchor", "middle")
+        .set("dominant-baseline", "central")
+        .set("fill", theme.font_color.as_str())
+        .set("font-size", text_size);
+    // the last line of the title stays just above the frame, the others go up
+    let title_y = (y - text_size / 2) as f64 - (lines.len() - 1) as f64 * text_utils::line_height(text_size) / 2.0;
+    text_element = text_utils::add_lines(text_element, &lines, title_x, title_y, text_size);
+
+    let rectangle = Rectangle::new()
+        .set("x", x)
+        .set("y", y)
+        .set("width", width)
+        .set("height", height)
+        .set("rx", corner_radius)
+        .set("ry", corner_radius)
+        .set("fill", color.clone().unwrap_or("transparent".to_string()))
+        .set("fill-opacity", "0.3")
+        .set("stroke", theme.context_color.as_str())
+        .set("stroke-width", "2");
+
+    *svg = svg.clone().add(text_element);
+    *svg = svg.clone().add(rectangle);
 }
@@ -0,0 +1,93 @@
+use std::collections::{HashMap, HashSet};
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::deployment_node::DeploymentNode;
+use crate::rules::graph_layout::Rect;
+use crate::rules::link::Link;
+use crate::rules::theme::Theme;
+use svg::node::element::SVG;
+
+const MARGIN: i32 = 40;
+
+pub struct Deployment {
+    root: DeploymentNode,
+    links: Vec<Link>,
+    theme: Theme
+}
+
+impl Deployment {
+    // Accepts the DEPLOYMENT_DIAGRAM pair, the links are the communication paths.
+    pub fn new(value: Pair<Rule>) -> Deployment {
+        let mut root = None;
+        let mut links = Vec::new();
+
+        for inner_pair in value.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::DEPLOYMENT_ELEMENTS => root = Some(DeploymentNode::root(inner_pair)),
+                Rule::LINK => links.push(Link::new(inner_pair)),
+                _ => {}
+            }
+        }
+        let mut root = root.unwrap();
+
+        let mut aliases = Vec::new();
+        root.aliases(&mut aliases);
+        let mut declared = HashSet::new();
+        for alias in aliases {
+            if !declared.insert(alias.clone()) {
+                panic!("Duplicate name/alias found: {}", alias);
+            }
+        }
+        for link in &links {
+            for id in [link.get_left_id(), link.get_right_id()] {
+                if !declared.contains(id) {
+                    panic!("Alias not found: {}", id);
+                }
+            }
+        }
+
+        let edges: Vec<(String, String)> = links.iter()
+            .map(|l| (l.get_left_id().clone(), l.get_right_id().clone()))
+            .collect();
+        root.layout(&edges);
+
+        Deployment {
+            root,
+            links,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_root(&self) -> &DeploymentNode {&self.root}
+
+    pub fn get_links(&self) -> &Vec<Link> {&self.links}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        self.root.set_theme(theme);
+        for link in &mut self.links {
+            link.set_theme(theme);
+        }
+    }
+
+    pub fn draw(&self, svg: &mut SVG) {
+        let mut rects: HashMap<String, Rect> = HashMap::new();
+        self.root.draw(svg, MARGIN, MARGIN, &mut rects);
+
+        for link in &self.links {
+            let from = rects[link.get_left_id()];
+            let to = rects[link.get_right_id()];
+            let (x1, y1) = from.border_point(to.center());
+            let (x2, y2) = to.border_point(from.center());
+            link.draw(svg, x1 as i32, y1 as i32, x2 as i32, y2 as i32);
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.root.get_width() + 2 * MARGIN) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.root.get_height() + 2 * MARGIN) as usize
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::HashMap;
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::graph_layout::{self, GraphLayout, Rect};
+use crate::rules::text_utils;
+use crate::rules::theme::{self, Theme};
+use svg::node::element::{
+    SVG, Polygon, Polyline, Rectangle, Text as TextElement
+};
+
+const TEXT_SIZE: i32 = 20;
+const STEREOTYPE_SIZE: i32 = 14;
+const LABEL_MAX_WIDTH: f64 = 240.0;
+// how far the top and the side of the 3D box stick out
+const DEPTH: i32 = 15;
+const PADDING: i32 = 25;
+const GAP_X: i32 = 80;
+const GAP_Y: i32 = 80;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum DeploymentKind {
+    Node,
+    Environment,
+    Artifact
+}
+
+// Node, execution environment or artifact, nodes and environments can contain other elements.
+pub struct DeploymentNode {
+    label: String,
+    alias: String,
+    kind: DeploymentKind,
+    color: Option<String>,
+    children: Vec<DeploymentNode>,
+    layout: Option<GraphLayout>,
+    theme: Theme
+}
+
+impl DeploymentNode {
+    pub fn new(value: Pair<Rule>) -> DeploymentNode {
+        let mut inner = value.into_inner();
+        let kind = match inner.next().unwrap().as_rule() {
+            Rule::node_kw => DeploymentKind::Node,
+            Rule::environment_kw => DeploymentKind::Environment,
+            Rule::artifact_kw => DeploymentKind::Artifact,
+            _ => unreachable!()
+        };
+
+        let label;
+        let alias;
+        let l = inner.next().unwrap();
+        match l.as_rule() {
+            Rule::label => {
+                label = text_utils::unquote(l.as_str());
+                alias = label.clone();
+            },
+            Rule::ALIAS => {
+                let mut inner2 = l.into_inner();
+                label = text_utils::unquote(inner2.next().unwrap().as_str());
+
+                inner2.next(); // skip 'as'
+                alias = inner2.next().unwrap().as_str().to_owned();
+            }
+            _ => unreachable!()
+        }
+
+        let mut color = None;
+        let mut children = Vec::new();
+        for inner_pair in inner {
+            match inner_pair.as_rule() {
+                Rule::color => color = Some(theme::parse_color(inner_pair.as_str())),
+                Rule::DEPLOYMENT_ELEMENTS => {
+                    if kind == DeploymentKind::Artifact {
+                        panic!("Artifact {} can't contain other elements", alias);
+                    }
+                    children = inner_pair.into_inner().map(DeploymentNode::new).collect();
+                }
+                _ => unreachable!()
+            }
+        }
+
+        DeploymentNode {
+            label,
+            alias,
+            kind,
+            color,
+            children,
+            layout: None,
+            theme: Theme::default()
+        }
+    }
+
+    // Top level of the diagram, accepts the DEPLOYMENT_ELEMENTS pair.
+    pub fn root(value: Pair<Rule>) -> DeploymentNode {
+        DeploymentNode {
+            label: String::new(),
+            alias: String::new(),
+            kind: DeploymentKind::Node,
+            color: None,
+            children: value.into_inner().map(DeploymentNode::new).collect(),
+            layout: None,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_label(&self) -> &String {&self.label}
+
+    pub fn get_alias(&self) -> &String {&self.alias}
+
+    pub fn get_kind(&self) -> DeploymentKind {self.kind}
+
+    pub fn get_children(&self) -> &Vec<DeploymentNode> {&self.children}
+
+    fn is_root(&self) -> bool {
+        self.alias.is_empty()
+    }
+
+    pub fn contains(&self, alias: &String) -> bool {
+        self.alias == *alias || self.children.iter().any(|c| c.contains(alias))
+    }
+
+    // Aliases of the element and all the nested ones.
+    pub fn aliases(&self, aliases: &mut Vec<String>) {
+        if !self.is_root() {
+            aliases.push(self.alias.clone());
+        }
+        for child in &self.children {
+            child.aliases(aliases);
+        }
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        for child in &mut self.children {
+            child.set_theme(theme);
+        }
+    }
+
+    // Lays out the nested elements first, as their size is needed here.
+    pub fn layout(&mut self, edges: &[(String, String)]) {
+        for child in &mut self.children {
+            child.layout(edges);
+        }
+        let sizes: Vec<(i32, i32)> = self.children.iter()
+            .map(|c| (c.get_width(), c.get_height()))
+            .collect();
+        let indices: Vec<(usize, usize)> = edges.iter().filter_map(|(from, to)| {
+            let from = self.children.iter().position(|c| c.contains(from))?;
+            let to = self.children.iter().position(|c| c.contains(to))?;
+            Some((from, to))
+        }).collect();
+        self.layout = Some(graph_layout::layout(&sizes, &indices, GAP_X, GAP_Y));
+    }
+
+    fn lines(&self) -> Vec<String> {
+        text_utils::wrap(&self.label, LABEL_MAX_WIDTH, TEXT_SIZE)
+    }
+
+    fn stereotype(&self) -> &str {
+        match self.kind {
+            DeploymentKind::Node => "",
+            DeploymentKind::Environment => "«executionEnvironment»",
+            DeploymentKind::Artifact => "«artifact»"
+        }
+    }
+
+    // Height of the stereotype and the label.
+    fn header_height(&self) -> i32 {
+        let stereotype = if self.stereotype().is_empty() { 0 } else { text_utils::line_height(STEREOTYPE_SIZE) as i32 };
+        stereotype + text_utils::text_height(self.lines().len(), TEXT_SIZE) as i32 + 20
+    }
+
+    fn content_width(&self) -> i32 {
+        self.layout.as_ref().map(|l| l.width).unwrap_or(0)
+    }
+
+    fn content_height(&self) -> i32 {
+        self.layout.as_ref().map(|l| l.height).unwrap_or(0)
+    }
+
+    // Size of the front face, the 3D box sticks out by DEPTH above and on the right.
+    fn face_width(&self) -> i32 {
+        let text = f64::max(
+            text_utils::text_width(&self.lines().join("\n"), TEXT_SIZE),
+            text_utils::text_width(self.stereotype(), STEREOTYPE_SIZE)) as i32 + 40;
+        let content = if self.children.is_empty() { 0 } else { self.content_width() + 2 * PADDING };
+        *[text, content, 140].iter().max().unwrap()
+    }
+
+    fn face_height(&self) -> i32 {
+        let content = if self.children.is_empty() { 10 } else { self.content_height() + PADDING };
+        std::cmp::max(self.header_height() + content, 70)
+    }
+
+    pub fn get_width(&self) -> i32 {
+        if self.is_root() {
+            return self.content_width();
+        }
+        match self.kind {
+            DeploymentKind::Artifact => self.face_width(),
+            _ => self.face_width() + DEPTH
+        }
+    }
+
+    pub fn get_height(&self) -> i32 {
+        if self.is_root() {
+            return self.content_height();
+        }
+        match self.kind {
+            DeploymentKind::Artifact => self.face_height(),
+            _ => self.face_height() + DEPTH
+        }
+    }
+
+    fn fill(&self) -> String {
+        self.color.clone().unwrap_or(self.theme.fill_color.clone())
+    }
+
+    fn draw_box(&self, svg: &mut SVG, face: Rect) {
+        let Rect { x, y, width, height } = face;
+        let stroke = self.theme.border_color.as_str();
+        let top = format!("{},{} {},{} {},{} {},{}",
+            x, y, x + DEPTH, y - DEPTH, x + width + DEPTH, y - DEPTH, x + width, y);
+        let side = format!("{},{} {},{} {},{} {},{}",
+            x + width, y, x + width + DEPTH, y - DEPTH, x + width + DEPTH, y + height - DEPTH, x + width, y + height);
+        for points in [top, side] {
+            let polygon = Polygon::new()
+                .set("points", points)
+                .set("fill", self.fill())
+                .set("stroke", stroke)
+                .set("stroke-width", 2);
+            *svg = svg.clone().add(polygon);
+        }
+        let front = Rectangle::new()
+            .set("x", x)
+            .set("y", y)
+            .set("width", width)
+            .set("height", height)
+            .set("fill", self.fill())
+            .set("stroke", stroke)
+            .set("stroke-width", 2);
+        *svg = svg.clone().add(front);
+    }
+
+    // Document with a folded corner, like the icon of an artifact.
+    fn draw_artifact(&self, svg: &mut SVG, face: Rect) {
+        let Rect { x, y, width, height } = face;
+        let stroke = self.theme.border_color.as_str();
+        let rect = Rectangle::new()
+            .set("x", x)
+            .set("y", y)
+            .set("width", width)
+            .set("height", height)
+            .set("fill", self.fill())
+            .set("stroke", stroke)
+            .set("stroke-width", 2);
+        *svg = svg.clone().add(rect);
+
+        let (icon_x, icon_y) = (x + width - 26, y + 8);
+        let icon = Polyline::new()
+            .set("points", format!("{},{} {},{} {},{} {},{} {},{} {},{} {},{}",
+                icon_x, icon_y, icon_x + 10, icon_y, icon_x + 16, icon_y + 6, icon_x + 16, icon_y + 20,
+                icon_x, icon_y + 20, icon_x, icon_y, icon_x + 10, icon_y))
+            .set("fill", "none")
+            .set("stroke", stroke)
+            .set("stroke-width", 2);
+        let fold = Polyline::new()
+            .set("points", format!("{},{} {},{} {},{}", icon_x + 10, icon_y, icon_x + 10, icon_y + 6, icon_x + 16, icon_y + 6))
+            .set("fill", "none")
+            .set("stroke", stroke)
+            .set("stroke-width", 2);
+        *svg = svg.clone().add(icon).add(fold);
+    }
+
+    // (x, y) is the top left corner of the element, rects collects the bounds of the front faces.
+    pub fn draw(&self, svg: &mut SVG, x: i32, y: i32, rects: &mut HashMap<String, Rect>) {
+        let (mut content_x, mut content_y) = (x, y);
+        if !self.is_root() {
+            let face = match self.kind {
+                DeploymentKind::Artifact => Rect::new(x, y, self.face_width(), self.face_height()),
+                _ => Rect::new(x, y + DEPTH, self.face_width(), self.face_height())
+            };
+            rects.insert(self.alias.clone(), face);
+            match self.kind {
+                DeploymentKind::Artifact => self.draw_artifact(svg, face),
+                _ => self.draw_box(svg, face)
+            }
+
+            let center_x = face.x + face.width / 2;
+            let mut text_y = face.y + 10;
+            if !self.stereotype().is_empty() {
+                text_y += text_utils::line_height(STEREOTYPE_SIZE) as i32 / 2;
+                let stereotype = TextElement::new()
+                    .set("x", center_x)
+                    .set("y", text_y)
+                    .set("text-anchor", "middle")
+                    .set("dominant-baseline", "central")
+                    .set("fill", self.theme.font_color.as_str())
+                    .set("font-size", STEREOTYPE_SIZE)
+                    .add(svg::node::Text::new(self.stereotype()));
+                *svg = svg.clone().add(stereotype);
+                text_y += text_utils::line_height(STEREOTYPE_SIZE) as i32 / 2;
+            }
+
+            let lines = self.lines();
+            // a lonely element has its label in the middle
+            let label_y = if self.children.is_empty() && self.kind != DeploymentKind::Artifact {
+                face.y + face.height / 2
+            } else {
+                text_y + text_utils::text_height(lines.len(), TEXT_SIZE) as i32 / 2 + 5
+            };
+            let text = TextElement::new()
+                .set("x", center_x)
+                .set("y", label_y)
+                .set("text-anchor", "middle")
+                .set("dominant-baseline", "central")
+                .set("fill", self.theme.font_color.as_str())
+                .set("font-size", TEXT_SIZE)
+                .set("font-weight", "bold");
+            *svg = svg.clone().add(text_utils::add_lines(text, &lines, center_x as f64, label_y as f64, TEXT_SIZE));
+
+            content_x = face.x + (face.width - self.content_width()) / 2;
+            content_y = face.y + self.header_height();
+        }
+
+        if let Some(layout) = &self.layout {
+            for (child, (dx, dy)) in self.children.iter().zip(&layout.positions) {
+                child.draw(svg, content_x + dx, content_y + dy, rects);
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::graph_layout::Rect;
+use crate::rules::structs;
+use crate::rules::text_utils;
+use crate::rules::theme::{self, Theme};
+use svg;
+
+pub struct Column {
+    name: String,
+    kind: String,
+    primary_key: bool,
+    foreign_key: bool
+}
+
+impl Column {
+    pub fn new(value: Pair<Rule>) -> Column {
+        let mut inner = value.into_inner();
+        let name = inner.next().unwrap().as_str().to_owned();
+        inner.next(); // skip colon
+        let kind = inner.next().unwrap().as_str().to_owned();
+
+        let mut primary_key = false;
+        let mut foreign_key = false;
+        for marker in inner {
+            match marker.as_rule() {
+                Rule::primary_key => primary_key = true,
+                Rule::foreign_key => foreign_key = true,
+                _ => unreachable!()
+            }
+        }
+
+        Column {
+            name,
+            kind,
+            primary_key,
+            foreign_key
+        }
+    }
+
+    pub fn get_name(&self) -> &String {&self.name}
+    pub fn get_kind(&self) -> &String {&self.kind}
+    pub fn is_primary_key(&self) -> bool {self.primary_key}
+    pub fn is_foreign_key(&self) -> bool {self.foreign_key}
+
+    fn content(&self) -> String {
+        let mut markers = Vec::new();
+        if self.primary_key {
+            markers.push("PK");
+        }
+        if self.foreign_key {
+            markers.push("FK");
+        }
+        let markers = if markers.is_empty() { String::new() } else { markers.join(",") + " " };
+        format!("{}{} : {}", markers, self.name, self.kind)
+    }
+}
+
+// Table of the schema, drawn with the compartments of a class: the name, the primary keys and the other columns.
+pub struct Entity {
+    name: String,
+    columns: Vec<Column>,
+    color: Option<String>,
+    theme: Theme
+}
+
+impl Entity {
+    pub fn new(value: Pair<Rule>) -> Entity {
+        let mut inner = value.into_inner();
+        let name = text_utils::unquote(inner.next().unwrap().as_str());
+        let mut color = None;
+        let mut columns = Vec::new();
+
+        for inner_pair in inner {
+            match inner_pair.as_rule() {
+                Rule::color => color = Some(theme::parse_color(inner_pair.as_str())),
+                Rule::COLUMN => columns.push(Column::new(inner_pair)),
+                _ => unreachable!()
+            }
+        }
+
+        Entity {
+            name,
+            columns,
+            color,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_name(&self) -> &String {&self.name}
+    pub fn get_columns(&self) -> &Vec<Column> {&self.columns}
+    pub fn get_color(&self) -> &Option<String> {&self.color}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+    }
+
+    // Primary keys go first, separated from the other columns.
+    fn rows(&self) -> (Vec<String>, Vec<String>) {
+        let keys = self.columns.iter().filter(|c| c.is_primary_key()).map(|c| c.content()).collect();
+        let others = self.columns.iter().filter(|c| !c.is_primary_key()).map(|c| c.content()).collect();
+        (keys, others)
+    }
+
+    pub fn get_width(&self) -> i32 {
+        let longest = self.columns.iter()
+            .map(|c| c.content().chars().count() * 15 + 30)
+            .chain(std::iter::once(self.name.chars().count() * 15 + 60))
+            .max()
+            .unwrap_or(0);
+        std::cmp::max(longest, 200) as i32
+    }
+
+    pub fn get_height(&self) -> i32 {
+        (self.columns.len() as i32 + 1) * 50
+    }
+
+    fn draw_line(&self, svg: &mut svg::node::element::SVG, x: i32, y: i32, width: i32) {
+        let line = svg::node::element::Line::new()
+            .set("x1", x)
+            .set("y1", y)
+            .set("x2", x + width)
+            .set("y2", y)
+            .set("stroke", self.theme.line_color.as_str())
+            .set("stroke-width", 5);
+        *svg = svg.clone().add(line);
+    }
+
+    pub fn draw(&self, svg: &mut svg::node::element::SVG, x: i32, y: i32) -> Rect {
+        let width = self.get_width();
+        let height = self.get_height();
+
+        let rect = svg::node::element::Rectangle::new()
+            .set("x", x)
+            .set("y", y)
+            .set("width", width)
+            .set("height", height)
+            .set("fill", self.color.clone().unwrap_or(self.theme.fill_color.clone()))
+            .set("stroke", self.theme.border_color.as_str())
+            .set("stroke-width", self.theme.class_border_width);
+        *svg = svg.clone().add(rect);
+
+        let name_label = svg::node::element::Text::new()
+            .set("x", x + width / 2)
+            .set("y", y + 25)
+            .set("text-anchor", "middle")
+            .set("dominant-baseline", "central")
+            .set("fill", self.theme.font_color.as_str())
+            .set("font-size", 28)
+            .set("font-weight", "bold")
+            .add(svg::node::Text::new(text_utils::escape(&self.name)));
+        *svg = svg.clone().add(name_label);
+
+        let (keys, others) = self.rows();
+        if !self.columns.is_empty() {
+            self.draw_line(svg, x, y + 50, width);
+        }
+        if !keys.is_empty() && !others.is_empty() {
+            self.draw_line(svg, x, y + 50 * (keys.len() as i32 + 1), width);
+        }
+
+        for (i, content) in keys.iter().chain(&others).enumerate() {
+            let text = svg::node::Text::new(text_utils::escape(content));
+            *svg = svg.clone().add(structs::add_row(text, i, x as usize, y as usize + 75, &self.theme));
+        }
+
+        Rect::new(x, y, width, height)
+    }
+}
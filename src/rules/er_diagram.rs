@@ -0,0 +1,98 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::entity::Entity;
+use crate::rules::graph_layout::{self, Rect};
+use crate::rules::relationship::Relationship;
+use crate::rules::theme::Theme;
+use svg::node::element::SVG;
+
+const MARGIN: i32 = 40;
+const GAP: i32 = 120;
+
+pub struct ErDiagram {
+    entities: Vec<Entity>,
+    relationships: Vec<Relationship>,
+    positions: Vec<(i32, i32)>,
+    width: i32,
+    height: i32,
+    theme: Theme
+}
+
+impl ErDiagram {
+    // Accepts the ER_DIAGRAM pair, related entities are laid out next to each other.
+    pub fn new(value: Pair<Rule>) -> ErDiagram {
+        let mut entities: Vec<Entity> = Vec::new();
+        let mut relationships = Vec::new();
+
+        for inner_pair in value.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::ENTITY => {
+                    let entity = Entity::new(inner_pair);
+                    if entities.iter().any(|e| e.get_name() == entity.get_name()) {
+                        panic!("Duplicate name/alias found: {}", entity.get_name());
+                    }
+                    entities.push(entity);
+                }
+                Rule::RELATIONSHIP => relationships.push(Relationship::new(inner_pair)),
+                _ => {}
+            }
+        }
+
+        let edges: Vec<(usize, usize)> = relationships.iter()
+            .map(|r: &Relationship| (ErDiagram::index(&entities, r.get_left_id()), ErDiagram::index(&entities, r.get_right_id())))
+            .collect();
+        let sizes: Vec<(i32, i32)> = entities.iter()
+            .map(|e| (e.get_width(), e.get_height()))
+            .collect();
+        let layout = graph_layout::layout(&sizes, &edges, GAP, GAP);
+
+        ErDiagram {
+            entities,
+            relationships,
+            positions: layout.positions,
+            width: layout.width,
+            height: layout.height,
+            theme: Theme::default()
+        }
+    }
+
+    fn index(entities: &[Entity], name: &String) -> usize {
+        entities.iter()
+            .position(|e| e.get_name() == name)
+            .unwrap_or_else(|| panic!("Alias not found: {}", name))
+    }
+
+    pub fn get_entities(&self) -> &Vec<Entity> {&self.entities}
+
+    pub fn get_relationships(&self) -> &Vec<Relationship> {&self.relationships}
+
+    pub fn get_positions(&self) -> &Vec<(i32, i32)> {&self.positions}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        for entity in &mut self.entities {
+            entity.set_theme(theme);
+        }
+    }
+
+    pub fn draw(&self, svg: &mut SVG) {
+        let rects: Vec<Rect> = self.entities.iter()
+            .zip(&self.positions)
+            .map(|(entity, (x, y))| entity.draw(svg, MARGIN + x, MARGIN + y))
+            .collect();
+
+        for relationship in &self.relationships {
+            let from = rects[ErDiagram::index(&self.entities, relationship.get_left_id())];
+            let to = rects[ErDiagram::index(&self.entities, relationship.get_right_id())];
+            relationship.draw(svg, from, to, &self.theme);
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.width + 2 * MARGIN) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.height + 2 * MARGIN) as usize
+    }
+}
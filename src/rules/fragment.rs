@@ -0,0 +1,129 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::sequence::Statement;
+use crate::rules::text_utils;
+use crate::rules::theme::Theme;
+use svg::node::element::{
+    Group, Line, Polygon, Rectangle, Text as TextElement
+};
+
+const TEXT_SIZE: i32 = 16;
+// height of the tab with the fragment kind in the top left corner
+pub const HEADER_HEIGHT: i32 = 24;
+
+pub struct Section {
+    guard: String,
+    statements: Vec<Statement>
+}
+
+impl Section {
+    pub fn new(value: Pair<Rule>) -> Section {
+        let mut guard = String::new();
+        let mut statements = Vec::new();
+
+        for inner_pair in value.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::label => guard = text_utils::unquote(inner_pair.as_str()),
+                _ => statements.push(Statement::new(inner_pair))
+            }
+        }
+
+        Section {
+            guard,
+            statements
+        }
+    }
+
+    pub fn get_guard(&self) -> &String {&self.guard}
+
+    pub fn get_statements(&self) -> &Vec<Statement> {&self.statements}
+}
+
+// Combined fragment: alt, opt, loop, par, break or critical.
+pub struct Fragment {
+    kind: String,
+    sections: Vec<Section>
+}
+
+impl Fragment {
+    pub fn new(value: Pair<Rule>) -> Fragment {
+        let mut inner = value.into_inner();
+        let kind = inner.next().unwrap().as_str().to_owned();
+        let sections = inner.map(Section::new).collect();
+
+        Fragment {
+            kind,
+            sections
+        }
+    }
+
+    pub fn get_kind(&self) -> &String {&self.kind}
+
+    pub fn get_sections(&self) -> &Vec<Section> {&self.sections}
+
+    fn draw_guard(group: &mut Group, guard: &str, x: i32, y: i32, theme: &Theme) {
+        if guard.is_empty() {
+            return;
+        }
+        let text = TextElement::new()
+            .set("x", x)
+            .set("y", y)
+            .set("dominant-baseline", "central")
+            .set("fill", theme.font_color.as_str())
+            .set("font-size", TEXT_SIZE)
+            .add(svg::node::Text::new(text_utils::escape(&format!("[{}]", guard))));
+        *group = group.clone().add(text);
+    }
+
+    // Draws the frame spanning horizontally from left to right,
+    // separators holds the y coordinates of the lines between the sections.
+    pub fn draw(&self, group: &mut Group, (left, right): (i32, i32), top: i32, bottom: i32, separators: &[i32], theme: &Theme) {
+        let frame = Rectangle::new()
+            .set("x", left)
+            .set("y", top)
+            .set("width", right - left)
+            .set("height", bottom - top)
+            .set("fill", "none")
+            .set("stroke", theme.border_color.as_str())
+            .set("stroke-width", 2);
+        *group = group.clone().add(frame);
+
+        let tab_width = text_utils::text_width(&self.kind, TEXT_SIZE) as i32 + 20;
+        let points = format!("{},{} {},{} {},{} {},{} {},{}",
+            left, top, left + tab_width, top, left + tab_width, top + HEADER_HEIGHT - 8,
+            left + tab_width - 8, top + HEADER_HEIGHT, left, top + HEADER_HEIGHT);
+        let tab = Polygon::new()
+            .set("points", points)
+            .set("fill", theme.fill_color.as_str())
+            .set("stroke", theme.border_color.as_str())
+            .set("stroke-width", 2);
+        *group = group.clone().add(tab);
+
+        let kind = TextElement::new()
+            .set("x", left + 8)
+            .set("y", top + HEADER_HEIGHT / 2)
+            .set("dominant-baseline", "central")
+            .set("fill", theme.font_color.as_str())
+            .set("font-size", TEXT_SIZE)
+            .set("font-weight", "bold")
+            .add(svg::node::Text::new(self.kind.as_str()));
+        *group = group.clone().add(kind);
+
+        if let Some(section) = self.sections.first() {
+            Fragment::draw_guard(group, section.get_guard(), left + tab_width + 10, top + HEADER_HEIGHT / 2, theme);
+        }
+
+        for (section, y) in self.sections.iter().skip(1).zip(separators) {
+            let line = Line::new()
+                .set("x1", left)
+                .set("y1", *y)
+                .set("x2", right)
+                .set("y2", *y)
+                .set("stroke", theme.border_color.as_str())
+                .set("stroke-width", 2)
+                .set("stroke-dasharray", "8 8");
+            *group = group.clone().add(line);
+            Fragment::draw_guard(group, section.get_guard(), left + 10, y + HEADER_HEIGHT / 2, theme);
+        }
+    }
+}
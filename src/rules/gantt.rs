@@ -0,0 +1,343 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::calendar::{self, Calendar, Date};
+use crate::rules::task::{self, Anchor, Task};
+use crate::rules::text_utils;
+use crate::rules::theme::Theme;
+use svg::node::element::{
+    SVG, Line, Path, Polygon, Rectangle, Text as TextElement
+};
+
+const MARGIN: i32 = 20;
+const TEXT_SIZE: i32 = 16;
+const AXIS_TEXT_SIZE: i32 = 12;
+const DAY_WIDTH: i32 = 28;
+const ROW_HEIGHT: i32 = 40;
+const BAR_HEIGHT: i32 = 20;
+// month names and day numbers
+const HEADER_HEIGHT: i32 = 50;
+// Longest time shown on the axis in days, about twenty years.
+const MAX_SPAN: i64 = 7300;
+
+pub struct Gantt {
+    start: Date,
+    calendar: Calendar,
+    tasks: Vec<Task>,
+    theme: Theme
+}
+
+impl Gantt {
+    // Accepts the GANTT_DIAGRAM pair, the statements about a task can be spread over several lines.
+    pub fn new(value: Pair<Rule>) -> Gantt {
+        let mut start = None;
+        let mut calendar = Calendar::default();
+        let mut tasks: Vec<Task> = Vec::new();
+
+        for inner_pair in value.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::PROJECT_START => {
+                    let date = inner_pair.into_inner().next().unwrap().as_str();
+                    start = Some(Date::parse(date).unwrap_or_else(|e| panic!("{}", e)));
+                }
+                Rule::CLOSED_DAYS => {
+                    let mut inner = inner_pair.into_inner();
+                    let day = inner.next().unwrap();
+                    match day.as_rule() {
+                        Rule::weekday => {
+                            let weekday = calendar::WEEKDAYS.iter()
+                                .position(|w| *w == day.as_str())
+                                .unwrap();
+                            calendar.close_weekday(weekday);
+                        }
+                        Rule::date => {
+                            let from = Date::parse(day.as_str()).unwrap_or_else(|e| panic!("{}", e));
+                            let to = match inner.next() {
+                                Some(to) => Date::parse(to.as_str()).unwrap_or_else(|e| panic!("{}", e)),
+                                None => from
+                            };
+                            calendar.close_dates(from, to);
+                        }
+                        _ => unreachable!()
+                    }
+                }
+                Rule::TASK_STATEMENT => {
+                    let mut inner = inner_pair.into_inner();
+                    let name = task::task_name(inner.next().unwrap().as_str());
+                    let index = match tasks.iter().position(|t| *t.get_name() == name) {
+                        Some(index) => index,
+                        None => {
+                            tasks.push(Task::new(name));
+                            tasks.len() - 1
+                        }
+                    };
+                    for constraint in inner {
+                        tasks[index].apply(constraint);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let start = start.unwrap_or_else(|| panic!("The start of the project is missing"));
+        let mut gantt = Gantt {
+            start,
+            calendar,
+            tasks,
+            theme: Theme::default()
+        };
+        gantt.schedule();
+        let (first, last) = gantt.range();
+        let span = first.days_until(last);
+        if span > MAX_SPAN {
+            panic!("Chart too long: {} days", span);
+        }
+        gantt
+    }
+
+    pub fn get_start(&self) -> Date {self.start}
+
+    pub fn get_calendar(&self) -> &Calendar {&self.calendar}
+
+    pub fn get_tasks(&self) -> &Vec<Task> {&self.tasks}
+
+    fn index(&self, name: &String) -> usize {
+        self.tasks.iter()
+            .position(|t| t.get_name() == name)
+            .unwrap_or_else(|| panic!("Task not found: {}", name))
+    }
+
+    // Resolves the dates of every task, following the dependencies.
+    fn schedule(&mut self) {
+        let mut spans: Vec<Option<(Date, Date)>> = vec![None; self.tasks.len()];
+        let mut visiting = vec![false; self.tasks.len()];
+        for i in 0..self.tasks.len() {
+            self.resolve(i, &mut spans, &mut visiting);
+        }
+        for (task, span) in self.tasks.iter_mut().zip(spans) {
+            let (begin, end) = span.unwrap();
+            task.set_span(begin, end);
+        }
+    }
+
+    fn resolve(&self, i: usize, spans: &mut Vec<Option<(Date, Date)>>, visiting: &mut Vec<bool>) -> (Date, Date) {
+        if let Some(span) = spans[i] {
+            return span;
+        }
+        let task = &self.tasks[i];
+        if visiting[i] {
+            panic!("Circular dependency found: {}", task.get_name());
+        }
+        visiting[i] = true;
+
+        let anchor = match task.get_anchor() {
+            Anchor::Project => self.start,
+            Anchor::Date(date) => *date,
+            Anchor::Start(name) => self.resolve(self.index(name), spans, visiting).0,
+            Anchor::End(name) => self.resolve(self.index(name), spans, visiting).1
+        };
+        // milestones happen on the given day, even a closed one
+        let span = if task.is_milestone() {
+            (anchor, anchor)
+        } else {
+            let begin = self.calendar.next_open(anchor);
+            (begin, self.calendar.add_working_days(begin, task.get_duration()))
+        };
+
+        visiting[i] = false;
+        spans[i] = Some(span);
+        span
+    }
+
+    // First and last day (exclusive) shown on the axis.
+    fn range(&self) -> (Date, Date) {
+        let first = self.tasks.iter()
+            .map(|t| t.get_begin())
+            .fold(self.start, std::cmp::min);
+        let last = self.tasks.iter()
+            .map(|t| if t.is_milestone() { t.get_end().add_days(1) } else { t.get_end() })
+            .fold(first.add_days(1), std::cmp::max);
+        (first, last)
+    }
+
+    fn label_width(&self) -> i32 {
+        self.tasks.iter()
+            .map(|t| text_utils::text_width(t.get_name(), TEXT_SIZE) as i32 + 30)
+            .fold(120, std::cmp::max)
+    }
+
+    fn resources_label(task: &Task) -> String {
+        task.get_resources().join(", ")
+    }
+
+    fn resources_width(&self) -> i32 {
+        self.tasks.iter()
+            .map(Gantt::resources_label)
+            .filter(|r| !r.is_empty())
+            .map(|r| text_utils::text_width(&r, AXIS_TEXT_SIZE) as i32 + 20)
+            .fold(0, std::cmp::max)
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+    }
+
+    fn x_of(&self, date: Date) -> i32 {
+        MARGIN + self.label_width() + self.range().0.days_until(date) as i32 * DAY_WIDTH
+    }
+
+    fn row_center(&self, i: usize) -> i32 {
+        MARGIN + HEADER_HEIGHT + i as i32 * ROW_HEIGHT + ROW_HEIGHT / 2
+    }
+
+    fn text(&self, content: &str, (x, y): (i32, i32), size: i32, anchor: &str) -> TextElement {
+        TextElement::new()
+            .set("x", x)
+            .set("y", y)
+            .set("text-anchor", anchor)
+            .set("dominant-baseline", "central")
+            .set("fill", self.theme.font_color.as_str())
+            .set("font-size", size)
+            .add(svg::node::Text::new(text_utils::escape(content)))
+    }
+
+    fn draw_axis(&self, mut svg: SVG) -> SVG {
+        let (first, last) = self.range();
+        let top = MARGIN + HEADER_HEIGHT;
+        let bottom = top + self.tasks.len() as i32 * ROW_HEIGHT;
+
+        for offset in 0..first.days_until(last) {
+            let date = first.add_days(offset);
+            let x = self.x_of(date);
+            if self.calendar.is_closed(date) {
+                let closed = Rectangle::new()
+                    .set("x", x)
+                    .set("y", top)
+                    .set("width", DAY_WIDTH)
+                    .set("height", bottom - top)
+                    .set("fill", self.theme.line_color.as_str())
+                    .set("fill-opacity", 0.15);
+                svg = svg.add(closed);
+            }
+
+            let (year, month, day) = date.ymd();
+            if day == 1 || offset == 0 {
+                let month_label = format!("{} {}", calendar::MONTHS[month as usize - 1], year);
+                svg = svg.add(self.text(&month_label, (x + 4, MARGIN + 12), AXIS_TEXT_SIZE, "start"));
+                let separator = Line::new()
+                    .set("x1", x)
+                    .set("y1", MARGIN)
+                    .set("x2", x)
+                    .set("y2", bottom)
+                    .set("stroke", self.theme.line_color.as_str())
+                    .set("stroke-width", 1);
+                svg = svg.add(separator);
+            }
+            svg = svg.add(self.text(&day.to_string(), (x + DAY_WIDTH / 2, MARGIN + 37), AXIS_TEXT_SIZE, "middle"));
+        }
+
+        let axis = Line::new()
+            .set("x1", MARGIN)
+            .set("y1", top)
+            .set("x2", self.x_of(last))
+            .set("y2", top)
+            .set("stroke", self.theme.line_color.as_str())
+            .set("stroke-width", 2);
+        svg.add(axis)
+    }
+
+    fn draw_task(&self, mut svg: SVG, i: usize) -> SVG {
+        let task = &self.tasks[i];
+        let y = self.row_center(i);
+        svg = svg.add(self.text(task.get_name(), (MARGIN, y), TEXT_SIZE, "start"));
+
+        let right = if task.is_milestone() {
+            let x = self.x_of(task.get_begin());
+            let h = BAR_HEIGHT / 2;
+            let diamond = Polygon::new()
+                .set("points", format!("{},{} {},{} {},{} {},{}", x, y - h, x + h, y, x, y + h, x - h, y))
+                .set("fill", self.theme.line_color.as_str())
+                .set("stroke", self.theme.border_color.as_str())
+                .set("stroke-width", 2);
+            svg = svg.add(diamond);
+            x + h
+        } else {
+            let x = self.x_of(task.get_begin());
+            let width = self.x_of(task.get_end()) - x;
+            let bar = Rectangle::new()
+                .set("x", x)
+                .set("y", y - BAR_HEIGHT / 2)
+                .set("width", width)
+                .set("height", BAR_HEIGHT)
+                .set("rx", 4)
+                .set("fill", self.theme.fill_color.as_str())
+                .set("stroke", self.theme.border_color.as_str())
+                .set("stroke-width", 2);
+            svg = svg.add(bar);
+            x + width
+        };
+
+        let resources = Gantt::resources_label(task);
+        if !resources.is_empty() {
+            svg = svg.add(self.text(&resources, (right + 10, y), AXIS_TEXT_SIZE, "start"));
+        }
+        svg
+    }
+
+    // Goes down from the task it depends on and enters the dependent one from the left,
+    // milestones are entered from the top.
+    fn draw_dependency(&self, svg: SVG, from: usize, to: usize, at_end: bool) -> SVG {
+        let source = &self.tasks[from];
+        let target = &self.tasks[to];
+        let x = self.x_of(if at_end { source.get_end() } else { source.get_begin() });
+        let x2 = self.x_of(target.get_begin());
+        let y2 = self.row_center(to);
+
+        let (d, head) = if target.is_milestone() {
+            let (y1, tip) = (self.row_center(from) + BAR_HEIGHT / 2, y2 - BAR_HEIGHT / 2);
+            (format!("M {} {} H {} V {}", x, y1, x2, tip),
+                format!("{},{} {},{} {},{}", x2, tip, x2 - 4, tip - 8, x2 + 4, tip - 8))
+        } else {
+            let d = if at_end {
+                format!("M {} {} V {} H {}", x - 6, self.row_center(from) + BAR_HEIGHT / 2, y2, x2)
+            } else {
+                format!("M {} {} H {} V {} H {}", x, self.row_center(from), x - 8, y2, x2)
+            };
+            (d, format!("{},{} {},{} {},{}", x2, y2, x2 - 8, y2 - 4, x2 - 8, y2 + 4))
+        };
+        let path = Path::new()
+            .set("d", d)
+            .set("fill", "none")
+            .set("stroke", self.theme.line_color.as_str())
+            .set("stroke-width", 1);
+        let head = Polygon::new()
+            .set("points", head)
+            .set("fill", self.theme.line_color.as_str());
+        svg.add(path).add(head)
+    }
+
+    // The elements are added to one owned document, the axis has one for every day.
+    pub fn draw(&self, svg: &mut SVG) {
+        let mut document = self.draw_axis(std::mem::replace(svg, SVG::new()));
+
+        for (i, task) in self.tasks.iter().enumerate() {
+            document = match task.get_anchor() {
+                Anchor::Start(name) => self.draw_dependency(document, self.index(name), i, false),
+                Anchor::End(name) => self.draw_dependency(document, self.index(name), i, true),
+                _ => document
+            };
+        }
+        for i in 0..self.tasks.len() {
+            document = self.draw_task(document, i);
+        }
+        *svg = document;
+    }
+
+    pub fn width(&self) -> usize {
+        let (_, last) = self.range();
+        (self.x_of(last) + self.resources_width() + MARGIN) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (HEADER_HEIGHT + self.tasks.len() as i32 * ROW_HEIGHT + 2 * MARGIN) as usize
+    }
+}
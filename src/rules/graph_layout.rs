@@ -0,0 +1,141 @@
+// Layered graph layout: the nodes are placed in rows from top to bottom
+// so that most of the edges point downwards.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32
+}
+
+impl Rect {
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Rect {
+        Rect { x, y, width, height }
+    }
+
+    pub fn center(&self) -> (f64, f64) {
+        (self.x as f64 + self.width as f64 / 2.0, self.y as f64 + self.height as f64 / 2.0)
+    }
+
+    // Point where the line from the center towards the given point leaves the rectangle.
+    pub fn border_point(&self, toward: (f64, f64)) -> (f64, f64) {
+        let (cx, cy) = self.center();
+        let (dx, dy) = (toward.0 - cx, toward.1 - cy);
+        if dx == 0.0 && dy == 0.0 {
+            return (cx, cy);
+        }
+        let scale_x = if dx == 0.0 { f64::INFINITY } else { (self.width as f64 / 2.0) / dx.abs() };
+        let scale_y = if dy == 0.0 { f64::INFINITY } else { (self.height as f64 / 2.0) / dy.abs() };
+        let scale = f64::min(scale_x, scale_y);
+        (cx + dx * scale, cy + dy * scale)
+    }
+}
+
+pub struct GraphLayout {
+    // top left corners of the nodes
+    pub positions: Vec<(i32, i32)>,
+    pub layers: Vec<usize>,
+    pub width: i32,
+    pub height: i32
+}
+
+fn visit(node: usize, edges: &[(usize, usize)], marks: &mut [u8], back: &mut [bool]) {
+    marks[node] = 1;
+    for (i, (from, to)) in edges.iter().enumerate() {
+        if *from != node {
+            continue;
+        }
+        match marks[*to] {
+            0 => visit(*to, edges, marks, back),
+            1 => back[i] = true,
+            _ => {}
+        }
+    }
+    marks[node] = 2;
+}
+
+// Marks the edges closing a cycle, the search starts from the nodes in index order.
+pub fn back_edges(count: usize, edges: &[(usize, usize)]) -> Vec<bool> {
+    let mut marks = vec![0; count];
+    let mut back = vec![false; edges.len()];
+    for node in 0..count {
+        if marks[node] == 0 {
+            visit(node, edges, &mut marks, &mut back);
+        }
+    }
+    back
+}
+
+// sizes holds the width and height of every node, edges are pairs of node indices
+pub fn layout(sizes: &[(i32, i32)], edges: &[(usize, usize)], gap_x: i32, gap_y: i32) -> GraphLayout {
+    let count = sizes.len();
+    let back = back_edges(count, edges);
+    let forward: Vec<(usize, usize)> = edges.iter()
+        .zip(&back)
+        .filter(|((from, to), is_back)| !**is_back && from != to)
+        .map(|(edge, _)| *edge)
+        .collect();
+
+    // longest path layering, the forward edges form an acyclic graph so it terminates
+    let mut layers = vec![0; count];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (from, to) in &forward {
+            if layers[*to] < layers[*from] + 1 {
+                layers[*to] = layers[*from] + 1;
+                changed = true;
+            }
+        }
+    }
+
+    let layer_count = layers.iter().max().map(|l| l + 1).unwrap_or(0);
+    let mut rows: Vec<Vec<usize>> = vec![Vec::new(); layer_count];
+    for node in 0..count {
+        rows[layers[node]].push(node);
+    }
+
+    // order the nodes of every row by the average position of their predecessors
+    for row in 1..layer_count {
+        let previous = &rows[row - 1];
+        let mut keys: Vec<(f64, usize)> = rows[row].iter().enumerate().map(|(index, node)| {
+            let parents: Vec<usize> = forward.iter()
+                .filter(|(from, to)| to == node && layers[*from] == row - 1)
+                .filter_map(|(from, _)| previous.iter().position(|n| n == from))
+                .collect();
+            if parents.is_empty() {
+                (index as f64, *node)
+            } else {
+                (parents.iter().sum::<usize>() as f64 / parents.len() as f64, *node)
+            }
+        }).collect();
+        keys.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+        rows[row] = keys.into_iter().map(|(_, node)| node).collect();
+    }
+
+    let row_width = |row: &Vec<usize>| -> i32 {
+        row.iter().map(|n| sizes[*n].0).sum::<i32>() + gap_x * (row.len() as i32 - 1)
+    };
+    let width = rows.iter().map(row_width).max().unwrap_or(0);
+
+    let mut positions = vec![(0, 0); count];
+    let mut y = 0;
+    for row in &rows {
+        let row_height = row.iter().map(|n| sizes[*n].1).max().unwrap_or(0);
+        let mut x = (width - row_width(row)) / 2;
+        for node in row {
+            positions[*node] = (x, y + (row_height - sizes[*node].1) / 2);
+            x += sizes[*node].0 + gap_x;
+        }
+        y += row_height + gap_y;
+    }
+    let height = if layer_count == 0 { 0 } else { y - gap_y };
+
+    GraphLayout {
+        positions,
+        layers,
+        width,
+        height
+    }
+}
@@ -0,0 +1,85 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::graph_layout::Rect;
+use crate::rules::shapes;
+use crate::rules::text_utils;
+use crate::rules::theme::{self, Theme};
+use svg::node::element::SVG;
+
+const TEXT_SIZE: i32 = 18;
+const LABEL_MAX_WIDTH: f64 = 220.0;
+const PADDING: (i32, i32) = (15, 10);
+
+// Side of the root the branch grows to, '*' lets the layout decide.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Side {
+    Auto,
+    Left,
+    Right
+}
+
+// Node of the mind map.
+pub struct Idea {
+    label: String,
+    level: usize,
+    side: Side,
+    color: Option<String>,
+    theme: Theme
+}
+
+impl Idea {
+    pub fn new(value: Pair<Rule>) -> Idea {
+        let mut inner = value.into_inner();
+        let marker = inner.next().unwrap().as_str();
+        let side = match marker.chars().next().unwrap() {
+            '+' => Side::Right,
+            '-' => Side::Left,
+            _ => Side::Auto
+        };
+
+        let mut color = None;
+        let mut label = String::new();
+        for inner_pair in inner {
+            match inner_pair.as_rule() {
+                Rule::color => color = Some(theme::parse_color(inner_pair.as_str())),
+                Rule::idea_text => label = text_utils::unquote(inner_pair.as_str()),
+                _ => unreachable!()
+            }
+        }
+
+        Idea {
+            label,
+            level: marker.chars().count(),
+            side,
+            color,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_label(&self) -> &String {&self.label}
+    pub fn get_level(&self) -> usize {self.level}
+    pub fn get_side(&self) -> Side {self.side}
+    pub fn get_color(&self) -> &Option<String> {&self.color}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+    }
+
+    fn lines(&self) -> Vec<String> {
+        text_utils::wrap(&self.label, LABEL_MAX_WIDTH, TEXT_SIZE)
+    }
+
+    pub fn get_width(&self) -> i32 {
+        shapes::label_size(&self.lines(), TEXT_SIZE, PADDING).0
+    }
+
+    pub fn get_height(&self) -> i32 {
+        shapes::label_size(&self.lines(), TEXT_SIZE, PADDING).1
+    }
+
+    pub fn draw(&self, svg: &mut SVG, bounds: Rect) {
+        let fill = self.color.clone().unwrap_or(self.theme.fill_color.clone());
+        *svg = svg.clone().add(shapes::rounded_box(bounds, 10, &fill, &self.theme));
+        *svg = svg.clone().add(shapes::centered_label(&self.lines(), bounds.center(), TEXT_SIZE, &self.theme));
+    }
+}
@@ -1,12 +1,15 @@
 use pest::iterators::Pair;
 use crate::grammar_parser::Rule;
+use crate::rules::text_utils;
+use crate::rules::theme::Theme;
 use svg::node::{
     element::{
         SVG, Definitions, Line, Marker, Polygon, Text as TextElement
-    }, 
-    Text
+    }
 };
 
+const LABEL_MAX_WIDTH: f64 = 300.0;
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum ArrowType {
     Left,
@@ -14,12 +17,20 @@ pub enum ArrowType {
     Missing
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum Stereotype {
+    Include,
+    Extend,
+    Missing
+}
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum LinkType {
     SolidLine,
     DashedLine,
     SolidArrow,
-    DashedArrow
+    DashedArrow,
+    Generalization
 }
 
 pub struct Link {
@@ -27,30 +38,50 @@ pub struct Link {
     right_id: String,
     link_type: LinkType,
     label: String,
-    arrow: ArrowType
+    arrow: ArrowType,
+    stereotype: Stereotype,
+    theme: Theme
 }
 
 impl Link {
     pub fn new(value: Pair<Rule>) -> Link {
         let mut inner = value.into_inner();
-        let left_id = inner.next().unwrap().as_str().to_owned();
+        let mut left_id = inner.next().unwrap().as_str().to_owned();
   
         let link_rule = inner.next().unwrap().as_rule();
-        let link_type: LinkType = match link_rule {
+        let mut link_type: LinkType = match link_rule {
             Rule::solid_line => LinkType::SolidLine,
             Rule::dashed_line => LinkType::DashedLine,
             Rule::solid_arrow => LinkType::SolidArrow,
-            Rule::dashed_arrow => LinkType::DashedArrow,
+            Rule::dashed_arrow | Rule::dotted_arrow => LinkType::DashedArrow,
+            Rule::generalization_arrow | Rule::reverse_generalization_arrow => LinkType::Generalization,
             _ => unreachable!()
         };
 
-        let right_id = inner.next().unwrap().as_str().to_owned();
+        let mut right_id = inner.next().unwrap().as_str().to_owned();
+        // the specialized element is always on the left
+        if link_rule == Rule::reverse_generalization_arrow {
+            std::mem::swap(&mut left_id, &mut right_id);
+        }
 
         inner.next(); // skip colon
 
-        let label = match inner.next() {
+        let mut stereotype = Stereotype::Missing;
+        let mut next = inner.next();
+        if let Some(s) = next.clone().filter(|s| s.as_rule() == Rule::STEREOTYPE) {
+            stereotype = match s.into_inner().next().unwrap().as_str() {
+                "include" => Stereotype::Include,
+                "extend" => Stereotype::Extend,
+                _ => unreachable!()
+            };
+            // include and extend are dependencies whatever the arrow was
+            link_type = LinkType::DashedArrow;
+            next = inner.next();
+        }
+
+        let label = match next {
             Some(l) => {
-                l.as_str().to_owned().replace("\"", "")
+                text_utils::unquote(l.as_str())
             }
             None => String::new()
         };
@@ -71,7 +102,9 @@ impl Link {
             right_id,
             link_type,
             label,
-            arrow
+            arrow,
+            stereotype,
+            theme: Theme::default()
         }
     }
     
@@ -95,18 +128,32 @@ impl Link {
         &self.arrow
     }
 
+    pub fn get_stereotype(&self) -> &Stereotype {
+        &self.stereotype
+    }
+
+    // The extending use case points at its base, which is laid out first like the source of the other links.
+    pub fn get_layout_ids(&self) -> (&String, &String) {
+        match self.stereotype {
+            Stereotype::Extend => (&self.right_id, &self.left_id),
+            _ => (&self.left_id, &self.right_id)
+        }
+    }
+
     pub fn set_arrow(&mut self, arrow: ArrowType) { self.arrow = arrow; }
 
     pub fn set_link_type(&mut self, link_type: LinkType) { self.link_type = link_type; }
 
+    pub fn set_theme(&mut self, theme: &Theme) { self.theme = theme.clone(); }
+
     pub fn print(&self) {
-        tracing::info!("Left: {:?} Link: {:?} Right: {:?} Label: {:?} Arrow: {:?}",
-            self.left_id, self.link_type, self.right_id, self.label, self.arrow);
+        tracing::info!("Left: {:?} Link: {:?} Right: {:?} Label: {:?} Arrow: {:?} Stereotype: {:?}",
+            self.left_id, self.link_type, self.right_id, self.label, self.arrow, self.stereotype);
     }
 
     pub fn draw(&self, svg: &mut SVG, x1: i32, y1: i32, x2: i32, y2: i32) {
-        let line_weight = 3;
-        let text_size = line_weight * 6;
+        let line_weight = self.theme.line_width as i32;
+        let text_size = 18;
 
         let mut line = self.draw_line(x1, y1, x2, y2, line_weight);
         
@@ -118,67 +165,84 @@ impl Link {
         let angle = ((y2 - y1) as f32 / (x2 - x1) as f32).atan();
 
         if self.link_type == LinkType::SolidArrow || self.link_type == LinkType::DashedArrow{
-            let line_length = 30.0;
-            let angle_offset:f32 = 7.0;
-
-            let angle_left = angle + angle_offset.to_radians();
-            let angle_right = angle - angle_offset.to_radians();
-
-            let left_x1 = x2;
-            let left_y1 = y2;
-            let left_x2 = x2 - (line_length * angle_left.cos()) as i32;
-            let left_y2 = y2 - (line_length * angle_left.sin()) as i32;
+            let ((left_x2, left_y2), (right_x2, right_y2)) = arrowhead(x1, y1, x2, y2, 30.0, 7.0);
 
-            let right_x1 = x2;
-            let right_y1 = y2;
-            let right_x2 = x2 - (line_length * angle_right.cos()) as i32;
-            let right_y2 = y2 - (line_length * angle_right.sin()) as i32;
-
-            let line_left = self.draw_line(left_x1, left_y1, left_x2, left_y2, line_weight);
-            let line_right = self.draw_line(right_x1, right_y1, right_x2, right_y2, line_weight);
-            let line_base = self.draw_line(left_x2, left_y2, right_x2, right_y2, line_weight);
+            let line_left = self.draw_line(x2, y2, left_x2, left_y2, line_weight);
+            let line_right = self.draw_line(x2, y2, right_x2, right_y2, line_weight);
 
             *svg = svg.clone().add(line_left);
             *svg = svg.clone().add(line_right);
-            *svg = svg.clone().add(line_base);
+            // dependencies have an open arrowhead
+            if self.stereotype == Stereotype::Missing {
+                let line_base = self.draw_line(left_x2, left_y2, right_x2, right_y2, line_weight);
+                *svg = svg.clone().add(line_base);
+            }
         }
 
 
-        if !self.label.is_empty() {
+        let text = self.text();
+        if !text.is_empty() {
             // Calculate the center point of the line
             let center_x = (x1 + x2) / 2;
             let center_y = (y1 + y2) / 2;
 
-            let text = match self.arrow {
-                ArrowType::Left => {
-                    Text::new((self.label.clone() + "◀").as_str())
-                },
-                ArrowType::Right => {
-                    Text::new((self.label.clone() + "▶").as_str())
-                },
-                ArrowType::Missing => Text::new(self.label.as_str())
-            };
+            let lines = text_utils::wrap(&text, LABEL_MAX_WIDTH, text_size);
 
             // Create a text element
-            let text_element = TextElement::new()
+            let mut text_element = TextElement::new()
                 .set("x", center_x)
                 .set("y", center_y - 5)
                 .set("text-anchor", "middle")
                 .set("dominant-baseline", "central")
-                .set("fill", "black")
-                .set("font-family", "Arial")
+                .set("fill", self.theme.font_color.as_str())
                 .set("font-size", text_size)
                 .set("transform", 
-                    format!("rotate({} {} {})", angle.to_degrees(), center_x, center_y))
-                .add(text);
+                    format!("rotate({} {} {})", angle.to_degrees(), center_x, center_y));
+            // multi-line labels grow upwards so they don't cover the line
+            let lines_offset = text_utils::text_height(lines.len(), text_size) / 2.0 - text_utils::line_height(text_size) / 2.0;
+            text_element = text_utils::add_lines(text_element, &lines, center_x as f64, (center_y - 5) as f64 - lines_offset, text_size);
             
             *svg = svg.clone().add(text_element);
         }
 
         *svg = svg.clone().add(line);
+
+        if self.link_type == LinkType::Generalization {
+            self.draw_triangle(svg, x1, y1, x2, y2);
+        }
+    }
+
+    // Hollow triangle pointing at the general element, it covers the end of the line.
+    fn draw_triangle(&self, svg: &mut SVG, x1: i32, y1: i32, x2: i32, y2: i32) {
+        let ((left_x, left_y), (right_x, right_y)) = arrowhead(x1, y1, x2, y2, 25.0, 25.0);
+        let triangle = Polygon::new()
+            .set("points", format!("{},{} {},{} {},{}", x2, y2, left_x, left_y, right_x, right_y))
+            .set("fill", self.theme.background_color.as_str())
+            .set("stroke", self.theme.line_color.as_str())
+            .set("stroke-width", self.theme.line_width.to_string());
+        *svg = svg.clone().add(triangle);
     }
+    // The label with the direction marker, the stereotype goes on its own line above it.
+    fn text(&self) -> String {
+        let label = match self.arrow {
+            ArrowType::Left => self.label.clone() + "◀",
+            ArrowType::Right => self.label.clone() + "▶",
+            ArrowType::Missing => self.label.clone()
+        };
+        let stereotype = match self.stereotype {
+            Stereotype::Include => "«include»",
+            Stereotype::Extend => "«extend»",
+            Stereotype::Missing => return label
+        };
+        if label.is_empty() {
+            stereotype.to_string()
+        } else {
+            format!("{}\n{}", stereotype, label)
+        }
+    }
+
     pub fn draw_class_link(&mut self, svg: &mut SVG, x1: i32, y1: i32, x2: i32, y2: i32, xs: i32) {
-        let line_weight = 3; // thickness of the line
+        let line_weight = self.theme.line_width as i32; // thickness of the line
 
         if *self.get_link_type() == LinkType::DashedArrow {
             self.set_link_type(LinkType::DashedLine);
@@ -191,6 +255,11 @@ impl Link {
             self.draw(svg, xs, y1, xs, y2);
             self.set_link_type(LinkType::SolidArrow);
         }
+        else if *self.get_link_type() == LinkType::Generalization {
+            self.set_link_type(LinkType::SolidLine);
+            self.draw(svg, xs, y1, xs, y2);
+            self.set_link_type(LinkType::Generalization);
+        }
         else {
             self.draw(svg, xs, y1, xs, y2); // middle line
         }
@@ -215,6 +284,7 @@ impl Link {
             .add(
                 Polygon::new()
                     .set("points", "-10 -5.25, 0 1.75, -10 8.75")  // Adjust the points to fit the new dimensions
+                    .set("fill", self.theme.line_color.as_str())
             );
 
         if self.link_type == LinkType::SolidArrow || self.link_type == LinkType::DashedArrow{
@@ -225,6 +295,10 @@ impl Link {
 
         *svg = svg.clone().add(line1);
         *svg = svg.clone().add(line2);
+
+        if self.link_type == LinkType::Generalization {
+            self.draw_triangle(svg, xs, y2, x2, y2);
+        }
     }
 
     pub fn draw_line(&self, x1: i32, y1: i32, x2: i32, y2: i32, line_weight: i32) -> Line {
@@ -233,10 +307,25 @@ impl Link {
             .set("y1", y1.to_string())
             .set("x2", x2.to_string())
             .set("y2", y2.to_string())
-            .set("stroke", "#000")
+            .set("stroke", self.theme.line_color.as_str())
             .set("stroke-width", line_weight.to_string());
 
         return line;
     }
 
 }
+
+// The ends of the two sides of an arrowhead at (x2, y2), spread degrees away from the line.
+fn arrowhead(x1: i32, y1: i32, x2: i32, y2: i32, length: f32, spread: f32) -> ((i32, i32), (i32, i32)) {
+    let angle = ((y2 - y1) as f32 / (x2 - x1) as f32).atan();
+    // the angle alone doesn't tell the direction of lines going to the left
+    let length = if x2 < x1 { -length } else { length };
+
+    let angle_left = angle + spread.to_radians();
+    let angle_right = angle - spread.to_radians();
+
+    (
+        (x2 - (length * angle_left.cos()) as i32, y2 - (length * angle_left.sin()) as i32),
+        (x2 - (length * angle_right.cos()) as i32, y2 - (length * angle_right.sin()) as i32)
+    )
+}
@@ -0,0 +1,170 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::text_utils;
+use crate::rules::theme::Theme;
+use svg::node::element::{
+    Group, Line, Polygon, Polyline, Text as TextElement
+};
+
+pub const TEXT_SIZE: i32 = 18;
+pub const LABEL_MAX_WIDTH: f64 = 300.0;
+// width of the loop drawn for a message sent by a participant to itself
+pub const SELF_MESSAGE_WIDTH: i32 = 40;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum MessageType {
+    Synchronous,
+    Asynchronous,
+    Return
+}
+
+pub struct Message {
+    from: String,
+    to: String,
+    message_type: MessageType,
+    label: String
+}
+
+impl Message {
+    pub fn new(value: Pair<Rule>) -> Message {
+        let mut inner = value.into_inner();
+        let from = inner.next().unwrap().as_str().to_owned();
+
+        let message_type = match inner.next().unwrap().as_rule() {
+            Rule::solid_arrow => MessageType::Synchronous,
+            Rule::async_arrow => MessageType::Asynchronous,
+            Rule::dashed_arrow => MessageType::Return,
+            _ => unreachable!()
+        };
+
+        let to = inner.next().unwrap().as_str().to_owned();
+
+        inner.next(); // skip colon
+
+        let label = match inner.next() {
+            Some(l) => text_utils::unquote(l.as_str()),
+            None => String::new()
+        };
+
+        Message {
+            from,
+            to,
+            message_type,
+            label
+        }
+    }
+
+    pub fn get_from(&self) -> &String {&self.from}
+
+    pub fn get_to(&self) -> &String {&self.to}
+
+    pub fn get_message_type(&self) -> MessageType {self.message_type}
+
+    pub fn get_label(&self) -> &String {&self.label}
+
+    pub fn is_self_message(&self) -> bool {
+        self.from == self.to
+    }
+
+    fn lines(&self, number: Option<usize>) -> Vec<String> {
+        let label = match number {
+            Some(n) => format!("{} {}", n, self.label),
+            None => self.label.clone()
+        };
+        text_utils::wrap(label.trim(), LABEL_MAX_WIDTH, TEXT_SIZE)
+    }
+
+    pub fn label_width(&self) -> i32 {
+        text_utils::text_width(&self.lines(None).join("\n"), TEXT_SIZE) as i32
+    }
+
+    // Space needed above the arrow for the label.
+    pub fn label_height(&self) -> i32 {
+        if self.label.is_empty() {
+            return 0;
+        }
+        text_utils::text_height(self.lines(None).len(), TEXT_SIZE) as i32
+    }
+
+    // Vertical space taken by the message, the arrow is drawn at the bottom of it.
+    pub fn get_height(&self) -> i32 {
+        let loop_height = if self.is_self_message() { 25 } else { 0 };
+        30 + self.label_height() + loop_height
+    }
+
+    // Draws the arrow from x1 to x2 at y, self messages loop back to x2 below y.
+    pub fn draw(&self, group: &mut Group, x1: i32, x2: i32, y: i32, number: Option<usize>, theme: &Theme) {
+        let lines = self.lines(number);
+        let label_x;
+        let (end_x, end_y, direction);
+
+        if self.is_self_message() {
+            let loop_x = x1 + SELF_MESSAGE_WIDTH;
+            let points = format!("{},{} {},{} {},{} {},{}", x1, y, loop_x, y, loop_x, y + 25, x2, y + 25);
+            let mut path = Polyline::new()
+                .set("points", points)
+                .set("fill", "none")
+                .set("stroke", theme.line_color.as_str())
+                .set("stroke-width", 2);
+            if self.message_type == MessageType::Return {
+                path = path.set("stroke-dasharray", "8 8");
+            }
+            *group = group.clone().add(path);
+            label_x = x1 + 5;
+            end_x = x2;
+            end_y = y + 25;
+            direction = -1;
+        } else {
+            let mut line = Line::new()
+                .set("x1", x1)
+                .set("y1", y)
+                .set("x2", x2)
+                .set("y2", y)
+                .set("stroke", theme.line_color.as_str())
+                .set("stroke-width", 2);
+            if self.message_type == MessageType::Return {
+                line = line.set("stroke-dasharray", "8 8");
+            }
+            *group = group.clone().add(line);
+            label_x = (x1 + x2) / 2;
+            end_x = x2;
+            end_y = y;
+            direction = if x2 > x1 { 1 } else { -1 };
+        }
+
+        self.draw_arrowhead(group, end_x, end_y, direction, theme);
+
+        if !lines.iter().all(|l| l.is_empty()) {
+            let anchor = if self.is_self_message() { "start" } else { "middle" };
+            let text_y = y as f64 - 5.0 - text_utils::text_height(lines.len(), TEXT_SIZE) / 2.0;
+            let text = TextElement::new()
+                .set("x", label_x)
+                .set("y", text_y)
+                .set("text-anchor", anchor)
+                .set("dominant-baseline", "central")
+                .set("fill", theme.font_color.as_str())
+                .set("font-size", TEXT_SIZE);
+            *group = group.clone().add(text_utils::add_lines(text, &lines, label_x as f64, text_y, TEXT_SIZE));
+        }
+    }
+
+    // Synchronous messages get a filled arrowhead, the others an open one.
+    fn draw_arrowhead(&self, group: &mut Group, x: i32, y: i32, direction: i32, theme: &Theme) {
+        let back_x = x - direction * 12;
+        let points = format!("{},{} {},{} {},{}", back_x, y - 6, x, y, back_x, y + 6);
+        if self.message_type == MessageType::Synchronous {
+            let head = Polygon::new()
+                .set("points", points)
+                .set("fill", theme.line_color.as_str())
+                .set("stroke", theme.line_color.as_str());
+            *group = group.clone().add(head);
+        } else {
+            let head = Polyline::new()
+                .set("points", points)
+                .set("fill", "none")
+                .set("stroke", theme.line_color.as_str())
+                .set("stroke-width", 2);
+            *group = group.clone().add(head);
+        }
+    }
+}
@@ -0,0 +1,195 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::graph_layout::Rect;
+use crate::rules::idea::{Idea, Side};
+use crate::rules::theme::Theme;
+use svg::node::element::{SVG, Path};
+
+const MARGIN: i32 = 40;
+const GAP_X: i32 = 60;
+const GAP_Y: i32 = 20;
+
+// Tree of ideas, the branches of the root are balanced between its left and right side.
+pub struct Mindmap {
+    ideas: Vec<Idea>,
+    parents: Vec<Option<usize>>,
+    sides: Vec<Side>,
+    rects: Vec<Rect>,
+    width: i32,
+    height: i32,
+    theme: Theme
+}
+
+impl Mindmap {
+    // Accepts the MINDMAP_DIAGRAM pair, the level of an idea is the number of its markers.
+    pub fn new(value: Pair<Rule>) -> Mindmap {
+        let ideas: Vec<Idea> = value.into_inner()
+            .filter(|p| p.as_rule() == Rule::IDEA)
+            .map(Idea::new)
+            .collect();
+
+        // the last idea seen on every level
+        let mut path: Vec<usize> = Vec::new();
+        let mut parents = Vec::new();
+        for (i, idea) in ideas.iter().enumerate() {
+            let level = idea.get_level();
+            if level == 1 && i > 0 {
+                panic!("Mind map can have only one root: {}", idea.get_label());
+            }
+            if level > path.len() + 1 {
+                panic!("Idea {} is nested too deep", idea.get_label());
+            }
+            path.truncate(level - 1);
+            parents.push(path.last().copied());
+            path.push(i);
+        }
+
+        let mut mindmap = Mindmap {
+            sides: vec![Side::Right; ideas.len()],
+            rects: vec![Rect::new(0, 0, 0, 0); ideas.len()],
+            ideas,
+            parents,
+            width: 0,
+            height: 0,
+            theme: Theme::default()
+        };
+        mindmap.layout();
+        mindmap
+    }
+
+    pub fn get_ideas(&self) -> &Vec<Idea> {&self.ideas}
+
+    pub fn get_parent(&self, i: usize) -> Option<usize> {self.parents[i]}
+
+    // The side of the root the idea ends up on.
+    pub fn get_side(&self, i: usize) -> Side {self.sides[i]}
+
+    pub fn get_rects(&self) -> &Vec<Rect> {&self.rects}
+
+    fn children(&self, i: usize) -> Vec<usize> {
+        (0..self.ideas.len()).filter(|c| self.parents[*c] == Some(i)).collect()
+    }
+
+    // Height of the idea together with all of its descendants.
+    fn subtree_height(&self, i: usize) -> i32 {
+        let children = self.children(i);
+        let block = self.block_height(&children);
+        std::cmp::max(self.ideas[i].get_height(), block)
+    }
+
+    fn block_height(&self, ideas: &[usize]) -> i32 {
+        let heights: i32 = ideas.iter().map(|c| self.subtree_height(*c)).sum();
+        heights + GAP_Y * (ideas.len() as i32 - 1).max(0)
+    }
+
+    // Puts the branches marked with '+' or '-' on their side, then the others on the lower side.
+    fn balance(&mut self, branches: &[usize]) -> (Vec<usize>, Vec<usize>) {
+        let (mut left, mut right) = (Vec::new(), Vec::new());
+        for branch in branches {
+            match self.ideas[*branch].get_side() {
+                Side::Left => left.push(*branch),
+                Side::Right => right.push(*branch),
+                Side::Auto => {}
+            }
+        }
+        for branch in branches {
+            if self.ideas[*branch].get_side() != Side::Auto {
+                continue;
+            }
+            if self.block_height(&right) <= self.block_height(&left) {
+                right.push(*branch);
+            } else {
+                left.push(*branch);
+            }
+        }
+        // keep the order of the source within each side
+        left.sort();
+        right.sort();
+        (left, right)
+    }
+
+    // Stacks the ideas from top, edge is the x next to the parent the ideas are attached to.
+    fn place(&mut self, ideas: &[usize], edge: i32, top: i32, side: Side) {
+        let mut y = top;
+        for i in ideas {
+            let (width, height) = (self.ideas[*i].get_width(), self.ideas[*i].get_height());
+            let subtree = self.subtree_height(*i);
+            let x = if side == Side::Left { edge - width } else { edge };
+            self.rects[*i] = Rect::new(x, y + (subtree - height) / 2, width, height);
+            self.sides[*i] = side;
+
+            let children = self.children(*i);
+            let child_edge = if side == Side::Left { x - GAP_X } else { x + width + GAP_X };
+            let child_top = y + (subtree - self.block_height(&children)) / 2;
+            self.place(&children, child_edge, child_top, side);
+            y += subtree + GAP_Y;
+        }
+    }
+
+    fn layout(&mut self) {
+        if self.ideas.is_empty() {
+            return;
+        }
+        let root = Rect::new(-self.ideas[0].get_width() / 2, -self.ideas[0].get_height() / 2,
+            self.ideas[0].get_width(), self.ideas[0].get_height());
+        self.rects[0] = root;
+
+        let branches = self.children(0);
+        let (left, right) = self.balance(&branches);
+        let right_top = -self.block_height(&right) / 2;
+        self.place(&right, root.x + root.width + GAP_X, right_top, Side::Right);
+        let left_top = -self.block_height(&left) / 2;
+        self.place(&left, root.x - GAP_X, left_top, Side::Left);
+
+        let min_x = self.rects.iter().map(|r| r.x).min().unwrap();
+        let min_y = self.rects.iter().map(|r| r.y).min().unwrap();
+        for rect in &mut self.rects {
+            rect.x -= min_x;
+            rect.y -= min_y;
+        }
+        self.width = self.rects.iter().map(|r| r.x + r.width).max().unwrap();
+        self.height = self.rects.iter().map(|r| r.y + r.height).max().unwrap();
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        for idea in &mut self.ideas {
+            idea.set_theme(theme);
+        }
+    }
+
+    pub fn draw(&self, svg: &mut SVG) {
+        let offset = |r: Rect| Rect::new(r.x + MARGIN, r.y + MARGIN, r.width, r.height);
+
+        // the branches leave the side of the parent and bend into the side of the child
+        for (i, parent) in self.parents.iter().enumerate() {
+            let Some(parent) = parent else { continue };
+            let (from, to) = (offset(self.rects[*parent]), offset(self.rects[i]));
+            let (x1, x2) = if self.sides[i] == Side::Left {
+                (from.x, to.x + to.width)
+            } else {
+                (from.x + from.width, to.x)
+            };
+            let (y1, y2) = (from.center().1, to.center().1);
+            let middle = (x1 + x2) as f64 / 2.0;
+            let branch = Path::new()
+                .set("d", format!("M {} {} C {} {} {} {} {} {}", x1, y1, middle, y1, middle, y2, x2, y2))
+                .set("fill", "none")
+                .set("stroke", self.theme.line_color.as_str())
+                .set("stroke-width", 2);
+            *svg = svg.clone().add(branch);
+        }
+
+        for (idea, rect) in self.ideas.iter().zip(&self.rects) {
+            idea.draw(svg, offset(*rect));
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.width + 2 * MARGIN) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.height + 2 * MARGIN) as usize
+    }
+}
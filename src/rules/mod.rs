@@ -2,8 +2,37 @@ pub mod link;
 pub mod actor;
 pub mod context;
 pub mod use_case;
+pub mod use_case_diagram;
 pub mod structs;
 pub mod activity;
 pub mod node;
 pub mod activity_utils;
 pub mod path;
+pub mod text_utils;
+pub mod theme;
+pub mod participant;
+pub mod message;
+pub mod fragment;
+pub mod sequence;
+pub mod graph_layout;
+pub mod transition;
+pub mod state;
+pub mod state_machine;
+pub mod component;
+pub mod subsystem;
+pub mod component_diagram;
+pub mod deployment_node;
+pub mod deployment;
+pub mod object;
+pub mod object_diagram;
+pub mod entity;
+pub mod relationship;
+pub mod er_diagram;
+pub mod calendar;
+pub mod task;
+pub mod gantt;
+pub mod shapes;
+pub mod idea;
+pub mod mindmap;
+pub mod signal;
+pub mod timing;
@@ -1,18 +1,23 @@
 use crate::grammar_parser::Rule;
 use activity_utils::Type;
-use crate::rules::activity_utils;
+use crate::rules::{activity_utils, shapes, text_utils};
+use crate::rules::graph_layout::Rect;
+use crate::rules::theme::Theme;
 use pest::iterators::Pair;
-use svg::node::{
-    element::{
-        SVG, Rectangle, Text as TextElement, Circle
-    },
-    Text
+use svg::node::element::{
+    SVG, Circle, Line, Polygon, Text as TextElement
 };
 
+const TEXT_SIZE: i32 = 28;
+const NAME_MAX_WIDTH: f64 = 400.0;
+const PADDING: usize = 20;
+
 pub struct Node {
     kind: Type,
     name: String,
-    arrow_label: String
+    arrow_label: String,
+    // label the flow can jump to with goto
+    alias: Option<String>
 }
 
 impl Node {
@@ -20,6 +25,7 @@ impl Node {
         let kind;
         let name;
         let mut arrow_label = "".to_string();
+        let mut alias = None;
 
         match value.as_rule() {
             Rule::start_state => {
@@ -27,16 +33,53 @@ impl Node {
                 name = "".to_string();
             }
             Rule::END_STATE => {
-                kind = Type::END;
+                kind = match value.into_inner().last().map(|p| p.as_rule()) {
+                    Some(Rule::end_kw) => Type::FLOWEND,
+                    Some(Rule::kill_kw) => Type::KILL,
+                    Some(Rule::detach_kw) => Type::DETACH,
+                    _ => Type::END
+                };
                 name = "".to_string();
             }
             Rule::ACTIVITY => {
                 kind = Type::STEP;
                 let mut inner = value.into_inner();
                 inner.next(); // skip arrow
-                name = inner.next().unwrap().as_str().to_owned();
+                let n = inner.next().unwrap();
+                if n.as_rule() == Rule::ALIAS {
+                    let mut inner2 = n.into_inner();
+                    name = text_utils::unquote(inner2.next().unwrap().as_str());
+
+                    inner2.next(); // skip 'as'
+                    alias = Some(inner2.next().unwrap().as_str().to_owned());
+                } else {
+                    name = text_utils::unquote(n.as_str());
+                }
                 if inner.next() != None {
-                    arrow_label = inner.next().unwrap().as_str().to_owned();
+                    arrow_label = text_utils::unquote(inner.next().unwrap().as_str());
+                }
+            }
+            // the node only marks where the flow jumps from, the target is its name
+            Rule::GOTO => {
+                kind = Type::GOTO;
+                name = value.into_inner().last().unwrap().as_str().to_owned();
+            }
+            Rule::EVENT => {
+                let mut inner = value.into_inner();
+                inner.next(); // skip arrow
+                let event = inner.next().unwrap();
+                kind = match event.as_rule() {
+                    Rule::OBJECT_NODE => Type::OBJECT,
+                    Rule::CONNECTOR => Type::CONNECTOR,
+                    _ => match event.clone().into_inner().next().unwrap().as_rule() {
+                        Rule::send_kw => Type::SEND,
+                        Rule::receive_kw => Type::RECEIVE,
+                        _ => Type::TIMER
+                    }
+                };
+                name = text_utils::unquote(event.into_inner().last().unwrap().as_str());
+                if inner.next().is_some() {
+                    arrow_label = text_utils::unquote(inner.next().unwrap().as_str());
                 }
             }
             _ => unreachable!()
@@ -45,15 +88,36 @@ impl Node {
         Node {
             kind,
             name,
-            arrow_label
+            arrow_label,
+            alias
         }
     }
 
-    pub fn if_node(name: String) -> Node {
+    pub fn if_node() -> Node {
         Node {
             kind: Type::IF,
-            name,
-            arrow_label: "".to_string()
+            name: "".to_string(),
+            arrow_label: "".to_string(),
+            alias: None
+        }
+    }
+
+    // Decision of a loop, the guard is drawn on the arrow going back.
+    pub fn loop_node(guard: String) -> Node {
+        Node {
+            kind: Type::LOOP,
+            name: guard,
+            arrow_label: "".to_string(),
+            alias: None
+        }
+    }
+
+    pub fn fork_node() -> Node {
+        Node {
+            kind: Type::FORK,
+            name: "".to_string(),
+            arrow_label: "".to_string(),
+            alias: None
         }
     }
 
@@ -61,7 +125,8 @@ impl Node {
         Node {
             kind: Type::START,
             name: "".to_string(),
-            arrow_label: "".to_string()
+            arrow_label: "".to_string(),
+            alias: None
         }
     }
 
@@ -69,63 +134,28 @@ impl Node {
         tracing::info!("Activity")
     }
 
-    pub fn draw(&self, x: usize, y: usize, svg: &mut SVG) {
-        let width = self.name.len()*16;
+    pub fn draw(&self, x: usize, y: usize, svg: &mut SVG, theme: &Theme) {
+        let lines = self.lines();
+        let width = self.get_width();
+        let extra_height = self.extra_height();
         match self.kind {
-            Type::IF => {
-                let name = Text::new(self.name.as_str());
-
-                let step = Rectangle::new()
-                    .set("x", x-25)
-                    .set("y", y-25)
-                    .set("width", (50.0*std::f64::consts::FRAC_1_SQRT_2) as usize)
-                    .set("height", (50.0*std::f64::consts::FRAC_1_SQRT_2) as usize)
-                    .set("fill", "white")
-                    .set("stroke", "black")
-                    .set("stroke-width", 3)
-                    .set("transform", format!("rotate({} {} {})", 45, x, y));
-                *svg = svg.clone().add(step);
-
-                let caption = TextElement::new()
-                    .set("x", x)
-                    .set("y", y+39)
-                    .set("text-anchor", "middle")
-                    .set("dominant-baseline", "central")
-                    .set("fill", "black")
-                    .set("font-size", 28)
-                    .add(name);
-                *svg = svg.clone().add(caption);
+            // the guards are written on the edges leaving the diamond
+            Type::IF | Type::LOOP => {
+                Node::draw_diamond(x, y, svg, theme);
             }
+            // the bars are as wide as all the lanes, the fork draws them
+            Type::FORK => {}
             Type::STEP => {
-                let name = Text::new(self.name.as_str());
-
-                let step = Rectangle::new()
-                    .set("x", x-width/2)
-                    .set("y", y-32)
-                    .set("width", width)
-                    .set("height", 50)
-                    .set("fill", "white")
-                    .set("stroke", "black")
-                    .set("stroke-width", 3)
-                    .set("rx", 15);
-                *svg = svg.clone().add(step);
-
-                let caption = TextElement::new()
-                    .set("x", x)
-                    .set("y", y)
-                    .set("text-anchor", "middle")
-                    .set("dominant-baseline", "central")
-                    .set("fill", "black")
-                    .set("font-size", 28)
-                    .add(name);
-                *svg = svg.clone().add(caption);
+                let bounds = Rect::new((x-width/2) as i32, (y-32-extra_height/2) as i32, width as i32, (50+extra_height) as i32);
+                *svg = svg.clone().add(shapes::rounded_box(bounds, 15, theme.fill_color.as_str(), theme));
+                *svg = svg.clone().add(shapes::centered_label(&lines, (x as f64, y as f64), TEXT_SIZE, theme));
             }
             Type::END => {
                 let end = Circle::new()
                     .set("cx", x)
                     .set("cy", y)
                     .set("r", 25)
-                    .set("stroke", "black")
+                    .set("stroke", theme.line_color.as_str())
                     .set("stroke-width", 2)
                     .set("fill", "none");
                 *svg = svg.clone().add(end);
@@ -133,19 +163,152 @@ impl Node {
                 let center = Circle::new()
                     .set("cx", x)
                     .set("cy", y)
-                    .set("r", 20);
+                    .set("r", 20)
+                    .set("fill", theme.line_color.as_str());
                 *svg = svg.clone().add(center);
             }
+            // pentagon pointing to where the signal goes
+            Type::SEND => {
+                let (top, bottom) = (y-32-extra_height/2, y+18+extra_height/2);
+                let (left, right) = (x-width/2, x+width/2);
+                let points = [(left, top), (right-20, top), (right, (top+bottom)/2), (right-20, bottom), (left, bottom)];
+                Node::draw_polygon(&points, svg, theme);
+                *svg = svg.clone().add(shapes::centered_label(&lines, ((x-10) as f64, y as f64), TEXT_SIZE, theme));
+            }
+            // pentagon with the notch the signal comes in through
+            Type::RECEIVE => {
+                let (top, bottom) = (y-32-extra_height/2, y+18+extra_height/2);
+                let (left, right) = (x-width/2, x+width/2);
+                let points = [(left, top), (right, top), (right, bottom), (left, bottom), (left+20, (top+bottom)/2)];
+                Node::draw_polygon(&points, svg, theme);
+                *svg = svg.clone().add(shapes::centered_label(&lines, ((x+10) as f64, y as f64), TEXT_SIZE, theme));
+            }
+            Type::OBJECT => {
+                let bounds = Rect::new((x-width/2) as i32, (y-32-extra_height/2) as i32, width as i32, (50+extra_height) as i32);
+                *svg = svg.clone().add(shapes::rounded_box(bounds, 0, theme.fill_color.as_str(), theme));
+                *svg = svg.clone().add(shapes::centered_label(&lines, (x as f64, y as f64), TEXT_SIZE, theme));
+            }
+            // hourglass with the time written next to it
+            Type::TIMER => {
+                let points = [(x-15, y-25), (x+15, y-25), (x-15, y+25), (x+15, y+25)];
+                Node::draw_polygon(&points, svg, theme);
+                let caption = TextElement::new()
+                    .set("x", x+30)
+                    .set("y", y)
+                    .set("text-anchor", "start")
+                    .set("dominant-baseline", "central")
+                    .set("fill", theme.font_color.as_str())
+                    .set("font-size", TEXT_SIZE)
+                    .add(svg::node::Text::new(text_utils::escape(&self.name)));
+                *svg = svg.clone().add(caption);
+            }
+            // the flow goes on from the connector of the same name
+            Type::CONNECTOR => {
+                let connector = Circle::new()
+                    .set("cx", x)
+                    .set("cy", y)
+                    .set("r", 25)
+                    .set("stroke", theme.line_color.as_str())
+                    .set("stroke-width", 2)
+                    .set("fill", theme.fill_color.as_str());
+                *svg = svg.clone().add(connector);
+                *svg = svg.clone().add(shapes::centered_label(&lines, (x as f64, y as f64), TEXT_SIZE, theme));
+            }
+            // circle crossed out, only the flow which reaches it ends
+            Type::FLOWEND => {
+                let end = Circle::new()
+                    .set("cx", x)
+                    .set("cy", y)
+                    .set("r", 25)
+                    .set("stroke", theme.line_color.as_str())
+                    .set("stroke-width", 2)
+                    .set("fill", theme.fill_color.as_str());
+                *svg = svg.clone().add(end);
+                Node::draw_cross(x, y, 17, svg, theme);
+            }
+            Type::KILL => {
+                Node::draw_cross(x, y, 20, svg, theme);
+            }
+            // the arrow just stops
+            Type::DETACH => {}
+            // the jump is drawn when the whole flow is in place
+            Type::GOTO => {}
             Type::START => {
                 let start = Circle::new()
                     .set("cx", x)
                     .set("cy", y)
-                    .set("r", 25);
+                    .set("r", 25)
+                    .set("fill", theme.line_color.as_str());
                 *svg = svg.clone().add(start);
             }
         }
     }
 
+    fn draw_polygon(points: &[(usize, usize)], svg: &mut SVG, theme: &Theme) {
+        let polygon = Polygon::new()
+            .set("points", points.iter().map(|(x, y)| format!("{},{}", x, y)).collect::<Vec<_>>().join(" "))
+            .set("fill", theme.fill_color.as_str())
+            .set("stroke", theme.border_color.as_str())
+            .set("stroke-width", theme.line_width);
+        *svg = svg.clone().add(polygon);
+    }
+
+    fn draw_cross(x: usize, y: usize, size: usize, svg: &mut SVG, theme: &Theme) {
+        for (x1, x2) in [(x-size, x+size), (x+size, x-size)] {
+            let line = Line::new()
+                .set("x1", x1)
+                .set("y1", y-size)
+                .set("x2", x2)
+                .set("y2", y+size)
+                .set("stroke", theme.line_color.as_str())
+                .set("stroke-width", 4);
+            *svg = svg.clone().add(line);
+        }
+    }
+
+    fn draw_diamond(x: usize, y: usize, svg: &mut SVG, theme: &Theme) {
+        let points = [(x, y-25), (x+25, y), (x, y+25), (x-25, y)];
+        Node::draw_polygon(&points, svg, theme);
+    }
+
+    fn lines(&self) -> Vec<String> {
+        text_utils::wrap(&self.name, NAME_MAX_WIDTH, TEXT_SIZE)
+    }
+
+    pub fn get_width(&self) -> usize {
+        let text = text_utils::text_width(&self.lines().join("\n"), TEXT_SIZE) as usize;
+        match self.kind {
+            Type::STEP | Type::OBJECT => text+2*PADDING,
+            Type::SEND | Type::RECEIVE => text+2*PADDING+20,
+            Type::IF | Type::LOOP => 50,
+            Type::TIMER => 30,
+            Type::CONNECTOR => 50,
+            _ => text
+        }
+    }
+
+    // How far the node reaches to the left and to the right of its column.
+    pub fn extents(&self) -> (usize, usize) {
+        match self.kind {
+            Type::TIMER => (15, 30+text_utils::text_width(&self.name, TEXT_SIZE) as usize),
+            Type::START | Type::END | Type::FLOWEND | Type::KILL => (25, 25),
+            // the blocks are measured by themselves
+            Type::LOOP | Type::FORK | Type::GOTO | Type::DETACH => (0, 0),
+            _ => {
+                let width = self.get_width();
+                (width/2, width-width/2)
+            }
+        }
+    }
+
+    // additional height of a box with a name wrapped into more than one line
+    pub fn extra_height(&self) -> usize {
+        if !matches!(self.kind, Type::STEP | Type::SEND | Type::RECEIVE | Type::OBJECT) {
+            return 0;
+        }
+        ((self.lines().len()-1) as f64 * text_utils::line_height(TEXT_SIZE)) as usize
+    }
+
     pub fn get_kind(&self) -> Type {
         self.kind.clone()
     }
@@ -154,6 +317,10 @@ impl Node {
         self.name.clone()
     }
 
+    pub fn get_alias(&self) -> &Option<String> {
+        &self.alias
+    }
+
     pub fn get_arrow_label(&self) -> String {
         self.arrow_label.clone()
     }
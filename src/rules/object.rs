@@ -0,0 +1,159 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::graph_layout::Rect;
+use crate::rules::structs::{self, Class};
+use crate::rules::text_utils;
+use crate::rules::theme::{self, Theme};
+use svg;
+
+pub struct Slot {
+    name: String,
+    value: String
+}
+
+impl Slot {
+    pub fn new(value: Pair<Rule>) -> Slot {
+        let mut inner = value.into_inner();
+        let name = inner.next().unwrap().as_str().to_owned();
+        let value = inner.next().unwrap().as_str().trim().to_owned();
+
+        Slot {
+            name,
+            value
+        }
+    }
+
+    pub fn get_name(&self) -> &String {&self.name}
+    pub fn get_value(&self) -> &String {&self.value}
+}
+
+// Instance of a class, drawn like a class box with the values of its attributes.
+pub struct Object {
+    name: String,
+    class: String,
+    slots: Vec<Slot>,
+    color: Option<String>,
+    theme: Theme
+}
+
+impl Object {
+    pub fn new(value: Pair<Rule>) -> Object {
+        let mut inner = value.into_inner();
+        let name = inner.next().unwrap().as_str().to_owned();
+        let mut class = String::new();
+        let mut slots = Vec::new();
+        let mut color = None;
+
+        for inner_pair in inner {
+            match inner_pair.as_rule() {
+                Rule::colon => {}
+                Rule::identifier => class = inner_pair.as_str().to_owned(),
+                Rule::color => color = Some(theme::parse_color(inner_pair.as_str())),
+                Rule::SLOT | Rule::INLINE_SLOT => slots.push(Slot::new(inner_pair)),
+                _ => unreachable!()
+            }
+        }
+
+        Object {
+            name,
+            class,
+            slots,
+            color,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_name(&self) -> &String {&self.name}
+    pub fn get_class(&self) -> &String {&self.class}
+    pub fn get_slots(&self) -> &Vec<Slot> {&self.slots}
+    pub fn get_color(&self) -> &Option<String> {&self.color}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+    }
+
+    // Slots must name the attributes of the class, when the class is known.
+    pub fn check_slots(&self, classes: &[Class]) -> Result<(), String> {
+        let class = match classes.iter().find(|c| *c.get_name() == self.class) {
+            Some(class) => class,
+            None => return Ok(())
+        };
+        for slot in &self.slots {
+            if !class.get_attributes().iter().any(|a| a.get_name() == slot.get_name()) {
+                return Err(format!("Unknown attribute {} of class {} in object {}", slot.get_name(), self.class, self.name));
+            }
+        }
+        Ok(())
+    }
+
+    fn header(&self) -> String {
+        if self.class.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{} : {}", self.name, self.class)
+        }
+    }
+
+    fn slot_texts(&self) -> Vec<String> {
+        self.slots.iter()
+            .map(|s| format!("{} = {}", s.get_name(), s.get_value()))
+            .collect()
+    }
+
+    pub fn get_width(&self) -> i32 {
+        let longest = self.slot_texts().iter()
+            .chain(std::iter::once(&self.header()))
+            .map(|t| t.chars().count())
+            .max()
+            .unwrap_or(0);
+        std::cmp::max(longest * 15 + 30, 200) as i32
+    }
+
+    pub fn get_height(&self) -> i32 {
+        (self.slots.len() as i32 + 1) * 50
+    }
+
+    pub fn draw(&self, svg: &mut svg::node::element::SVG, x: i32, y: i32) -> Rect {
+        let width = self.get_width();
+        let height = self.get_height();
+
+        let rect = svg::node::element::Rectangle::new()
+            .set("x", x)
+            .set("y", y)
+            .set("width", width)
+            .set("height", height)
+            .set("fill", self.color.clone().unwrap_or(self.theme.fill_color.clone()))
+            .set("stroke", self.theme.border_color.as_str())
+            .set("stroke-width", self.theme.class_border_width);
+        *svg = svg.clone().add(rect);
+
+        let header = svg::node::element::Text::new()
+            .set("x", x + width / 2)
+            .set("y", y + 25)
+            .set("text-anchor", "middle")
+            .set("dominant-baseline", "central")
+            .set("fill", self.theme.font_color.as_str())
+            .set("font-size", 28)
+            .set("text-decoration", "underline")
+            .add(svg::node::Text::new(text_utils::escape(&self.header())));
+        *svg = svg.clone().add(header);
+
+        if !self.slots.is_empty() {
+            let line = svg::node::element::Line::new()
+                .set("x1", x)
+                .set("y1", y + 50)
+                .set("x2", x + width)
+                .set("y2", y + 50)
+                .set("stroke", self.theme.line_color.as_str())
+                .set("stroke-width", 5);
+            *svg = svg.clone().add(line);
+        }
+
+        for (i, content) in self.slot_texts().iter().enumerate() {
+            let text = svg::node::Text::new(text_utils::escape(content));
+            *svg = svg.clone().add(structs::add_row(text, i, x as usize, y as usize + 75, &self.theme));
+        }
+
+        Rect::new(x, y, width, height)
+    }
+}
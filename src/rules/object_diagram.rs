@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::graph_layout::{self, Rect};
+use crate::rules::link::Link;
+use crate::rules::object::Object;
+use crate::rules::structs::Class;
+use crate::rules::theme::Theme;
+use svg::node::element::SVG;
+
+const MARGIN: i32 = 40;
+const GAP: i32 = 120;
+
+pub struct ObjectDiagram {
+    objects: Vec<Object>,
+    links: Vec<Link>,
+    positions: Vec<(i32, i32)>,
+    width: i32,
+    height: i32,
+    theme: Theme
+}
+
+impl ObjectDiagram {
+    // Accepts the OBJECT_DIAGRAM pair, the classes are only used to check the slots of the objects.
+    pub fn new(value: Pair<Rule>) -> ObjectDiagram {
+        let mut classes = Vec::new();
+        let mut objects: Vec<Object> = Vec::new();
+        let mut links = Vec::new();
+
+        for inner_pair in value.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::CLASS => classes.push(Class::new(inner_pair, false)),
+                Rule::INTERFACE => classes.push(Class::new(inner_pair, true)),
+                Rule::OBJECT => {
+                    let object = Object::new(inner_pair);
+                    if objects.iter().any(|o| o.get_name() == object.get_name()) {
+                        panic!("Duplicate name/alias found: {}", object.get_name());
+                    }
+                    objects.push(object);
+                }
+                Rule::LINK => links.push(Link::new(inner_pair)),
+                _ => {}
+            }
+        }
+
+        for object in &objects {
+            object.check_slots(&classes).unwrap_or_else(|e| panic!("{}", e));
+        }
+
+        let index = |id: &String| objects.iter()
+            .position(|o| o.get_name() == id)
+            .unwrap_or_else(|| panic!("Alias not found: {}", id));
+        let edges: Vec<(usize, usize)> = links.iter()
+            .map(|l: &Link| (index(l.get_left_id()), index(l.get_right_id())))
+            .collect();
+        let sizes: Vec<(i32, i32)> = objects.iter()
+            .map(|o| (o.get_width(), o.get_height()))
+            .collect();
+        let layout = graph_layout::layout(&sizes, &edges, GAP, GAP);
+
+        ObjectDiagram {
+            objects,
+            links,
+            positions: layout.positions,
+            width: layout.width,
+            height: layout.height,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_objects(&self) -> &Vec<Object> {&self.objects}
+
+    pub fn get_links(&self) -> &Vec<Link> {&self.links}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        for object in &mut self.objects {
+            object.set_theme(theme);
+        }
+        for link in &mut self.links {
+            link.set_theme(theme);
+        }
+    }
+
+    pub fn draw(&self, svg: &mut SVG) {
+        let mut rects: HashMap<&String, Rect> = HashMap::new();
+        for (object, (x, y)) in self.objects.iter().zip(&self.positions) {
+            rects.insert(object.get_name(), object.draw(svg, MARGIN + x, MARGIN + y));
+        }
+
+        for link in &self.links {
+            let from = rects[link.get_left_id()];
+            let to = rects[link.get_right_id()];
+            let (x1, y1) = from.border_point(to.center());
+            let (x2, y2) = to.border_point(from.center());
+            link.draw(svg, x1 as i32, y1 as i32, x2 as i32, y2 as i32);
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.width + 2 * MARGIN) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.height + 2 * MARGIN) as usize
+    }
+}
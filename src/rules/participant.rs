@@ -0,0 +1,124 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::actor::Actor;
+use crate::rules::graph_layout::Rect;
+use crate::rules::{shapes, text_utils};
+use crate::rules::theme::{self, Theme};
+use svg::node::element::SVG;
+
+const TEXT_SIZE: i32 = 20;
+const LABEL_MAX_WIDTH: f64 = 200.0;
+const ACTOR_SIZE: i32 = 15;
+
+pub struct Participant {
+    label: String,
+    alias: String,
+    color: Option<String>,
+    actor: Option<Actor>,
+    theme: Theme
+}
+
+impl Participant {
+    // Accepts both PARTICIPANT and ACTOR rules, actors are drawn as stick figures.
+    pub fn new(value: Pair<Rule>) -> Participant {
+        if value.as_rule() == Rule::ACTOR {
+            let actor = Actor::new(value);
+            return Participant {
+                label: actor.get_actor_label().clone(),
+                alias: actor.get_actor_alias().clone(),
+                color: actor.get_color().clone(),
+                actor: Some(actor),
+                theme: Theme::default()
+            }
+        }
+
+        let mut inner = value.into_inner();
+        let label;
+        let alias;
+
+        inner.next(); // skip 'participant'
+        let l = inner.next().unwrap();
+        match l.as_rule() {
+            Rule::label => {
+                label = text_utils::unquote(l.as_str());
+                alias = label.clone();
+            },
+            Rule::ALIAS => {
+                let mut inner2 = l.into_inner();
+                label = text_utils::unquote(inner2.next().unwrap().as_str());
+
+                inner2.next(); // skip 'as'
+                alias = inner2.next().unwrap().as_str().to_owned();
+            }
+            _ => unreachable!()
+        }
+        let color = inner.next().map(|c| theme::parse_color(c.as_str()));
+
+        Participant {
+            label,
+            alias,
+            color,
+            actor: None,
+            theme: Theme::default()
+        }
+    }
+
+    // Participant used in a message without being declared.
+    pub fn implicit(alias: String) -> Participant {
+        Participant {
+            label: alias.clone(),
+            alias,
+            color: None,
+            actor: None,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_label(&self) -> &String {&self.label}
+
+    pub fn get_alias(&self) -> &String {&self.alias}
+
+    pub fn is_actor(&self) -> bool {
+        self.actor.is_some()
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        if let Some(actor) = &mut self.actor {
+            actor.set_theme(theme);
+        }
+    }
+
+    fn lines(&self) -> Vec<String> {
+        text_utils::wrap(&self.label, LABEL_MAX_WIDTH, TEXT_SIZE)
+    }
+
+    pub fn get_width(&self) -> i32 {
+        if self.is_actor() {
+            // actor labels are drawn with a bigger font below the figure
+            return f64::max(text_utils::text_width(&self.label, 30), 3.0 * ACTOR_SIZE as f64) as i32 + 10;
+        }
+        f64::max(text_utils::text_width(&self.lines().join("\n"), TEXT_SIZE) + 30.0, 100.0) as i32
+    }
+
+    pub fn get_height(&self) -> i32 {
+        if self.is_actor() {
+            return 6 * ACTOR_SIZE + 30 + 10;
+        }
+        50 + (text_utils::text_height(self.lines().len(), TEXT_SIZE) - text_utils::line_height(TEXT_SIZE)) as i32
+    }
+
+    // x is the middle of the lifeline, y the top of the participant.
+    pub fn draw(&mut self, svg: &mut SVG, x: i32, y: i32) {
+        if let Some(actor) = &mut self.actor {
+            actor.draw(svg, x, y + ACTOR_SIZE, ACTOR_SIZE);
+            return;
+        }
+
+        let width = self.get_width();
+        let height = self.get_height();
+        let fill = self.color.clone().unwrap_or(self.theme.fill_color.clone());
+        *svg = svg.clone().add(shapes::rounded_box(Rect::new(x - width / 2, y, width, height), 0, &fill, &self.theme));
+        *svg = svg.clone().add(shapes::centered_label(&self.lines(), (x as f64, (y + height / 2) as f64), TEXT_SIZE, &self.theme));
+    }
+}
@@ -1,185 +1,745 @@
 use std::cmp;
 use crate::grammar_parser::Rule;
-use activity_utils::{Type, draw_line};
-use crate::rules::{activity_utils, node};
+use activity_utils::{Type, draw_line, draw_polyline};
+use crate::rules::{activity_utils, node, text_utils};
 use pest::iterators::Pair;
-use svg::node::element::SVG;
+use svg::node::element::{SVG, Rectangle, Text as TextElement};
 use node::Node;
-use std::collections::LinkedList;
+use crate::rules::theme::Theme;
+use std::collections::{HashMap, LinkedList};
+
+pub const LANE_TEXT_SIZE: i32 = 28;
+// space between the flow and the borders of its swimlane or of the diagram
+pub const LANE_PADDING: usize = 50;
+// distance between the tracks of the goto jumps
+pub const GOTO_TRACK: usize = 30;
+
+// space between two lanes of the branches
+const LANE_GAP: usize = 60;
+// space between a loop and the arrows going around it
+const LOOP_GAP: usize = 60;
+const GUARD_SIZE: i32 = 25;
+
+// Places lanes of the given (left, right) extents side by side, centered around the column of the path.
+// Returns the offsets of the lanes and how far they reach to the left and to the right of the column.
+fn spread(lanes: &[(usize, usize)]) -> (Vec<isize>, usize, usize) {
+    let mut centers = Vec::new();
+    let mut start: usize = 0;
+    for (left, right) in lanes {
+        centers.push(start+left);
+        start += left+right+LANE_GAP;
+    }
+    let width = start-LANE_GAP;
+    let offsets = centers.iter().map(|c| *c as isize-(width/2) as isize).collect();
+    (offsets, width/2, width-width/2)
+}
+
+// Moves the column by the offset of a lane.
+fn shift(x: usize, offset: isize) -> usize {
+    (x as isize+offset) as usize
+}
 
+// Guard written right of the edge going down into its lane.
+fn draw_guard(x: usize, y: usize, svg: &mut SVG, guard: &str, theme: &Theme) {
+    let guard = TextElement::new()
+        .set("x", x+15)
+        .set("y", y)
+        .set("text-anchor", "start")
+        .set("dominant-baseline", "central")
+        .set("fill", theme.font_color.as_str())
+        .set("font-size", GUARD_SIZE)
+        .add(svg::node::Text::new(text_utils::escape(guard)));
+    *svg = svg.clone().add(guard);
+}
+
+// Branches of if, elseif and else, drawn in lanes below the decision and joined in the merge node.
 pub struct Condition {
-    main_path: Path,
-    alternative_path: Path
+    branches: Vec<(String, Path)>,
+    else_path: Option<Path>
 }
 
 impl Condition {
-    pub fn new(if_st: Pair<Rule>, else_st: Pair<Rule>) -> Condition {
-        let main_path = Path::new(if_st, false);
-        let alternative_path = Path::new(else_st, false);
+    pub fn new(value: Pair<Rule>) -> Condition {
+        let mut branches = Vec::new();
+        let mut else_path = None;
+        for statement in value.into_inner() {
+            let kind = statement.as_rule();
+            let mut inner = statement.into_inner();
+            match kind {
+                Rule::IF_STATEMENT | Rule::ELSEIF_STATEMENT => {
+                    let guard = text_utils::unquote(inner.next().unwrap().as_str());
+                    branches.push((guard, Path::new(inner.next().unwrap(), false)));
+                }
+                Rule::ELSE_STATEMENT => else_path = Some(Path::new(inner.next().unwrap(), false)),
+                _ => unreachable!()
+            }
+        }
 
         Condition {
-            main_path,
-            alternative_path
+            branches,
+            else_path
         }
     }
 
+    pub fn get_guards(&self) -> Vec<&String> {
+        self.branches.iter().map(|(guard, _)| guard).collect()
+    }
+
+    pub fn has_else(&self) -> bool {
+        self.else_path.is_some()
+    }
+
     pub fn print(&self) {
-        tracing::info!("If {{");
-        self.main_path.print();
-        tracing::info!("}}");
-        tracing::info!("else {{");
-        self.alternative_path.print();
-        tracing::info!("}}");
+        for (i, (guard, path)) in self.branches.iter().enumerate() {
+            tracing::info!("{} {} {{", if i == 0 { "If" } else { "elseif" }, guard);
+            path.print();
+            tracing::info!("}}");
+        }
+        if let Some(path) = &self.else_path {
+            tracing::info!("else {{");
+            path.print();
+            tracing::info!("}}");
+        }
     }
 
-    pub fn draw(&self, x: usize, y: usize, svg: &mut SVG, label: String) {
-        let right_x = x+self.main_path.max_left()*250+250;
-        let right_width = self.main_path.nodes.front().unwrap().get_name().len()*16;
-        self.main_path.draw(right_x, y, svg);
-        let arrow_label_r = self.main_path.nodes.front().unwrap().get_arrow_label().to_string();
-        draw_line(x+20,y-12,right_x-right_width/2, y-12, svg, arrow_label_r);
+    // Every lane makes room for its guard, without else the last lane is left empty for the edge
+    // which goes around the branches.
+    fn lanes(&self) -> (Vec<isize>, usize, usize) {
+        let mut lanes: Vec<(usize, usize)> = self.paths().iter().zip(self.labels())
+            .map(|(p, label)| (p.max_left(), cmp::max(p.max_right(), text_utils::text_width(&label, GUARD_SIZE) as usize+15)))
+            .collect();
+        if self.else_path.is_none() {
+            lanes.push((10, 10));
+        }
+        spread(&lanes)
+    }
 
-        let left_x = x-self.alternative_path.max_right()*250-250;
-        let left_width = self.alternative_path.nodes.front().unwrap().get_name().len()*16;
-        self.alternative_path.draw(left_x, y, svg);
-        let arrow_label_l = self.alternative_path.nodes.front().unwrap().get_arrow_label().to_string();
-        draw_line(x-20, y-12, left_x+left_width/2, y-12, svg, arrow_label_l);
+    // Guards of the branches, followed by the labels of their first arrows.
+    fn labels(&self) -> Vec<String> {
+        self.paths().iter().zip(self.guards())
+            .map(|(path, guard)| format!("{} {}", guard, path.nodes.front().unwrap().get_arrow_label()).trim_end().to_string())
+            .collect()
+    }
 
-        self.bound_last_nodes(x, y, svg, label);
+    fn paths(&self) -> Vec<&Path> {
+        let mut paths: Vec<&Path> = self.branches.iter().map(|(_, path)| path).collect();
+        paths.extend(self.else_path.iter());
+        paths
     }
 
-    pub fn bound_last_nodes(&self, x2: usize, y: usize, svg: &mut SVG, label: String) {
-        // find last node
-        let mut x1 = x2+self.main_path.max_right()*250+250;
-        let mut y1 = y+self.main_path.get_height()-110;
-        let y2 = y+self.get_height()-25;
+    fn guards(&self) -> Vec<String> {
+        let mut guards: Vec<String> = self.branches.iter().map(|(guard, _)| format!("[{}]", guard)).collect();
+        if self.else_path.is_some() {
+            guards.push("[else]".to_string());
+        }
+        guards
+    }
+
+    // The decision is drawn by its node at (x, y), the merge node at the bottom.
+    pub fn draw(&self, x: usize, y: usize, svg: &mut SVG, theme: &Theme, jumps: &mut Jumps) {
+        let (offsets, _, _) = self.lanes();
+        let body_y = y+130;
+        let merge_y = body_y+self.branches_height();
+
+        for ((path, label), offset) in self.paths().iter().zip(self.labels()).zip(&offsets) {
+            let lane_x = shift(x, *offset);
+            // edges leave the diamond from the corner on the side of the lane
+            let corner = match offset.cmp(&0) {
+                cmp::Ordering::Less => (x-25, y),
+                cmp::Ordering::Greater => (x+25, y),
+                cmp::Ordering::Equal => (x, y+25)
+            };
+            draw_polyline(&[corner, (lane_x, y), (lane_x, body_y-20)], svg, "".to_string(), theme);
+            draw_guard(lane_x, y+60, svg, &label, theme);
+            path.draw(lane_x, body_y, svg, theme, jumps);
+
+            if let Some(exit_y) = path.exit_y(body_y) {
+                let corner = match offset.cmp(&0) {
+                    cmp::Ordering::Less => (x-25, merge_y),
+                    cmp::Ordering::Greater => (x+25, merge_y),
+                    cmp::Ordering::Equal => (x, merge_y-25)
+                };
+                draw_polyline(&[(lane_x, exit_y), (lane_x, merge_y), corner], svg, "".to_string(), theme);
+            }
+        }
 
-        if self.main_path.get_last_node_type() != Type::END {
-            draw_line(x1, y1, x2, y2, svg, label.clone());
+        if self.else_path.is_none() {
+            let lane_x = shift(x, *offsets.last().unwrap());
+            draw_polyline(&[(x+25, y), (lane_x, y), (lane_x, merge_y), (x+25, merge_y)], svg, "".to_string(), theme);
         }
 
-        x1 = x2-self.alternative_path.max_right()*250-250;
-        y1 = y+self.alternative_path.get_height()-110;
-        if self.alternative_path.get_last_node_type() != Type::END {
-            draw_line(x1, y1, x2, y2, svg, label);
+        if !self.is_terminated() {
+            Node::if_node().draw(x, merge_y, svg, theme);
         }
     }
 
+    fn branches_height(&self) -> usize {
+        self.paths().iter().map(|p| p.get_height()).max().unwrap()
+    }
+
     pub fn get_left_depth(&self) -> usize {
-        return self.main_path.get_left_depth()
+        self.branches[0].1.get_left_depth()
     }
 
     pub fn get_right_depth(&self) -> usize {
-        return self.alternative_path.get_right_depth()
+        self.paths().last().unwrap().get_right_depth()
     }
 
     pub fn max_right(&self) -> usize {
-        let mut n: usize = 1;
+        cmp::max(self.lanes().2, 25)
+    }
 
-        n += self.main_path.max_right();
+    pub fn max_left(&self) -> usize {
+        cmp::max(self.lanes().1, 25)
+    }
 
-        return n;
+    // The flow ends inside the condition when every branch, including else, is terminated.
+    pub fn is_terminated(&self) -> bool {
+        self.else_path.is_some() && self.paths().iter().all(|p| p.get_last_node_type().is_final())
+    }
+
+    // the decision, the branches and the merge node
+    pub fn get_height(&self) -> usize {
+        self.branches_height()+260
+    }
+
+    pub fn nodes_count(&self) -> usize {
+        self.paths().iter().map(|p| p.nodes_count()).sum()
+    }
+}
+
+#[derive(PartialEq)]
+#[derive(Copy, Clone)]
+pub enum LoopKind {
+    // the guard is checked before the body
+    While,
+    // the body runs at least once, the guard is checked after it
+    Repeat
+}
+
+pub struct Loop {
+    kind: LoopKind,
+    guard: String,
+    body: Path
+}
+
+impl Loop {
+    pub fn new(kind: LoopKind, guard: String, body: Pair<Rule>) -> Loop {
+        Loop {
+            kind,
+            guard,
+            body: Path::new(body, false)
+        }
     }
 
+    pub fn get_kind(&self) -> LoopKind {
+        self.kind
+    }
+
+    pub fn get_guard(&self) -> &String {
+        &self.guard
+    }
+
+    pub fn print(&self) {
+        match self.kind {
+            LoopKind::While => {
+                tracing::info!("While {} {{", self.guard);
+                self.body.print();
+                tracing::info!("}}");
+            }
+            LoopKind::Repeat => {
+                tracing::info!("Repeat {{");
+                self.body.print();
+                tracing::info!("}} while {}", self.guard);
+            }
+        }
+    }
+
+    // The arrow going back passes left of the body, leaving a while loop goes around its right side.
+    pub fn draw(&self, x: usize, y: usize, svg: &mut SVG, theme: &Theme, jumps: &mut Jumps) {
+        let left_x = x-cmp::max(self.body.max_left(), 25)-LOOP_GAP;
+        let decision = Node::loop_node(self.guard.clone());
+        match self.kind {
+            LoopKind::While => {
+                decision.draw(x, y, svg, theme);
+                let body_y = y+130;
+                let first = self.body.nodes.front().unwrap();
+                draw_line(x, y+20, x, body_y-20, svg, first.get_arrow_label(), theme);
+                self.body.draw(x, body_y, svg, theme, jumps);
+
+                let bottom = body_y+self.body.get_height();
+                if let Some(exit_y) = self.body.exit_y(body_y) {
+                    let points = [(x, exit_y), (x, bottom-5), (left_x, bottom-5), (left_x, y), (x-30, y)];
+                    draw_polyline(&points, svg, self.guard.clone(), theme);
+                }
+
+                let right_x = x+cmp::max(self.body.max_right(), 25)+LOOP_GAP;
+                let points = [(x+25, y), (right_x, y), (right_x, bottom+20), (x, bottom+20)];
+                draw_polyline(&points, svg, "".to_string(), theme);
+            }
+            LoopKind::Repeat => {
+                self.body.draw(x, y, svg, theme, jumps);
+
+                let bottom = y+self.body.get_height();
+                let Some(exit_y) = self.body.exit_y(y) else {
+                    return;
+                };
+                if exit_y < bottom-25 {
+                    draw_line(x, exit_y, x, bottom-20, svg, "".to_string(), theme);
+                }
+                decision.draw(x, bottom, svg, theme);
+
+                let first = self.body.nodes.front().unwrap();
+                let points = [(x-25, bottom), (left_x, bottom), (left_x, y), (x-first.get_width()/2-10, y)];
+                draw_polyline(&points, svg, self.guard.clone(), theme);
+            }
+        }
+    }
+
+    // the arrow going back with the guard beside it
     pub fn max_left(&self) -> usize {
-        let mut n: usize = 1;
+        cmp::max(self.body.max_left(), 25)+LOOP_GAP+text_utils::text_width(&self.guard, GUARD_SIZE) as usize+15
+    }
 
-        n += self.alternative_path.max_left();
+    pub fn max_right(&self) -> usize {
+        match self.kind {
+            LoopKind::While => cmp::max(self.body.max_right(), 25)+LOOP_GAP+10,
+            LoopKind::Repeat => cmp::max(self.body.max_right(), 25)
+        }
+    }
 
-        return n;
+    // A repeat loop never gets to its guard when its body ends the flow.
+    pub fn is_terminated(&self) -> bool {
+        self.kind == LoopKind::Repeat && self.body.get_last_node_type().is_final()
+    }
+
+    pub fn get_height(&self) -> usize {
+        match self.kind {
+            LoopKind::While => self.body.get_height()+260,
+            LoopKind::Repeat => self.body.get_height()+130
+        }
+    }
+
+    pub fn nodes_count(&self) -> usize {
+        self.body.nodes_count()
+    }
+}
+
+// Parallel flows between the fork and the join bar.
+pub struct Fork {
+    branches: Vec<Path>
+}
+
+impl Fork {
+    pub fn new(value: Pair<Rule>) -> Fork {
+        let branches = value.into_inner()
+            .map(|branch| Path::new(branch.into_inner().next().unwrap(), false))
+            .collect();
+
+        Fork {
+            branches
+        }
+    }
+
+    pub fn get_branches(&self) -> &Vec<Path> {
+        &self.branches
+    }
+
+    pub fn print(&self) {
+        tracing::info!("Fork {{");
+        for (i, branch) in self.branches.iter().enumerate() {
+            if i > 0 {
+                tracing::info!("}} and {{");
+            }
+            branch.print();
+        }
+        tracing::info!("}}");
+    }
+
+    fn lanes(&self) -> (Vec<isize>, usize, usize) {
+        let lanes: Vec<(usize, usize)> = self.branches.iter()
+            .map(|b| (cmp::max(b.max_left(), 60), cmp::max(b.max_right(), 60)))
+            .collect();
+        spread(&lanes)
+    }
+
+    fn draw_bar(x1: usize, x2: usize, y: usize, svg: &mut SVG, theme: &Theme) {
+        let bar = Rectangle::new()
+            .set("x", x1)
+            .set("y", y-6)
+            .set("width", x2-x1)
+            .set("height", 12)
+            .set("fill", theme.line_color.as_str());
+        *svg = svg.clone().add(bar);
+    }
+
+    // The bars span all the lanes, every branch starts below the fork bar and ends in the join bar,
+    // there is no join bar when all the branches end the flow.
+    pub fn draw(&self, x: usize, y: usize, svg: &mut SVG, theme: &Theme, jumps: &mut Jumps) {
+        let (offsets, left, right) = self.lanes();
+        let lane_xs: Vec<usize> = offsets.iter().map(|o| shift(x, *o)).collect();
+        let body_y = y+130;
+        let join_y = body_y+self.branch_height();
+
+        Fork::draw_bar(x-left, x+right, y, svg, theme);
+        if !self.is_terminated() {
+            Fork::draw_bar(x-left, x+right, join_y, svg, theme);
+        }
+
+        for (branch, lane_x) in self.branches.iter().zip(lane_xs) {
+            let arrow_label = branch.nodes.front().unwrap().get_arrow_label();
+            draw_line(lane_x, y+6, lane_x, body_y-20, svg, arrow_label, theme);
+            branch.draw(lane_x, body_y, svg, theme, jumps);
+            if let Some(exit_y) = branch.exit_y(body_y) {
+                draw_line(lane_x, exit_y, lane_x, join_y-12, svg, "".to_string(), theme);
+            }
+        }
+    }
+
+    fn branch_height(&self) -> usize {
+        self.branches.iter().map(|b| b.get_height()).max().unwrap()
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.branches.iter().all(|b| b.get_last_node_type().is_final())
+    }
+
+    pub fn max_left(&self) -> usize {
+        self.lanes().1
+    }
+
+    pub fn max_right(&self) -> usize {
+        self.lanes().2
     }
 
+    // the fork bar, the branches and the join bar
     pub fn get_height(&self) -> usize {
-        return cmp::max(self.main_path.get_height(), self.alternative_path.get_height())
+        self.branch_height()+260
     }
 
     pub fn nodes_count(&self) -> usize {
-        return self.main_path.nodes_count()+self.alternative_path.nodes_count();
+        self.branches.iter().map(|b| b.nodes_count()).sum()
+    }
+}
+
+// Where the labeled nodes ended up and where the jumps to them start, the jumps are drawn last.
+#[derive(Default)]
+pub struct Jumps {
+    targets: HashMap<String, (usize, usize, usize)>,
+    sources: Vec<(usize, usize, String)>
+}
+
+impl Jumps {
+    // Every jump goes down to its own track left of the flow, along it to the row of the target
+    // and enters the target from the left, so it doesn't cross any node.
+    pub fn draw(&self, tracks_x: usize, svg: &mut SVG, theme: &Theme) {
+        for (n, (x, y, label)) in self.sources.iter().enumerate() {
+            let track_x = tracks_x-n*GOTO_TRACK;
+            let (target_x, target_y, width) = self.targets[label];
+            let side_x = target_x-width/2-25;
+            let points = [
+                (*x, *y), (*x, y+50), (track_x, y+50), (track_x, target_y-65),
+                (side_x, target_y-65), (side_x, target_y), (side_x+20, target_y)
+            ];
+            draw_polyline(&points, svg, "".to_string(), theme);
+        }
     }
 }
 
 pub struct Path {
     nodes: LinkedList<Node>,
-    alternatives: Vec<Condition>
+    alternatives: Vec<Condition>,
+    loops: Vec<Loop>,
+    forks: Vec<Fork>,
+    // swimlane of every node, the lanes are numbered in the order of their names
+    node_lanes: Vec<usize>,
+    lane_names: Vec<String>
 }
 
 impl Path {
     pub fn new(value: Pair<Rule>, main: bool) -> Path {
-        let mut nodes = LinkedList::new();
-        let mut alternatives = Vec::new();
+        Path::build(value, main, Vec::new())
+    }
+
+    // Main flow which starts in the given swimlane.
+    pub fn in_lane(value: Pair<Rule>, lane: String) -> Path {
+        Path::build(value, true, vec![lane])
+    }
+
+    fn build(value: Pair<Rule>, main: bool, lane_names: Vec<String>) -> Path {
+        let mut path = Path {
+            nodes: LinkedList::new(),
+            alternatives: Vec::new(),
+            loops: Vec::new(),
+            forks: Vec::new(),
+            node_lanes: Vec::new(),
+            lane_names
+        };
 
         if main {
-            nodes.push_back(Node::start_node());
+            path.push(Node::start_node(), 0);
         }
+        path.add(value, main, 0);
+
+        path
+    }
 
+    fn push(&mut self, node: Node, lane: usize) {
+        self.nodes.push_back(node);
+        self.node_lanes.push(lane);
+    }
+
+    // The nodes before the first switch belong to the first lane.
+    fn switch_lane(&mut self, name: String, main: bool) -> usize {
+        if !main {
+            panic!("Swimlanes can be switched only in the main flow: {}", name);
+        }
+        match self.lane_names.iter().position(|n| *n == name) {
+            Some(lane) => lane,
+            None => {
+                self.lane_names.push(name);
+                self.lane_names.len()-1
+            }
+        }
+    }
+
+    fn add(&mut self, value: Pair<Rule>, main: bool, mut lane: usize) {
         for inner_pair in value.into_inner() {
             match inner_pair.as_rule() {
-                Rule::END_STATE | Rule::ACTIVITY => {
-                    nodes.push_back(Node::new(inner_pair))
+                Rule::END_STATE | Rule::ACTIVITY | Rule::EVENT | Rule::GOTO => {
+                    self.push(Node::new(inner_pair), lane)
                 }
                 Rule::IF => {
+                    let condition = Condition::new(inner_pair);
+                    // the guards are drawn on the edges, the diamond itself is left empty
+                    self.push(Node::if_node(), lane);
+                    self.alternatives.push(condition);
+                }
+                Rule::WHILE | Rule::REPEAT => {
+                    let kind = if inner_pair.as_rule() == Rule::WHILE { LoopKind::While } else { LoopKind::Repeat };
                     let mut inner = inner_pair.into_inner();
-                    let mut if_st = inner.next().unwrap().into_inner();
-                    let mut else_st = inner.next().unwrap().into_inner();
-
-                    let name = if_st.next().unwrap().as_str().to_owned();
-                    nodes.push_back(Node::if_node(name));
+                    let (guard, body) = match kind {
+                        LoopKind::While => {
+                            let guard = inner.next().unwrap();
+                            (guard, inner.next().unwrap())
+                        }
+                        LoopKind::Repeat => {
+                            let body = inner.next().unwrap();
+                            (inner.next().unwrap(), body)
+                        }
+                    };
+                    let guard = text_utils::unquote(guard.as_str());
+                    self.push(Node::loop_node(guard.clone()), lane);
+                    self.loops.push(Loop::new(kind, guard, body));
+                }
+                Rule::FORK => {
+                    self.push(Node::fork_node(), lane);
+                    self.forks.push(Fork::new(inner_pair));
+                }
+                Rule::SWIMLANE => {
+                    let name = inner_pair.into_inner().next().unwrap().as_str().trim().to_owned();
+                    lane = self.switch_lane(name, main);
+                }
+                // the flow goes back to the previous lane after the partition
+                Rule::PARTITION => {
+                    let mut inner = inner_pair.into_inner();
+                    let name = text_utils::unquote(inner.next().unwrap().as_str());
+                    let partition_lane = self.switch_lane(name, main);
+                    self.add(inner.next().unwrap(), main, partition_lane);
+                }
+                _ => unreachable!()
+            }
+        }
+    }
 
-                    let if_body = if_st.next().unwrap();
+    // Labels of the nodes and the labels the gotos jump to, in all the nested paths.
+    pub fn collect_jumps(&self, labels: &mut Vec<String>, gotos: &mut Vec<String>) {
+        for node in self.nodes.iter() {
+            if let Some(alias) = node.get_alias() {
+                labels.push(alias.clone());
+            }
+            if node.get_kind() == Type::GOTO {
+                gotos.push(node.get_name());
+            }
+        }
+        for alternative in &self.alternatives {
+            for path in alternative.paths() {
+                path.collect_jumps(labels, gotos);
+            }
+        }
+        for l in &self.loops {
+            l.body.collect_jumps(labels, gotos);
+        }
+        for fork in &self.forks {
+            for branch in &fork.branches {
+                branch.collect_jumps(labels, gotos);
+            }
+        }
+    }
 
-                    let else_body = else_st.next().unwrap();
+    pub fn get_lane_names(&self) -> &Vec<String> {
+        &self.lane_names
+    }
 
-                    alternatives.push(Condition::new(if_body, else_body));
+    // How far the blocks of every lane reach to the left and to the right of its column.
+    fn lane_extents(&self) -> Vec<(usize, usize)> {
+        let mut extents = vec![(0, 0); cmp::max(self.lane_names.len(), 1)];
+        let (mut i, mut j, mut k) = (0, 0, 0);
+        for (node, lane) in self.nodes.iter().zip(&self.node_lanes) {
+            let block = match node.get_kind() {
+                Type::IF => {
+                    i += 1;
+                    (self.alternatives[i-1].max_left(), self.alternatives[i-1].max_right())
                 }
-                _ => unreachable!()
-            }
+                Type::LOOP => {
+                    j += 1;
+                    (self.loops[j-1].max_left(), self.loops[j-1].max_right())
+                }
+                Type::FORK => {
+                    k += 1;
+                    (self.forks[k-1].max_left(), self.forks[k-1].max_right())
+                }
+                _ => node.extents()
+            };
+            extents[*lane] = (cmp::max(extents[*lane].0, block.0), cmp::max(extents[*lane].1, block.1));
         }
+        extents
+    }
+
+    // Widths of the swimlanes, each is wide enough for its blocks and its header.
+    pub fn lane_widths(&self) -> Vec<usize> {
+        self.lane_extents().iter().enumerate().map(|(lane, (left, right))| {
+            let header = self.lane_names.get(lane)
+                .map(|name| text_utils::text_width(name, LANE_TEXT_SIZE) as usize+40)
+                .unwrap_or(0);
+            cmp::max(left+right+2*LANE_PADDING, header)
+        }).collect()
+    }
 
-        Path {
-            nodes,
-            alternatives
+    // Distance of the column of the lane from its left border.
+    pub fn lane_column(&self, lane: usize) -> usize {
+        let (left, right) = self.lane_extents()[lane];
+        left+(self.lane_widths()[lane]-left-right)/2
+    }
+
+    // Columns of all the lanes when the first one is at x.
+    fn lane_columns(&self, x: usize) -> Vec<usize> {
+        if self.lane_names.len() < 2 {
+            return vec![x];
+        }
+        let mut border = x-self.lane_column(0);
+        let mut columns = Vec::new();
+        for (lane, width) in self.lane_widths().iter().enumerate() {
+            columns.push(border+self.lane_column(lane));
+            border += width;
         }
+        columns
     }
 
     pub fn print(&self) {
         let mut i: usize = 0;
+        let mut j: usize = 0;
+        let mut k: usize = 0;
         for node in self.nodes.iter() {
             match node.get_kind() {
                 Type::IF => {
                     self.alternatives[i].print();
                     i += 1;
                 }
+                Type::LOOP => {
+                    self.loops[j].print();
+                    j += 1;
+                }
+                Type::FORK => {
+                    self.forks[k].print();
+                    k += 1;
+                }
                 Type::START => {
                     tracing::info!("Start")
                 }
                 Type::END => {
                     tracing::info!("Stop")
                 }
-                Type::STEP => {
+                Type::FLOWEND => {
+                    tracing::info!("End")
+                }
+                Type::KILL => {
+                    tracing::info!("Kill")
+                }
+                Type::DETACH => {
+                    tracing::info!("Detach")
+                }
+                Type::GOTO => {
+                    tracing::info!("Goto {}", node.get_name())
+                }
+                _ => {
                     node.print()
                 }
             }
         }
     }
 
-    pub fn draw(&self, x: usize, mut y: usize, svg: &mut SVG) {
+    pub fn draw(&self, x: usize, mut y: usize, svg: &mut SVG, theme: &Theme, jumps: &mut Jumps) {
+        let columns = self.lane_columns(x);
         let mut i:usize = 0;
+        let mut j:usize = 0;
+        let mut k:usize = 0;
         let mut node_num: usize = 1;
+        // no arrow leaves a node which ends the flow, nor a condition, loop or fork which ends it
+        let mut ended = false;
         for node in self.nodes.iter() {
-            if node_num != 1 && self.nodes.iter().nth(node_num-2).unwrap().get_kind() != Type::IF {
+            let x = columns[self.node_lanes[node_num-1]];
+            // taller nodes are moved down, so the gap between them and the previous node is kept
+            let half_extra = node.extra_height()/2;
+            y += half_extra;
+            if let Some(alias) = node.get_alias() {
+                jumps.targets.insert(alias.clone(), (x, y, node.get_width()));
+            }
+            if node.get_kind() == Type::GOTO {
+                if !ended {
+                    jumps.sources.push((x, y-110-half_extra, node.get_name()));
+                }
+                ended = true;
+                y += 130;
+                node_num += 1;
+                continue;
+            }
+            if node_num != 1 && !ended {
                 let arrow_label = node.get_arrow_label().to_string();
-                draw_line(x, y-110, x, y-20, svg, arrow_label);
+                let from_x = columns[self.node_lanes[node_num-2]];
+                if from_x == x {
+                    draw_line(x, y-110-half_extra, x, y-20-half_extra, svg, arrow_label, theme);
+                } else {
+                    // arrows between the lanes turn halfway between the rows
+                    let turn_y = y-65-half_extra;
+                    draw_polyline(&[(from_x, y-110-half_extra), (from_x, turn_y), (x, turn_y), (x, y-20-half_extra)], svg, arrow_label, theme);
+                }
+            }
+            if node.get_kind() == Type::LOOP {
+                self.loops[j].draw(x, y, svg, theme, jumps);
+                ended = self.loops[j].is_terminated();
+                y += self.loops[j].get_height();
+                j += 1;
+                node_num += 1;
+                continue;
+            }
+            if node.get_kind() == Type::FORK {
+                self.forks[k].draw(x, y, svg, theme, jumps);
+                ended = self.forks[k].is_terminated();
+                y += self.forks[k].get_height();
+                k += 1;
+                node_num += 1;
+                continue;
             }
-            node.draw(x, y, svg);
+            node.draw(x, y, svg, theme);
+            ended = node.get_kind().is_final();
             if node.get_kind() == Type::IF {
-                let label = self.nodes.iter().nth(node_num).unwrap().get_arrow_label().to_string();
-                self.alternatives[i].draw(x, y, svg, label);
-                y += self.alternatives[i].get_height();
+                self.alternatives[i].draw(x, y, svg, theme, jumps);
+                ended = self.alternatives[i].is_terminated();
+                y += self.alternatives[i].get_height()+half_extra;
                 i += 1;
-            } else if node.get_kind() == Type::STEP {
-                y += 130;
             } else {
-                y += 130;
+                y += 130+half_extra;
             }
             node_num += 1;
         }
@@ -220,32 +780,57 @@ impl Path {
     pub fn max_right(&self) -> usize {
         let mut n: usize = 0;
 
+        for node in self.nodes.iter() {
+            n = cmp::max(n, node.extents().1);
+        }
+
         for alternative in &self.alternatives {
             if alternative.max_right() > n {
                 n = alternative.max_right();
             }
         }
 
+        for l in &self.loops {
+            n = cmp::max(n, l.max_right());
+        }
+
+        for fork in &self.forks {
+            n = cmp::max(n, fork.max_right());
+        }
+
         return n;
     }
 
     pub fn max_left(&self) -> usize {
         let mut n: usize = 0;
 
+        for node in self.nodes.iter() {
+            n = cmp::max(n, node.extents().0);
+        }
+
         for alternative in &self.alternatives {
             if alternative.max_left() > n {
                 n = alternative.max_left();
             }
         }
 
+        for l in &self.loops {
+            n = cmp::max(n, l.max_left());
+        }
+
+        for fork in &self.forks {
+            n = cmp::max(n, fork.max_left());
+        }
+
         return n;
     }
 
     pub fn get_height(&self) -> usize {
         let mut n: usize = 0;
 
-        for _node in self.nodes.iter() {
-            n += 130;
+        for node in self.nodes.iter() {
+            let half_extra = node.extra_height()/2;
+            n += 130+2*half_extra;
         }
 
         for alternative in &self.alternatives {
@@ -254,15 +839,34 @@ impl Path {
 
         n -= 130*self.alternatives.len();
 
+        for l in &self.loops {
+            n += l.get_height()-130;
+        }
+
+        for fork in &self.forks {
+            n += fork.get_height()-130;
+        }
+
         return n
     }
 
-    pub fn get_last_node_type(&self) -> Type {
-        return if self.nodes.back().unwrap().get_kind() == Type::IF {
-            self.alternatives.last().unwrap().main_path.get_last_node_type()
-        } else {
-            self.nodes.back().unwrap().get_kind()
+    // Where the flow leaves the path drawn at the given y, None when it ends inside.
+    pub fn exit_y(&self, y: usize) -> Option<usize> {
+        if self.get_last_node_type().is_final() {
+            return None;
         }
+        Some(y+self.get_height()-110)
+    }
+
+    pub fn get_last_node_type(&self) -> Type {
+        let kind = self.nodes.back().unwrap().get_kind();
+        let terminated = match kind {
+            Type::IF => self.alternatives.last().unwrap().is_terminated(),
+            Type::LOOP => self.loops.last().unwrap().is_terminated(),
+            Type::FORK => self.forks.last().unwrap().is_terminated(),
+            _ => false
+        };
+        if terminated { Type::END } else { kind }
     }
 
     pub fn nodes_count(&self) -> usize {
@@ -273,6 +877,14 @@ impl Path {
             n += alternative.nodes_count()
         }
 
+        for l in &self.loops {
+            n += l.nodes_count()
+        }
+
+        for fork in &self.forks {
+            n += fork.nodes_count()
+        }
+
         n
     }
 }
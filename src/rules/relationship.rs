@@ -0,0 +1,168 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::graph_layout::Rect;
+use crate::rules::text_utils;
+use crate::rules::theme::Theme;
+use svg::node::element::{
+    SVG, Circle, Line, Text as TextElement
+};
+
+const TEXT_SIZE: i32 = 18;
+const LABEL_MAX_WIDTH: f64 = 250.0;
+
+// Crow's foot notation for one end of a relationship.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Cardinality {
+    ZeroOrOne,
+    ExactlyOne,
+    ZeroOrMany,
+    OneOrMany
+}
+
+impl Cardinality {
+    // The symbols are mirrored on the two sides of the line, e.g. '}o' and 'o{'.
+    fn from_symbol(symbol: &str) -> Cardinality {
+        match symbol {
+            "|o" | "o|" => Cardinality::ZeroOrOne,
+            "||" => Cardinality::ExactlyOne,
+            "}o" | "o{" => Cardinality::ZeroOrMany,
+            "}|" | "|{" => Cardinality::OneOrMany,
+            _ => unreachable!()
+        }
+    }
+}
+
+pub struct Relationship {
+    left_id: String,
+    right_id: String,
+    left: Cardinality,
+    right: Cardinality,
+    identifying: bool,
+    label: String
+}
+
+impl Relationship {
+    pub fn new(value: Pair<Rule>) -> Relationship {
+        let mut inner = value.into_inner();
+        let left_id = text_utils::unquote(inner.next().unwrap().as_str());
+        let left = Cardinality::from_symbol(inner.next().unwrap().as_str());
+        let identifying = inner.next().unwrap().as_rule() == Rule::solid_line;
+        let right = Cardinality::from_symbol(inner.next().unwrap().as_str());
+        let right_id = text_utils::unquote(inner.next().unwrap().as_str());
+
+        inner.next(); // skip colon
+
+        let label = match inner.next() {
+            Some(l) => text_utils::unquote(l.as_str()),
+            None => String::new()
+        };
+
+        Relationship {
+            left_id,
+            right_id,
+            left,
+            right,
+            identifying,
+            label
+        }
+    }
+
+    pub fn get_left_id(&self) -> &String {&self.left_id}
+    pub fn get_right_id(&self) -> &String {&self.right_id}
+    pub fn get_left(&self) -> Cardinality {self.left}
+    pub fn get_right(&self) -> Cardinality {self.right}
+    pub fn is_identifying(&self) -> bool {self.identifying}
+    pub fn get_label(&self) -> &String {&self.label}
+
+    fn line(svg: &mut SVG, a: (f64, f64), b: (f64, f64), theme: &Theme) {
+        let line = Line::new()
+            .set("x1", a.0)
+            .set("y1", a.1)
+            .set("x2", b.0)
+            .set("y2", b.1)
+            .set("stroke", theme.line_color.as_str())
+            .set("stroke-width", 2);
+        *svg = svg.clone().add(line);
+    }
+
+    // end is on the border of the entity, direction is the unit vector pointing away from it
+    fn draw_cardinality(svg: &mut SVG, cardinality: Cardinality, end: (f64, f64), direction: (f64, f64), theme: &Theme) {
+        let at = |distance: f64| (end.0 + direction.0 * distance, end.1 + direction.1 * distance);
+        let normal = (-direction.1, direction.0);
+        let bar = |svg: &mut SVG, distance: f64| {
+            let (cx, cy) = at(distance);
+            Relationship::line(svg, (cx + normal.0 * 10.0, cy + normal.1 * 10.0), (cx - normal.0 * 10.0, cy - normal.1 * 10.0), theme);
+        };
+        let ring = |svg: &mut SVG, distance: f64| {
+            let (cx, cy) = at(distance);
+            let circle = Circle::new()
+                .set("cx", cx)
+                .set("cy", cy)
+                .set("r", 6)
+                .set("fill", theme.background_color.as_str())
+                .set("stroke", theme.line_color.as_str())
+                .set("stroke-width", 2);
+            *svg = svg.clone().add(circle);
+        };
+        let crow_foot = |svg: &mut SVG| {
+            let toe = at(18.0);
+            for side in [-1.0, 0.0, 1.0] {
+                Relationship::line(svg, toe, (end.0 + normal.0 * 12.0 * side, end.1 + normal.1 * 12.0 * side), theme);
+            }
+        };
+
+        match cardinality {
+            Cardinality::ExactlyOne => {
+                bar(svg, 10.0);
+                bar(svg, 18.0);
+            }
+            Cardinality::ZeroOrOne => {
+                bar(svg, 10.0);
+                ring(svg, 26.0);
+            }
+            Cardinality::OneOrMany => {
+                crow_foot(svg);
+                bar(svg, 24.0);
+            }
+            Cardinality::ZeroOrMany => {
+                crow_foot(svg);
+                ring(svg, 28.0);
+            }
+        }
+    }
+
+    pub fn draw(&self, svg: &mut SVG, from: Rect, to: Rect, theme: &Theme) {
+        let start = from.border_point(to.center());
+        let end = to.border_point(from.center());
+        let length = ((end.0 - start.0).powi(2) + (end.1 - start.1).powi(2)).sqrt().max(1.0);
+        let direction = ((end.0 - start.0) / length, (end.1 - start.1) / length);
+
+        let mut line = Line::new()
+            .set("x1", start.0)
+            .set("y1", start.1)
+            .set("x2", end.0)
+            .set("y2", end.1)
+            .set("stroke", theme.line_color.as_str())
+            .set("stroke-width", 2);
+        if !self.identifying {
+            line = line.set("stroke-dasharray", "8 8");
+        }
+        *svg = svg.clone().add(line);
+
+        Relationship::draw_cardinality(svg, self.left, start, direction, theme);
+        Relationship::draw_cardinality(svg, self.right, end, (-direction.0, -direction.1), theme);
+
+        if !self.label.is_empty() {
+            let (cx, cy) = ((start.0 + end.0) / 2.0, (start.1 + end.1) / 2.0 - 12.0);
+            let lines = text_utils::wrap(&self.label, LABEL_MAX_WIDTH, TEXT_SIZE);
+            let text = TextElement::new()
+                .set("x", cx)
+                .set("y", cy)
+                .set("text-anchor", "middle")
+                .set("dominant-baseline", "central")
+                .set("fill", theme.font_color.as_str())
+                .set("font-size", TEXT_SIZE);
+            *svg = svg.clone().add(text_utils::add_lines(text, &lines, cx, cy, TEXT_SIZE));
+        }
+    }
+}
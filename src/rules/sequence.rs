@@ -0,0 +1,433 @@
+use std::collections::{HashMap, HashSet};
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::fragment::{self, Fragment};
+use crate::rules::message::{self, Message};
+use crate::rules::participant::Participant;
+use crate::rules::theme::Theme;
+use svg::node::element::{
+    SVG, Group, Line, Rectangle
+};
+
+const MARGIN: i32 = 60;
+const TOP: i32 = 20;
+const BAR_WIDTH: i32 = 12;
+
+pub enum Statement {
+    Message(Message),
+    Activate(String),
+    Deactivate(String),
+    Create(String),
+    Destroy(String),
+    Autonumber(usize),
+    Fragment(Fragment)
+}
+
+impl Statement {
+    pub fn new(value: Pair<Rule>) -> Statement {
+        match value.as_rule() {
+            Rule::MESSAGE => Statement::Message(Message::new(value)),
+            Rule::LIFELINE => {
+                let mut inner = value.into_inner();
+                let keyword = inner.next().unwrap().as_rule();
+                let alias = inner.next().unwrap().as_str().to_owned();
+                match keyword {
+                    Rule::activate_kw => Statement::Activate(alias),
+                    Rule::deactivate_kw => Statement::Deactivate(alias),
+                    Rule::create_kw => Statement::Create(alias),
+                    Rule::destroy_kw => Statement::Destroy(alias),
+                    _ => unreachable!()
+                }
+            }
+            Rule::AUTONUMBER => {
+                let start = value.into_inner().next()
+                    .map(|n| n.as_str().parse()
+                        .unwrap_or_else(|_| panic!("Autonumber start too large: {}", n.as_str())))
+                    .unwrap_or(1);
+                Statement::Autonumber(start)
+            }
+            Rule::FRAGMENT => Statement::Fragment(Fragment::new(value)),
+            _ => unreachable!()
+        }
+    }
+}
+
+// Collects the aliases of participants used by the statements, in order of appearance.
+fn referenced_aliases(statements: &[Statement], aliases: &mut Vec<String>) {
+    let add = |alias: &String, aliases: &mut Vec<String>| {
+        if !aliases.contains(alias) {
+            aliases.push(alias.clone());
+        }
+    };
+    for statement in statements {
+        match statement {
+            Statement::Message(message) => {
+                add(message.get_from(), aliases);
+                add(message.get_to(), aliases);
+            }
+            Statement::Activate(alias) | Statement::Deactivate(alias)
+            | Statement::Create(alias) | Statement::Destroy(alias) => add(alias, aliases),
+            Statement::Autonumber(_) => {}
+            Statement::Fragment(fragment) => {
+                for section in fragment.get_sections() {
+                    referenced_aliases(section.get_statements(), aliases);
+                }
+            }
+        }
+    }
+}
+
+fn collect_messages<'a>(statements: &'a [Statement], messages: &mut Vec<&'a Message>) {
+    for statement in statements {
+        match statement {
+            Statement::Message(message) => messages.push(message),
+            Statement::Fragment(fragment) => {
+                for section in fragment.get_sections() {
+                    collect_messages(section.get_statements(), messages);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Layout state shared while walking through the statements.
+struct State {
+    y: i32,
+    number: Option<usize>,
+    depth: i32,
+    activations: HashMap<String, Vec<i32>>,
+    pending_creations: HashSet<String>,
+    created_at: HashMap<String, i32>,
+    destroyed_at: HashMap<String, i32>
+}
+
+pub struct Sequence {
+    participants: Vec<Participant>,
+    statements: Vec<Statement>,
+    theme: Theme
+}
+
+impl Sequence {
+    pub fn new(value: Pair<Rule>) -> Sequence {
+        let mut participants: Vec<Participant> = Vec::new();
+        let mut statements = Vec::new();
+
+        for inner_pair in value.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::ACTOR | Rule::PARTICIPANT => {
+                    let participant = Participant::new(inner_pair);
+                    if participants.iter().any(|p| p.get_alias() == participant.get_alias()) {
+                        panic!("Duplicate name/alias found: {}", participant.get_alias());
+                    }
+                    participants.push(participant);
+                }
+                _ => statements.push(Statement::new(inner_pair))
+            }
+        }
+
+        // participants used without a declaration are added in order of appearance
+        let mut aliases = Vec::new();
+        referenced_aliases(&statements, &mut aliases);
+        for alias in aliases {
+            if !participants.iter().any(|p| *p.get_alias() == alias) {
+                participants.push(Participant::implicit(alias));
+            }
+        }
+
+        Sequence {
+            participants,
+            statements,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_participants(&self) -> &Vec<Participant> {&self.participants}
+
+    pub fn get_statements(&self) -> &Vec<Statement> {&self.statements}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        for participant in &mut self.participants {
+            participant.set_theme(theme);
+        }
+    }
+
+    fn index(&self, alias: &String) -> usize {
+        self.participants.iter()
+            .position(|p| p.get_alias() == alias)
+            .unwrap()
+    }
+
+    // x coordinates of the lifelines, spaced so the labels of the messages fit between them
+    pub fn positions(&self) -> Vec<i32> {
+        let n = self.participants.len();
+        let mut gaps: Vec<i32> = (0..n).map(|i| {
+            if i == 0 { 0 } else { self.participants[i-1].get_width() / 2 + self.participants[i].get_width() / 2 + 40 }
+        }).collect();
+
+        let mut messages = Vec::new();
+        collect_messages(&self.statements, &mut messages);
+        for message in messages {
+            let from = self.index(message.get_from());
+            let to = self.index(message.get_to());
+            if from == to {
+                if from + 1 < n {
+                    gaps[from+1] = std::cmp::max(gaps[from+1], message.label_width() + message::SELF_MESSAGE_WIDTH + 30);
+                }
+            } else if from.abs_diff(to) == 1 {
+                let i = std::cmp::max(from, to);
+                gaps[i] = std::cmp::max(gaps[i], message.label_width() + 40);
+            }
+        }
+
+        let mut positions = Vec::new();
+        let mut x = MARGIN;
+        for (i, gap) in gaps.iter().enumerate() {
+            x += if i == 0 { self.participants[0].get_width() / 2 } else { *gap };
+            positions.push(x);
+        }
+        positions
+    }
+
+    fn header_height(&self) -> i32 {
+        let mut created = HashSet::new();
+        for statement in &self.statements {
+            if let Statement::Create(alias) = statement {
+                created.insert(alias.clone());
+            }
+        }
+        self.participants.iter()
+            .filter(|p| !created.contains(p.get_alias()))
+            .map(|p| p.get_height())
+            .max()
+            .unwrap_or(0)
+    }
+
+    // offset of the left or right edge of the top activation bar from the lifeline
+    fn bar_edge(state: &State, alias: &String, right: bool) -> i32 {
+        let level = state.activations.get(alias).map(|a| a.len()).unwrap_or(0) as i32;
+        if level == 0 {
+            return 0;
+        }
+        let center = (level - 1) * BAR_WIDTH / 2;
+        if right { center + BAR_WIDTH / 2 } else { center - BAR_WIDTH / 2 }
+    }
+
+    fn draw_bar(&self, group: &mut Group, x: i32, level: i32, top: i32, bottom: i32) {
+        let bar = Rectangle::new()
+            .set("x", x + (level - 1) * BAR_WIDTH / 2 - BAR_WIDTH / 2)
+            .set("y", top)
+            .set("width", BAR_WIDTH)
+            .set("height", std::cmp::max(bottom - top, 10))
+            .set("fill", self.theme.fill_color.as_str())
+            .set("stroke", self.theme.border_color.as_str())
+            .set("stroke-width", 2);
+        *group = group.clone().add(bar);
+    }
+
+    // left and right edge of the frame of a combined fragment
+    fn fragment_bounds(&self, fragment: &Fragment, positions: &[i32], depth: i32) -> (i32, i32) {
+        let mut aliases = Vec::new();
+        let mut messages = Vec::new();
+        for section in fragment.get_sections() {
+            referenced_aliases(section.get_statements(), &mut aliases);
+            collect_messages(section.get_statements(), &mut messages);
+        }
+        let indices: Vec<usize> = if aliases.is_empty() {
+            (0..self.participants.len()).collect()
+        } else {
+            aliases.iter().map(|a| self.index(a)).collect()
+        };
+
+        let margin = std::cmp::max(20, 50 - 10 * depth);
+        let mut left = indices.iter().map(|i| positions[*i]).min().unwrap_or(MARGIN) - margin;
+        let mut right = indices.iter().map(|i| positions[*i]).max().unwrap_or(MARGIN) + margin;
+        for message in messages.iter().filter(|m| m.is_self_message()) {
+            let x = positions[self.index(message.get_from())];
+            right = std::cmp::max(right, x + message::SELF_MESSAGE_WIDTH + message.label_width() + margin);
+        }
+        if left < 5 {
+            left = 5;
+        }
+        if right <= left {
+            right = left + 2 * margin;
+        }
+        (left, right)
+    }
+
+    fn walk(&self, statements: &[Statement], state: &mut State, group: &mut Group, positions: &[i32]) {
+        for statement in statements {
+            match statement {
+                Statement::Message(message) => {
+                    state.y += message.get_height();
+                    let from = self.index(message.get_from());
+                    let to = self.index(message.get_to());
+                    let (mut x1, mut x2) = (positions[from], positions[to]);
+                    let mut arrow_y = state.y;
+
+                    if from == to {
+                        arrow_y -= 25;
+                        x1 += Sequence::bar_edge(state, message.get_from(), true);
+                        x2 = x1;
+                    } else if x2 > x1 {
+                        x1 += Sequence::bar_edge(state, message.get_from(), true);
+                        x2 += Sequence::bar_edge(state, message.get_to(), false);
+                    } else {
+                        x1 += Sequence::bar_edge(state, message.get_from(), false);
+                        x2 += Sequence::bar_edge(state, message.get_to(), true);
+                    }
+
+                    // the box of a created participant is placed at the end of the creating message
+                    let created = from != to && state.pending_creations.remove(message.get_to());
+                    if created {
+                        let half_width = self.participants[to].get_width() / 2;
+                        x2 = if x2 > x1 { positions[to] - half_width } else { positions[to] + half_width };
+                        state.created_at.insert(message.get_to().clone(), arrow_y);
+                    }
+
+                    message.draw(group, x1, x2, arrow_y, state.number, &self.theme);
+                    state.number = state.number.map(|n| n + 1);
+
+                    if created {
+                        state.y += self.participants[to].get_height() / 2;
+                    }
+                }
+                Statement::Activate(alias) => {
+                    state.activations.entry(alias.clone()).or_default().push(state.y);
+                }
+                Statement::Deactivate(alias) => {
+                    let level = state.activations.get(alias).map(|a| a.len()).unwrap_or(0) as i32;
+                    if let Some(top) = state.activations.get_mut(alias).and_then(|a| a.pop()) {
+                        self.draw_bar(group, positions[self.index(alias)], level, top, state.y);
+                    }
+                }
+                Statement::Create(alias) => {
+                    state.pending_creations.insert(alias.clone());
+                }
+                Statement::Destroy(alias) => {
+                    let x = positions[self.index(alias)];
+                    while let Some(top) = state.activations.get_mut(alias).and_then(|a| a.pop()) {
+                        let level = state.activations[alias].len() as i32 + 1;
+                        self.draw_bar(group, x, level, top, state.y);
+                    }
+                    for (dx1, dx2) in [(-10, 10), (10, -10)] {
+                        let line = Line::new()
+                            .set("x1", x + dx1)
+                            .set("y1", state.y - 10)
+                            .set("x2", x + dx2)
+                            .set("y2", state.y + 10)
+                            .set("stroke", self.theme.line_color.as_str())
+                            .set("stroke-width", 3);
+                        *group = group.clone().add(line);
+                    }
+                    state.destroyed_at.insert(alias.clone(), state.y);
+                    state.y += 15;
+                }
+                Statement::Autonumber(start) => {
+                    state.number = Some(*start);
+                }
+                Statement::Fragment(fragment) => {
+                    let (left, right) = self.fragment_bounds(fragment, positions, state.depth);
+                    state.y += 15;
+                    let top = state.y;
+                    state.y += fragment::HEADER_HEIGHT;
+
+                    let mut separators = Vec::new();
+                    for (i, section) in fragment.get_sections().iter().enumerate() {
+                        if i > 0 {
+                            state.y += 15;
+                            separators.push(state.y);
+                            state.y += fragment::HEADER_HEIGHT;
+                        }
+                        state.depth += 1;
+                        self.walk(section.get_statements(), state, group, positions);
+                        state.depth -= 1;
+                    }
+                    state.y += 15;
+                    fragment.draw(group, (left, right), top, state.y, &separators, &self.theme);
+                }
+            }
+        }
+    }
+
+    // Lays out and draws all the statements, returns the final state.
+    fn layout(&self, group: &mut Group, positions: &[i32]) -> State {
+        let mut state = State {
+            y: TOP + self.header_height() + 10,
+            number: None,
+            depth: 0,
+            activations: HashMap::new(),
+            pending_creations: HashSet::new(),
+            created_at: HashMap::new(),
+            destroyed_at: HashMap::new()
+        };
+        self.walk(&self.statements, &mut state, group, positions);
+        state.y += 20;
+
+        // close the bars which were never deactivated
+        let activations: Vec<(String, Vec<i32>)> = state.activations.drain().collect();
+        for (alias, tops) in activations {
+            for (i, top) in tops.iter().enumerate() {
+                self.draw_bar(group, positions[self.index(&alias)], i as i32 + 1, *top, state.y);
+            }
+        }
+        // participants created without a message appear at the end
+        for alias in state.pending_creations.drain() {
+            state.created_at.insert(alias, state.y);
+        }
+
+        state
+    }
+
+    pub fn draw(&mut self, svg: &mut SVG) {
+        let positions = self.positions();
+        let mut group = Group::new();
+        let state = self.layout(&mut group, &positions);
+
+        for (i, participant) in self.participants.iter_mut().enumerate() {
+            let x = positions[i];
+            let top = match state.created_at.get(participant.get_alias()) {
+                Some(y) => y - participant.get_height() / 2,
+                None => TOP
+            };
+            let bottom = *state.destroyed_at.get(participant.get_alias()).unwrap_or(&state.y);
+
+            let lifeline = Line::new()
+                .set("x1", x)
+                .set("y1", top + participant.get_height())
+                .set("x2", x)
+                .set("y2", bottom)
+                .set("stroke", self.theme.line_color.as_str())
+                .set("stroke-width", 2)
+                .set("stroke-dasharray", "6 6");
+            *svg = svg.clone().add(lifeline);
+            participant.draw(svg, x, top);
+        }
+
+        *svg = svg.clone().add(group);
+    }
+
+    pub fn width(&self) -> usize {
+        if self.participants.is_empty() {
+            return (2 * MARGIN) as usize;
+        }
+        let positions = self.positions();
+        let last = self.participants.len() - 1;
+        let mut width = positions[last] + self.participants[last].get_width() / 2 + MARGIN;
+
+        let mut messages = Vec::new();
+        collect_messages(&self.statements, &mut messages);
+        for message in messages.iter().filter(|m| m.is_self_message()) {
+            let x = positions[self.index(message.get_from())];
+            width = std::cmp::max(width, x + message::SELF_MESSAGE_WIDTH + message.label_width() + MARGIN);
+        }
+        width as usize
+    }
+
+    pub fn height(&self) -> usize {
+        let positions = self.positions();
+        let state = self.layout(&mut Group::new(), &positions);
+        (state.y + 20) as usize
+    }
+}
@@ -0,0 +1,40 @@
+use crate::rules::graph_layout::Rect;
+use crate::rules::text_utils;
+use crate::rules::theme::Theme;
+use svg::node::element::{
+    Rectangle, Text as TextElement
+};
+
+// Shapes shared by the diagrams which draw plain boxes around wrapped labels,
+// the boxes with compartments of objects and entities are drawn like classes.
+
+// Size of the box around the lines with the given padding on each side.
+pub fn label_size(lines: &[String], text_size: i32, (padding_x, padding_y): (i32, i32)) -> (i32, i32) {
+    let width = text_utils::text_width(&lines.join("\n"), text_size) as i32;
+    let height = text_utils::text_height(lines.len(), text_size) as i32;
+    (width + 2 * padding_x, height + 2 * padding_y)
+}
+
+pub fn rounded_box(bounds: Rect, radius: i32, fill: &str, theme: &Theme) -> Rectangle {
+    Rectangle::new()
+        .set("x", bounds.x)
+        .set("y", bounds.y)
+        .set("width", bounds.width)
+        .set("height", bounds.height)
+        .set("fill", fill)
+        .set("stroke", theme.border_color.as_str())
+        .set("stroke-width", theme.line_width)
+        .set("rx", radius)
+}
+
+// The lines centered both horizontally and vertically around (x, y).
+pub fn centered_label(lines: &[String], (x, y): (f64, f64), text_size: i32, theme: &Theme) -> TextElement {
+    let caption = TextElement::new()
+        .set("x", x)
+        .set("y", y)
+        .set("text-anchor", "middle")
+        .set("dominant-baseline", "central")
+        .set("fill", theme.font_color.as_str())
+        .set("font-size", text_size);
+    text_utils::add_lines(caption, lines, x, y, text_size)
+}
@@ -0,0 +1,262 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::text_utils;
+use crate::rules::theme::Theme;
+use svg::node::element::{
+    SVG, Polygon, Polyline, Text as TextElement
+};
+
+const TEXT_SIZE: i32 = 18;
+const STATE_SIZE: i32 = 14;
+// distance between two levels of a robust signal
+const LEVEL_HEIGHT: i32 = 30;
+const CONCISE_HEIGHT: i32 = 30;
+const CLOCK_HEIGHT: i32 = 30;
+// width of the slanted ends of a concise state
+const SLANT: f64 = 5.0;
+// shortest distance between two drawn rising edges of a clock
+const MIN_CYCLE_WIDTH: f64 = 6.0;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SignalKind {
+    Robust,
+    Concise,
+    Clock { period: i64, pulse: i64, offset: i64 }
+}
+
+// Maps the time onto the x axis of the plot.
+#[derive(Debug, Clone, Copy)]
+pub struct TimeScale {
+    pub left: i32,
+    pub start: i64,
+    pub end: i64,
+    // pixels per unit of time
+    pub scale: f64
+}
+
+impl TimeScale {
+    pub fn x(&self, time: i64) -> f64 {
+        self.left as f64 + (time - self.start) as f64 * self.scale
+    }
+}
+
+// Lifeline of the timing diagram, with the states it takes at the given time points.
+pub struct Signal {
+    label: String,
+    alias: String,
+    kind: SignalKind,
+    changes: Vec<(i64, String)>,
+    theme: Theme
+}
+
+impl Signal {
+    // Accepts both TIMING_LINE and CLOCK rules.
+    pub fn new(value: Pair<Rule>) -> Signal {
+        let clock = value.as_rule() == Rule::CLOCK;
+        let mut inner = value.into_inner();
+        let mut kind = SignalKind::Robust;
+        // clocks have no keyword of their kind
+        if !clock && inner.next().unwrap().as_rule() == Rule::concise_kw {
+            kind = SignalKind::Concise;
+        }
+
+        let label;
+        let alias;
+        let l = inner.next().unwrap();
+        match l.as_rule() {
+            Rule::label => {
+                label = text_utils::unquote(l.as_str());
+                alias = label.clone();
+            },
+            Rule::ALIAS => {
+                let mut inner2 = l.into_inner();
+                label = text_utils::unquote(inner2.next().unwrap().as_str());
+
+                inner2.next(); // skip 'as'
+                alias = inner2.next().unwrap().as_str().to_owned();
+            }
+            _ => unreachable!()
+        }
+
+        if clock {
+            let mut period = 0;
+            let mut pulse = None;
+            let mut offset = 0;
+            for setting in inner {
+                let value: i64 = setting.clone().into_inner().next().unwrap().as_str().parse().unwrap();
+                match setting.as_rule() {
+                    Rule::CLOCK_PERIOD => period = value,
+                    Rule::CLOCK_PULSE => pulse = Some(value),
+                    Rule::CLOCK_OFFSET => offset = value,
+                    _ => unreachable!()
+                }
+            }
+            if period == 0 {
+                panic!("Period of clock {} must be positive", alias);
+            }
+            let pulse = pulse.unwrap_or(period / 2);
+            if pulse == 0 || pulse >= period {
+                panic!("Pulse of clock {} must be positive and shorter than its period", alias);
+            }
+            kind = SignalKind::Clock { period, pulse, offset };
+        }
+
+        Signal {
+            label,
+            alias,
+            kind,
+            changes: Vec::new(),
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_label(&self) -> &String {&self.label}
+    pub fn get_alias(&self) -> &String {&self.alias}
+    pub fn get_kind(&self) -> SignalKind {self.kind}
+    pub fn get_changes(&self) -> &Vec<(i64, String)> {&self.changes}
+
+    pub fn add_change(&mut self, time: i64, state: String) {
+        if let SignalKind::Clock { .. } = self.kind {
+            panic!("Clock {} can't change its state", self.alias);
+        }
+        // a later statement for the same time point wins
+        self.changes.retain(|(t, _)| *t != time);
+        self.changes.push((time, state));
+        self.changes.sort_by_key(|(t, _)| *t);
+    }
+
+    // States of a robust signal in the order they first appear, drawn from the bottom up.
+    pub fn states(&self) -> Vec<String> {
+        let mut states: Vec<String> = Vec::new();
+        for (_, state) in &self.changes {
+            if !states.contains(state) {
+                states.push(state.clone());
+            }
+        }
+        states
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+    }
+
+    // Width of the names in the column left of the plot.
+    pub fn label_width(&self) -> i32 {
+        let states = match self.kind {
+            SignalKind::Robust => self.states().iter()
+                .map(|s| text_utils::text_width(s, STATE_SIZE) as i32)
+                .max()
+                .unwrap_or(0),
+            _ => 0
+        };
+        std::cmp::max(text_utils::text_width(&self.label, TEXT_SIZE) as i32, states)
+    }
+
+    // Height of the waveform with the label above it.
+    pub fn get_height(&self) -> i32 {
+        let waveform = match self.kind {
+            SignalKind::Robust => std::cmp::max(self.states().len() as i32 - 1, 1) * LEVEL_HEIGHT,
+            SignalKind::Concise => CONCISE_HEIGHT,
+            SignalKind::Clock { .. } => CLOCK_HEIGHT
+        };
+        text_utils::line_height(TEXT_SIZE) as i32 + waveform
+    }
+
+    fn text(&self, content: &str, (x, y): (f64, f64), size: i32, anchor: &str) -> TextElement {
+        TextElement::new()
+            .set("x", x)
+            .set("y", y)
+            .set("text-anchor", anchor)
+            .set("dominant-baseline", "central")
+            .set("fill", self.theme.font_color.as_str())
+            .set("font-size", size)
+            .add(svg::node::Text::new(text_utils::escape(content)))
+    }
+
+    // States spanning the time between consecutive changes, the last one lasts until the end.
+    fn intervals(&self, end: i64) -> Vec<(i64, i64, &String)> {
+        self.changes.iter().enumerate().map(|(i, (time, state))| {
+            let next = self.changes.get(i + 1).map(|(t, _)| *t).unwrap_or(end);
+            (*time, next, state)
+        }).collect()
+    }
+
+    fn waveform(&self, points: String) -> Polyline {
+        Polyline::new()
+            .set("points", points)
+            .set("fill", "none")
+            .set("stroke", self.theme.line_color.as_str())
+            .set("stroke-width", 2)
+    }
+
+    // (x, top) is the top left corner of the label, the waveform is drawn below it on the time scale.
+    pub fn draw(&self, svg: &mut SVG, x: i32, top: i32, time: TimeScale) {
+        let label_height = text_utils::line_height(TEXT_SIZE) as i32;
+        let text = self.text(&self.label, (x as f64, (top + label_height / 2) as f64), TEXT_SIZE, "start")
+            .set("font-weight", "bold");
+        *svg = svg.clone().add(text);
+
+        let wave_top = (top + label_height) as f64;
+        let bottom = (top + self.get_height()) as f64;
+        match self.kind {
+            SignalKind::Robust => {
+                let states = self.states();
+                let level = |state: &String| {
+                    let index = states.iter().position(|s| s == state).unwrap();
+                    bottom - (index as i32 * LEVEL_HEIGHT) as f64
+                };
+                for state in &states {
+                    let name = self.text(state, ((time.left - 10) as f64, level(state)), STATE_SIZE, "end");
+                    *svg = svg.clone().add(name);
+                }
+
+                let mut points = Vec::new();
+                for (from, to, state) in self.intervals(time.end) {
+                    points.push(format!("{},{}", time.x(from), level(state)));
+                    points.push(format!("{},{}", time.x(to), level(state)));
+                }
+                if !points.is_empty() {
+                    *svg = svg.clone().add(self.waveform(points.join(" ")));
+                }
+            }
+            SignalKind::Concise => {
+                let middle = (wave_top + bottom) / 2.0;
+                for (from, to, state) in self.intervals(time.end) {
+                    let (x1, x2) = (time.x(from), time.x(to));
+                    let slant = f64::min(SLANT, (x2 - x1) / 2.0);
+                    let shape = Polygon::new()
+                        .set("points", format!("{},{} {},{} {},{} {},{} {},{} {},{}",
+                            x1, middle, x1 + slant, wave_top, x2 - slant, wave_top,
+                            x2, middle, x2 - slant, bottom, x1 + slant, bottom))
+                        .set("fill", self.theme.fill_color.as_str())
+                        .set("stroke", self.theme.line_color.as_str())
+                        .set("stroke-width", 2);
+                    *svg = svg.clone().add(shape);
+                    // the name is left out when it doesn't fit into the state
+                    if text_utils::text_width(state, STATE_SIZE) < x2 - x1 - 2.0 * slant {
+                        *svg = svg.clone().add(self.text(state, ((x1 + x2) / 2.0, middle), STATE_SIZE, "middle"));
+                    }
+                }
+            }
+            SignalKind::Clock { period, pulse, offset } => {
+                let mut points = vec![format!("{},{}", time.x(time.start), bottom)];
+                // the first rising edge at or before the start of the plot
+                let mut rise = time.start + (offset - time.start).rem_euclid(period) - period;
+                // cycles too narrow to be seen at this scale are skipped
+                let skipped = (MIN_CYCLE_WIDTH / (period as f64 * time.scale)).ceil().max(1.0) as i64;
+                let step = period.saturating_mul(skipped);
+                while rise < time.end {
+                    let fall = rise + pulse;
+                    for (edge, from, to) in [(rise, bottom, wave_top), (fall, wave_top, bottom)] {
+                        let edge = edge.clamp(time.start, time.end);
+                        points.push(format!("{},{}", time.x(edge), from));
+                        points.push(format!("{},{}", time.x(edge), to));
+                    }
+                    rise = rise.saturating_add(step);
+                }
+                points.push(format!("{},{}", time.x(time.end), bottom));
+                *svg = svg.clone().add(self.waveform(points.join(" ")));
+            }
+        }
+    }
+}
@@ -0,0 +1,454 @@
+use std::collections::{HashMap, HashSet};
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::graph_layout::{self, GraphLayout, Rect};
+use crate::rules::{shapes, text_utils};
+use crate::rules::theme::{self, Theme};
+use crate::rules::transition::Transition;
+use svg::node::element::{
+    SVG, Circle, Line, Polygon, Text as TextElement
+};
+
+const TEXT_SIZE: i32 = 20;
+const ACTION_SIZE: i32 = 16;
+const PADDING: i32 = 20;
+const GAP_X: i32 = 60;
+const GAP_Y: i32 = 80;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum StateKind {
+    Simple,
+    Initial,
+    Final,
+    Choice,
+    History,
+    DeepHistory
+}
+
+// Part of a composite state (or of the whole diagram), concurrent regions are drawn side by side.
+pub struct Region {
+    states: Vec<State>,
+    transitions: Vec<Transition>,
+    layout: Option<GraphLayout>
+}
+
+impl Region {
+    pub fn new(value: Pair<Rule>, owner: &str, index: usize) -> Region {
+        let scope = format!("{}/{}", owner, index);
+        let mut states = Vec::new();
+        let mut transitions = Vec::new();
+
+        for inner_pair in value.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::STATE => states.push(State::new(inner_pair)),
+                Rule::PSEUDO_STATE => {
+                    let alias = inner_pair.into_inner().next().unwrap().as_str().to_owned();
+                    states.push(State::pseudo(alias, StateKind::Choice));
+                }
+                Rule::TRANSITION => transitions.push(Transition::new(inner_pair, &scope, owner)),
+                _ => unreachable!()
+            }
+        }
+
+        let mut region = Region {
+            states,
+            transitions,
+            layout: None
+        };
+
+        // pseudo-states used by the transitions of this region
+        let initial = format!("{}(*)", scope);
+        let final_state = format!("{}(^)", scope);
+        let history = format!("{}[H]", owner);
+        let deep_history = format!("{}[H*]", owner);
+        let endpoints: Vec<String> = region.transitions.iter()
+            .flat_map(|t| [t.get_from().clone(), t.get_to().clone()])
+            .collect();
+        for id in endpoints {
+            if region.contains(&id) {
+                continue;
+            }
+            if id == initial {
+                region.states.insert(0, State::pseudo(id, StateKind::Initial));
+            } else if id == final_state {
+                region.states.push(State::pseudo(id, StateKind::Final));
+            } else if id == history {
+                region.states.push(State::pseudo(id, StateKind::History));
+            } else if id == deep_history {
+                region.states.push(State::pseudo(id, StateKind::DeepHistory));
+            }
+        }
+        region
+    }
+
+    pub fn empty() -> Region {
+        Region {
+            states: Vec::new(),
+            transitions: Vec::new(),
+            layout: None
+        }
+    }
+
+    pub fn get_states(&self) -> &Vec<State> {&self.states}
+
+    pub fn add_state(&mut self, state: State) {
+        self.states.push(state);
+    }
+
+    pub fn contains(&self, alias: &String) -> bool {
+        self.states.iter().any(|s| s.contains(alias))
+    }
+
+    pub fn find_mut(&mut self, alias: &String) -> Option<&mut State> {
+        for state in &mut self.states {
+            if state.get_alias() == alias {
+                return Some(state);
+            }
+            for region in &mut state.regions {
+                if let Some(found) = region.find_mut(alias) {
+                    return Some(found);
+                }
+            }
+        }
+        None
+    }
+
+    // Aliases of all the states, including the nested ones.
+    pub fn aliases(&self, aliases: &mut Vec<String>) {
+        for state in &self.states {
+            aliases.push(state.get_alias().clone());
+            for region in &state.regions {
+                region.aliases(aliases);
+            }
+        }
+    }
+
+    pub fn get_transitions(&self) -> Vec<&Transition> {
+        let mut transitions: Vec<&Transition> = self.transitions.iter().collect();
+        for state in &self.states {
+            for region in &state.regions {
+                transitions.extend(region.get_transitions());
+            }
+        }
+        transitions
+    }
+
+    // States used in the transitions without a declaration are added to the region they appear in.
+    pub fn add_implicit_states(&mut self, declared: &mut HashSet<String>) {
+        let endpoints: Vec<String> = self.transitions.iter()
+            .flat_map(|t| [t.get_from().clone(), t.get_to().clone()])
+            .collect();
+        for id in endpoints {
+            if !id.contains(['(', '[']) && declared.insert(id.clone()) {
+                self.states.push(State::implicit(id));
+            }
+        }
+        for state in &mut self.states {
+            for region in &mut state.regions {
+                region.add_implicit_states(declared);
+            }
+        }
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        for state in &mut self.states {
+            state.set_theme(theme);
+        }
+    }
+
+    // Lays out the nested regions first, as they decide about the size of the composite states.
+    pub fn layout(&mut self, transitions: &[(String, String)]) {
+        for state in &mut self.states {
+            for region in &mut state.regions {
+                region.layout(transitions);
+            }
+        }
+        let sizes: Vec<(i32, i32)> = self.states.iter()
+            .map(|s| (s.get_width(), s.get_height()))
+            .collect();
+        let edges: Vec<(usize, usize)> = transitions.iter().filter_map(|(from, to)| {
+            let from = self.states.iter().position(|s| s.contains(from))?;
+            let to = self.states.iter().position(|s| s.contains(to))?;
+            Some((from, to))
+        }).collect();
+        self.layout = Some(graph_layout::layout(&sizes, &edges, GAP_X, GAP_Y));
+    }
+
+    pub fn get_width(&self) -> i32 {
+        self.layout.as_ref().map(|l| l.width).unwrap_or(0)
+    }
+
+    pub fn get_height(&self) -> i32 {
+        self.layout.as_ref().map(|l| l.height).unwrap_or(0)
+    }
+
+    // Draws the states with (x, y) being the top left corner, rects collects the bounds of every state.
+    pub fn draw(&self, svg: &mut SVG, x: i32, y: i32, rects: &mut HashMap<String, Rect>) {
+        if let Some(layout) = &self.layout {
+            for (state, (dx, dy)) in self.states.iter().zip(&layout.positions) {
+                state.draw(svg, x + dx, y + dy, rects);
+            }
+        }
+    }
+}
+
+pub struct State {
+    name: String,
+    alias: String,
+    kind: StateKind,
+    color: Option<String>,
+    actions: Vec<String>,
+    regions: Vec<Region>,
+    theme: Theme
+}
+
+impl State {
+    pub fn new(value: Pair<Rule>) -> State {
+        let mut inner = value.into_inner();
+        let name;
+        let alias;
+
+        let l = inner.next().unwrap();
+        match l.as_rule() {
+            Rule::label => {
+                name = text_utils::unquote(l.as_str());
+                alias = name.clone();
+            },
+            Rule::ALIAS => {
+                let mut inner2 = l.into_inner();
+                name = text_utils::unquote(inner2.next().unwrap().as_str());
+
+                inner2.next(); // skip 'as'
+                alias = inner2.next().unwrap().as_str().to_owned();
+            }
+            _ => unreachable!()
+        }
+
+        let mut color = None;
+        let mut actions = Vec::new();
+        let mut regions = Vec::new();
+        for inner_pair in inner {
+            match inner_pair.as_rule() {
+                Rule::color => color = Some(theme::parse_color(inner_pair.as_str())),
+                Rule::INTERNAL_ACTION => {
+                    let mut action = inner_pair.into_inner();
+                    let kind = action.next().unwrap().as_str();
+                    let text = action.next().map(|t| t.as_str().trim()).unwrap_or("");
+                    actions.push(format!("{} / {}", kind, text));
+                }
+                Rule::STATE_REGION => {
+                    let index = regions.len();
+                    regions.push(Region::new(inner_pair, &alias, index));
+                }
+                Rule::solid_line => {}
+                _ => unreachable!()
+            }
+        }
+
+        State {
+            name,
+            alias,
+            kind: StateKind::Simple,
+            color,
+            actions,
+            regions,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn pseudo(alias: String, kind: StateKind) -> State {
+        State {
+            name: String::new(),
+            alias,
+            kind,
+            color: None,
+            actions: Vec::new(),
+            regions: Vec::new(),
+            theme: Theme::default()
+        }
+    }
+
+    // State used in a transition without being declared.
+    pub fn implicit(alias: String) -> State {
+        State {
+            name: alias.clone(),
+            ..State::pseudo(alias, StateKind::Simple)
+        }
+    }
+
+    pub fn get_name(&self) -> &String {&self.name}
+
+    pub fn get_alias(&self) -> &String {&self.alias}
+
+    pub fn get_kind(&self) -> StateKind {self.kind}
+
+    pub fn get_actions(&self) -> &Vec<String> {&self.actions}
+
+    pub fn get_regions(&self) -> &Vec<Region> {&self.regions}
+
+    pub fn add_region(&mut self, region: Region) {
+        self.regions.push(region);
+    }
+
+    pub fn regions_mut(&mut self) -> &mut Vec<Region> {&mut self.regions}
+
+    pub fn is_composite(&self) -> bool {
+        !self.regions.is_empty()
+    }
+
+    pub fn contains(&self, alias: &String) -> bool {
+        self.alias == *alias || self.regions.iter().any(|r| r.contains(alias))
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        for region in &mut self.regions {
+            region.set_theme(theme);
+        }
+    }
+
+    // Height of the name and the internal actions.
+    fn header_height(&self) -> i32 {
+        if self.actions.is_empty() {
+            return 40;
+        }
+        40 + (self.actions.len() as f64 * text_utils::line_height(ACTION_SIZE)) as i32 + 10
+    }
+
+    fn regions_width(&self) -> i32 {
+        self.regions.iter().map(|r| r.get_width() + 2 * PADDING).sum()
+    }
+
+    pub fn get_width(&self) -> i32 {
+        match self.kind {
+            StateKind::Initial | StateKind::Final => 30,
+            StateKind::Choice => 40,
+            StateKind::History | StateKind::DeepHistory => 36,
+            StateKind::Simple => {
+                let actions = self.actions.iter()
+                    .map(|a| text_utils::text_width(a, ACTION_SIZE))
+                    .fold(0.0, f64::max);
+                let text = f64::max(text_utils::text_width(&self.name, TEXT_SIZE), actions) as i32 + 40;
+                *[text, 120, self.regions_width()].iter().max().unwrap()
+            }
+        }
+    }
+
+    pub fn get_height(&self) -> i32 {
+        match self.kind {
+            StateKind::Simple if self.is_composite() => {
+                let regions = self.regions.iter().map(|r| r.get_height()).max().unwrap_or(0);
+                self.header_height() + regions + 2 * PADDING
+            }
+            StateKind::Simple => std::cmp::max(self.header_height(), 50),
+            _ => self.get_width()
+        }
+    }
+
+    fn draw_text(&self, svg: &mut SVG, content: &str, x: i32, y: i32, anchor: &str, size: i32) {
+        let text = TextElement::new()
+            .set("x", x)
+            .set("y", y)
+            .set("text-anchor", anchor)
+            .set("dominant-baseline", "central")
+            .set("fill", self.theme.font_color.as_str())
+            .set("font-size", size)
+            .add(svg::node::Text::new(text_utils::escape(content)));
+        *svg = svg.clone().add(text);
+    }
+
+    fn draw_line(&self, svg: &mut SVG, x1: i32, y1: i32, x2: i32, y2: i32, dashed: bool) {
+        let mut line = Line::new()
+            .set("x1", x1)
+            .set("y1", y1)
+            .set("x2", x2)
+            .set("y2", y2)
+            .set("stroke", self.theme.border_color.as_str())
+            .set("stroke-width", 2);
+        if dashed {
+            line = line.set("stroke-dasharray", "8 8");
+        }
+        *svg = svg.clone().add(line);
+    }
+
+    // (x, y) is the top left corner of the state
+    pub fn draw(&self, svg: &mut SVG, x: i32, y: i32, rects: &mut HashMap<String, Rect>) {
+        let width = self.get_width();
+        let height = self.get_height();
+        rects.insert(self.alias.clone(), Rect::new(x, y, width, height));
+        let (cx, cy) = (x + width / 2, y + height / 2);
+        let fill = self.color.clone().unwrap_or(self.theme.fill_color.clone());
+
+        match self.kind {
+            StateKind::Initial => {
+                let circle = Circle::new()
+                    .set("cx", cx)
+                    .set("cy", cy)
+                    .set("r", width / 2)
+                    .set("fill", self.theme.line_color.as_str());
+                *svg = svg.clone().add(circle);
+            }
+            StateKind::Final => {
+                let outer = Circle::new()
+                    .set("cx", cx)
+                    .set("cy", cy)
+                    .set("r", width / 2)
+                    .set("fill", "none")
+                    .set("stroke", self.theme.line_color.as_str())
+                    .set("stroke-width", 2);
+                let inner = Circle::new()
+                    .set("cx", cx)
+                    .set("cy", cy)
+                    .set("r", width / 2 - 6)
+                    .set("fill", self.theme.line_color.as_str());
+                *svg = svg.clone().add(outer).add(inner);
+            }
+            StateKind::Choice => {
+                let diamond = Polygon::new()
+                    .set("points", format!("{},{} {},{} {},{} {},{}", cx, y, x + width, cy, cx, y + height, x, cy))
+                    .set("fill", fill)
+                    .set("stroke", self.theme.border_color.as_str())
+                    .set("stroke-width", 2);
+                *svg = svg.clone().add(diamond);
+            }
+            StateKind::History | StateKind::DeepHistory => {
+                let circle = Circle::new()
+                    .set("cx", cx)
+                    .set("cy", cy)
+                    .set("r", width / 2)
+                    .set("fill", fill)
+                    .set("stroke", self.theme.border_color.as_str())
+                    .set("stroke-width", 2);
+                *svg = svg.clone().add(circle);
+                let label = if self.kind == StateKind::History { "H" } else { "H*" };
+                self.draw_text(svg, label, cx, cy, "middle", ACTION_SIZE);
+            }
+            StateKind::Simple => {
+                *svg = svg.clone().add(shapes::rounded_box(Rect::new(x, y, width, height), 15, &fill, &self.theme));
+
+                if self.actions.is_empty() && !self.is_composite() {
+                    self.draw_text(svg, &self.name, cx, cy, "middle", TEXT_SIZE);
+                    return;
+                }
+                self.draw_text(svg, &self.name, cx, y + 20, "middle", TEXT_SIZE);
+                self.draw_line(svg, x, y + 40, x + width, y + 40, false);
+
+                let line_height = text_utils::line_height(ACTION_SIZE);
+                for (i, action) in self.actions.iter().enumerate() {
+                    let action_y = y as f64 + 45.0 + line_height * (i as f64 + 0.5);
+                    self.draw_text(svg, action, x + 10, action_y as i32, "start", ACTION_SIZE);
+                }
+
+                let mut region_x = x + (width - self.regions_width()) / 2 + PADDING;
+                let region_y = y + self.header_height() + PADDING;
+                for (i, region) in self.regions.iter().enumerate() {
+                    if i > 0 {
+                        self.draw_line(svg, region_x - PADDING, y + self.header_height(), region_x - PADDING, y + height, true);
+                    }
+                    region.draw(svg, region_x, region_y, rects);
+                    region_x += region.get_width() + 2 * PADDING;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::{HashMap, HashSet};
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::state::{Region, State, StateKind};
+use crate::rules::theme::Theme;
+use svg::node::element::SVG;
+
+const MARGIN: i32 = 40;
+// room on the right for the transitions bending around the states
+const RIGHT_MARGIN: i32 = 120;
+
+pub struct StateMachine {
+    root: Region,
+    theme: Theme
+}
+
+impl StateMachine {
+    pub fn new(value: Pair<Rule>) -> StateMachine {
+        let mut root = Region::new(value, "", 0);
+
+        let mut aliases = Vec::new();
+        root.aliases(&mut aliases);
+        let mut declared = HashSet::new();
+        for alias in aliases {
+            if !declared.insert(alias.clone()) {
+                panic!("Duplicate name/alias found: {}", alias);
+            }
+        }
+        root.add_implicit_states(&mut declared);
+
+        // history of a composite state referenced from outside of it, e.g. 'Active[H]'
+        let endpoints: Vec<String> = root.get_transitions().iter()
+            .flat_map(|t| [t.get_from().clone(), t.get_to().clone()])
+            .collect();
+        for id in endpoints {
+            if declared.contains(&id) {
+                continue;
+            }
+            let (owner, kind) = if let Some(owner) = id.strip_suffix("[H*]") {
+                (owner.to_owned(), StateKind::DeepHistory)
+            } else if let Some(owner) = id.strip_suffix("[H]") {
+                (owner.to_owned(), StateKind::History)
+            } else {
+                continue;
+            };
+            if root.contains(&id) {
+                continue;
+            }
+            let state = root.find_mut(&owner)
+                .unwrap_or_else(|| panic!("State not found: {}", owner));
+            if !state.is_composite() {
+                state.add_region(Region::empty());
+            }
+            state.regions_mut()[0].add_state(State::pseudo(id.clone(), kind));
+            declared.insert(id);
+        }
+
+        let transitions: Vec<(String, String)> = root.get_transitions().iter()
+            .map(|t| (t.get_from().clone(), t.get_to().clone()))
+            .collect();
+        root.layout(&transitions);
+
+        StateMachine {
+            root,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_root(&self) -> &Region {&self.root}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        self.root.set_theme(theme);
+    }
+
+    pub fn draw(&self, svg: &mut SVG) {
+        let mut rects = HashMap::new();
+        self.root.draw(svg, MARGIN, MARGIN, &mut rects);
+
+        for transition in self.root.get_transitions() {
+            let from = rects[transition.get_from()];
+            let to = rects[transition.get_to()];
+            transition.draw(svg, from, to, &self.theme);
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        (self.root.get_width() + MARGIN + RIGHT_MARGIN) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.root.get_height() + 2 * MARGIN) as usize
+    }
+}
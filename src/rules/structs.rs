@@ -1,6 +1,8 @@
 use pest::iterators::Pair;
 use crate::grammar_parser::Rule;
 use crate::rules::structs::Visibility::{PRIVATE, PROTECTED, PUBLIC};
+use crate::rules::text_utils;
+use crate::rules::theme::{self, Theme};
 use svg;
 
 #[derive(Debug, PartialEq, Eq)]
@@ -21,18 +23,25 @@ pub struct Class {
     name: String,
     attributes: Vec<Component>,
     methods: Vec<Component>,
+    color: Option<String>,
+    theme: Theme,
     x: usize,
     y: usize,
     height: usize,
     width: usize
 }
 
-fn add_text(text : svg::node::Text, i: usize, x: usize, y: usize) -> svg::node::element::Text {
+pub(crate) fn add_text(text : svg::node::Text, i: usize, x: usize, y: usize, theme: &Theme) -> svg::node::element::Text {
+    add_row(text, i, x, y + 87, theme)
+}
+
+// y is the middle of the first row, the rows are 50 high.
+pub(crate) fn add_row(text : svg::node::Text, i: usize, x: usize, y: usize, theme: &Theme) -> svg::node::element::Text {
     svg::node::element::Text::new()
         .set("x", x + 15)
-        .set("y", y + 87 + i*50)
+        .set("y", y + i*50)
         .set("dominant-baseline", "central")
-        .set("fill", "black")
+        .set("fill", theme.font_color.as_str())
         .set("font-size", 28)
         .add(text)
 }
@@ -44,8 +53,13 @@ impl Class {
         let mut attributes = Vec::new();
         let mut methods = Vec::new();
         let mut inner = value.into_inner();
-        let name= inner.next().unwrap().as_str().to_owned();
-        let body = inner.next().unwrap();
+        let name= text_utils::unquote(inner.next().unwrap().as_str());
+        let mut color = None;
+        let mut body = inner.next().unwrap();
+        if body.as_rule() == Rule::color {
+            color = Some(theme::parse_color(body.as_str()));
+            body = inner.next().unwrap();
+        }
         for inner_pair in body.into_inner() {
             match inner_pair.as_rule() {
                 Rule::ATTRIBUTES => {
@@ -71,6 +85,8 @@ impl Class {
             name,
             attributes,
             methods,
+            color,
+            theme: Theme::default(),
             x: 0,
             y: 0,
             height: 0,
@@ -86,6 +102,11 @@ impl Class {
     pub fn get_y(&self) -> &usize {&self.y}
     pub fn get_height(&self) -> &usize {&self.height}
     pub fn get_width(&self) -> &usize {&self.width}
+    pub fn get_color(&self) -> &Option<String> {&self.color}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+    }
 
     pub fn print(&self) {
         tracing::info!("{} {}\n\
@@ -104,7 +125,7 @@ impl Class {
 
         let additional = if self.keyword != "class".to_owned() { 50 } else { 0 };
 
-        let name = svg::node::Text::new(self.name.as_str());
+        let name = svg::node::Text::new(text_utils::escape(&self.name));
 
         let mut i:usize = 0;
         let mut texts = Vec::new();
@@ -121,12 +142,12 @@ impl Class {
                 " : ".to_owned() + component.kind.as_str()
             };
             let content = vis.to_string() + " " + component.get_name() + &kind;
-            let new_width:usize = content.len()*15;
+            let new_width:usize = content.chars().count()*15;
             if new_width > width {
                 width = new_width;
             }
-            let text = svg::node::Text::new(content);
-            let attrib = add_text(text, i, x ,y + additional);
+            let text = svg::node::Text::new(text_utils::escape(&content));
+            let attrib = add_text(text, i, x ,y + additional, &self.theme);
             texts.push(attrib);
 
             i += 1;
@@ -142,12 +163,12 @@ impl Class {
                 " : ".to_owned() + method.kind.as_str()
             };
             let content = vis.to_string() + " " + method.get_name() + "()" + &kind;
-            let new_width:usize = content.len()*15;
+            let new_width:usize = content.chars().count()*15;
             if new_width > width {
                 width = new_width;
             }
-            let text = svg::node::Text::new(content);
-            let meth = add_text(text, i, x, y + additional);
+            let text = svg::node::Text::new(text_utils::escape(&content));
+            let meth = add_text(text, i, x, y + additional, &self.theme);
             texts.push(meth);
             i += 1;
         }
@@ -158,9 +179,9 @@ impl Class {
             .set("y", y)
             .set("width", width)
             .set("height", height)
-            .set("fill", "white")
-            .set("stroke", "black")
-            .set("stroke-width", 10);
+            .set("fill", self.color.clone().unwrap_or(self.theme.fill_color.clone()))
+            .set("stroke", self.theme.border_color.as_str())
+            .set("stroke-width", self.theme.class_border_width);
         *svg = svg.clone().add(rect);
 
         if self.keyword == "interface".to_owned() {
@@ -170,7 +191,7 @@ impl Class {
                 .set("y", y + 37)
                 .set("text-anchor", "middle")
                 .set("dominant-baseline", "central")
-                .set("fill", "black")
+                .set("fill", self.theme.font_color.as_str())
                 .set("font-size", 28)
                 .add(text);
             *svg = svg.clone().add(keyword);
@@ -181,7 +202,7 @@ impl Class {
             .set("y", y + 37+additional)
             .set("text-anchor", "middle")
             .set("dominant-baseline", "central")
-            .set("fill", "black")
+            .set("fill", self.theme.font_color.as_str())
             .set("font-size", 28)
             .add(name);
         *svg = svg.clone().add(name_label);
@@ -191,7 +212,7 @@ impl Class {
             .set("y1", y + 50 + additional)
             .set("x2", x + width)
             .set("y2", y + 50 + additional)
-            .set("stroke", "#000")
+            .set("stroke", self.theme.line_color.as_str())
             .set("stroke-width", 5);
         *svg = svg.clone().add(line);
 
@@ -200,7 +221,7 @@ impl Class {
             .set("y1", y + (i-self.attributes.len()+1)*50 + additional)
             .set("x2", x + width)
             .set("y2", y + ((i-self.attributes.len())+1)*50 + additional)
-            .set("stroke", "#000")
+            .set("stroke", self.theme.line_color.as_str())
             .set("stroke-width", 5);
         *svg = svg.clone().add(line_comp);
 
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::component::Component;
+use crate::rules::context;
+use crate::rules::graph_layout::{self, GraphLayout, Rect};
+use crate::rules::text_utils;
+use crate::rules::theme::{self, Theme};
+use svg::node::element::SVG;
+
+const PADDING: i32 = 30;
+// room for the title drawn above the frame
+const TITLE_HEIGHT: i32 = 30;
+const GAP_X: i32 = 80;
+const GAP_Y: i32 = 80;
+
+pub enum Element {
+    Component(Component),
+    Subsystem(Subsystem)
+}
+
+impl Element {
+    pub fn contains(&self, id: &String) -> bool {
+        match self {
+            Element::Component(component) => component.contains(id),
+            Element::Subsystem(subsystem) => subsystem.contains(id)
+        }
+    }
+
+    pub fn get_width(&self) -> i32 {
+        match self {
+            Element::Component(component) => component.get_width(),
+            Element::Subsystem(subsystem) => subsystem.get_width()
+        }
+    }
+
+    pub fn get_height(&self) -> i32 {
+        match self {
+            Element::Component(component) => component.get_height(),
+            Element::Subsystem(subsystem) => subsystem.get_height()
+        }
+    }
+}
+
+// Frame grouping components and other subsystems, the whole diagram is an untitled subsystem.
+pub struct Subsystem {
+    label: String,
+    color: Option<String>,
+    elements: Vec<Element>,
+    layout: Option<GraphLayout>,
+    theme: Theme
+}
+
+impl Subsystem {
+    pub fn new(value: Pair<Rule>) -> Subsystem {
+        let mut label = String::new();
+        let mut color = None;
+        let mut elements = Vec::new();
+
+        for inner_pair in value.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::label => label = text_utils::unquote(inner_pair.as_str()),
+                Rule::color => color = Some(theme::parse_color(inner_pair.as_str())),
+                Rule::COMPONENT_ELEMENTS => {
+                    for element in inner_pair.into_inner() {
+                        elements.push(match element.as_rule() {
+                            Rule::COMPONENT_BOX => Element::Component(Component::new(element)),
+                            Rule::SUBSYSTEM => Element::Subsystem(Subsystem::new(element)),
+                            _ => unreachable!()
+                        });
+                    }
+                }
+                _ => unreachable!()
+            }
+        }
+
+        Subsystem {
+            label,
+            color,
+            elements,
+            layout: None,
+            theme: Theme::default()
+        }
+    }
+
+    // Top level of the diagram, accepts the COMPONENT_ELEMENTS pair.
+    pub fn root(value: Pair<Rule>) -> Subsystem {
+        let elements = value.into_inner().map(|element| match element.as_rule() {
+            Rule::COMPONENT_BOX => Element::Component(Component::new(element)),
+            Rule::SUBSYSTEM => Element::Subsystem(Subsystem::new(element)),
+            _ => unreachable!()
+        }).collect();
+
+        Subsystem {
+            label: String::new(),
+            color: None,
+            elements,
+            layout: None,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_label(&self) -> &String {&self.label}
+
+    pub fn get_elements(&self) -> &Vec<Element> {&self.elements}
+
+    fn is_root(&self) -> bool {
+        self.label.is_empty()
+    }
+
+    pub fn contains(&self, id: &String) -> bool {
+        self.elements.iter().any(|e| e.contains(id))
+    }
+
+    // All the components, including the nested ones.
+    pub fn components(&self) -> Vec<&Component> {
+        let mut components = Vec::new();
+        for element in &self.elements {
+            match element {
+                Element::Component(component) => components.push(component),
+                Element::Subsystem(subsystem) => components.extend(subsystem.components())
+            }
+        }
+        components
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        for element in &mut self.elements {
+            match element {
+                Element::Component(component) => component.set_theme(theme),
+                Element::Subsystem(subsystem) => subsystem.set_theme(theme)
+            }
+        }
+    }
+
+    // Lays out the nested subsystems first, as their size is needed here.
+    pub fn layout(&mut self, edges: &[(String, String)]) {
+        for element in &mut self.elements {
+            if let Element::Subsystem(subsystem) = element {
+                subsystem.layout(edges);
+            }
+        }
+        let sizes: Vec<(i32, i32)> = self.elements.iter()
+            .map(|e| (e.get_width(), e.get_height()))
+            .collect();
+        let indices: Vec<(usize, usize)> = edges.iter().filter_map(|(from, to)| {
+            let from = self.elements.iter().position(|e| e.contains(from))?;
+            let to = self.elements.iter().position(|e| e.contains(to))?;
+            Some((from, to))
+        }).collect();
+        self.layout = Some(graph_layout::layout(&sizes, &indices, GAP_X, GAP_Y));
+    }
+
+    fn content_width(&self) -> i32 {
+        self.layout.as_ref().map(|l| l.width).unwrap_or(0)
+    }
+
+    fn content_height(&self) -> i32 {
+        self.layout.as_ref().map(|l| l.height).unwrap_or(0)
+    }
+
+    pub fn get_width(&self) -> i32 {
+        if self.is_root() {
+            return self.content_width();
+        }
+        let title = text_utils::text_width(&self.label, 20) as i32 + 20;
+        std::cmp::max(self.content_width() + 2 * PADDING, title)
+    }
+
+    pub fn get_height(&self) -> i32 {
+        if self.is_root() {
+            return self.content_height();
+        }
+        TITLE_HEIGHT + self.content_height() + 2 * PADDING
+    }
+
+    // (x, y) is the top left corner, rects collects the bounds of everything links can point at.
+    pub fn draw(&self, svg: &mut SVG, x: i32, y: i32, rects: &mut HashMap<String, Rect>) {
+        let (mut content_x, mut content_y) = (x, y);
+        if !self.is_root() {
+            let frame = Rect::new(x, y + TITLE_HEIGHT, self.get_width(), self.get_height() - TITLE_HEIGHT);
+            context::draw_frame(svg, &self.label, frame, &self.color, &self.theme);
+            content_x = x + (self.get_width() - self.content_width()) / 2;
+            content_y = y + TITLE_HEIGHT + PADDING;
+        }
+
+        if let Some(layout) = &self.layout {
+            for (element, (dx, dy)) in self.elements.iter().zip(&layout.positions) {
+                match element {
+                    Element::Component(component) => component.draw(svg, content_x + dx, content_y + dy, rects),
+                    Element::Subsystem(subsystem) => subsystem.draw(svg, content_x + dx, content_y + dy, rects)
+                }
+            }
+        }
+    }
+}
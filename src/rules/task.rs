@@ -0,0 +1,113 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::calendar::Date;
+
+// Longest duration of a task in working days, about ten years.
+const MAX_DURATION: i64 = 2600;
+
+// Where a task or a milestone is placed on the timeline.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Anchor {
+    // the task starts together with the project
+    Project,
+    Date(Date),
+    Start(String),
+    End(String)
+}
+
+impl Anchor {
+    // Accepts a date or the TASK_POINT pair, e.g. [Design]'s end.
+    fn new(value: Pair<Rule>) -> Anchor {
+        match value.as_rule() {
+            Rule::date => Anchor::Date(Date::parse(value.as_str()).unwrap_or_else(|e| panic!("{}", e))),
+            Rule::TASK_POINT => {
+                let mut inner = value.into_inner();
+                let name = task_name(inner.next().unwrap().as_str());
+                match inner.next().unwrap().as_rule() {
+                    Rule::start_kw => Anchor::Start(name),
+                    Rule::end_kw => Anchor::End(name),
+                    _ => unreachable!()
+                }
+            }
+            _ => unreachable!()
+        }
+    }
+
+    // Name of the task this one depends on.
+    pub fn get_task(&self) -> Option<&String> {
+        match self {
+            Anchor::Start(name) | Anchor::End(name) => Some(name),
+            _ => None
+        }
+    }
+}
+
+// Strips the square brackets around the name of a task.
+pub fn task_name(value: &str) -> String {
+    value.trim().trim_start_matches('[').trim_end_matches(']').trim().to_owned()
+}
+
+// Task of the gantt chart, built from all the statements about it.
+pub struct Task {
+    name: String,
+    anchor: Anchor,
+    // in working days
+    duration: i64,
+    milestone: bool,
+    resources: Vec<String>,
+    begin: Date,
+    end: Date
+}
+
+impl Task {
+    pub fn new(name: String) -> Task {
+        Task {
+            name,
+            anchor: Anchor::Project,
+            duration: 1,
+            milestone: false,
+            resources: Vec::new(),
+            begin: Date::default(),
+            end: Date::default()
+        }
+    }
+
+    // Applies one of the constraints of the TASK_STATEMENT: a duration, a start, a milestone or resources.
+    pub fn apply(&mut self, value: Pair<Rule>) {
+        match value.as_rule() {
+            Rule::DURATION => {
+                let days = value.into_inner().next().unwrap().as_str();
+                self.duration = days.parse().ok()
+                    .filter(|days| *days <= MAX_DURATION)
+                    .unwrap_or_else(|| panic!("Duration too long: {} days", days));
+            }
+            Rule::TASK_START => self.anchor = Anchor::new(value.into_inner().next().unwrap()),
+            Rule::MILESTONE => {
+                self.milestone = true;
+                self.duration = 0;
+                self.anchor = Anchor::new(value.into_inner().next().unwrap());
+            }
+            Rule::RESOURCES => {
+                for resource in value.into_inner() {
+                    let name = resource.as_str().trim_start_matches('{').trim_end_matches('}').trim();
+                    self.resources.push(name.to_owned());
+                }
+            }
+            _ => unreachable!()
+        }
+    }
+
+    pub fn get_name(&self) -> &String {&self.name}
+    pub fn get_anchor(&self) -> &Anchor {&self.anchor}
+    pub fn get_duration(&self) -> i64 {self.duration}
+    pub fn is_milestone(&self) -> bool {self.milestone}
+    pub fn get_resources(&self) -> &Vec<String> {&self.resources}
+    pub fn get_begin(&self) -> Date {self.begin}
+    // The first day after the task, a milestone ends on the day it happens.
+    pub fn get_end(&self) -> Date {self.end}
+
+    pub fn set_span(&mut self, begin: Date, end: Date) {
+        self.begin = begin;
+        self.end = end;
+    }
+}
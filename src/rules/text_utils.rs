@@ -0,0 +1,103 @@
+use svg::node::{
+    element::{
+        TSpan, Text as TextElement
+    },
+    Text
+};
+
+// average glyph width relative to the font size
+const CHAR_WIDTH_RATIO: f64 = 0.6;
+// distance between baselines of two consecutive lines relative to the font size
+const LINE_HEIGHT_RATIO: f64 = 1.2;
+
+// Removes the surrounding quotes from a label and resolves the `\n`, `\"` and `\\` escape sequences.
+pub fn unquote(label: &str) -> String {
+    let trimmed = label.trim();
+    if !(trimmed.len() >= 2 && trimmed.starts_with('"') && trimmed.ends_with('"')) {
+        return trimmed.to_string();
+    }
+
+    let mut result = String::new();
+    let mut chars = trimmed[1..trimmed.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some(escaped) => result.push(escaped),
+            None => result.push(c)
+        }
+    }
+
+    result
+}
+
+// Escapes the characters with a special meaning in the SVG markup.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+pub fn line_height(text_size: i32) -> f64 {
+    text_size as f64 * LINE_HEIGHT_RATIO
+}
+
+// Estimated width of the widest line of the text.
+pub fn text_width(text: &str, text_size: i32) -> f64 {
+    text.split('\n')
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0) as f64 * text_size as f64 * CHAR_WIDTH_RATIO
+}
+
+// Height of the block taken by the given number of lines.
+pub fn text_height(lines: usize, text_size: i32) -> f64 {
+    lines.max(1) as f64 * line_height(text_size)
+}
+
+// Splits the label on explicit line breaks and wraps every line so it fits into max_width.
+// Words longer than max_width are left on their own line.
+pub fn wrap(label: &str, max_width: f64, text_size: i32) -> Vec<String> {
+    let max_chars = std::cmp::max(1, (max_width / (text_size as f64 * CHAR_WIDTH_RATIO)) as usize);
+    let mut lines = Vec::new();
+
+    for paragraph in label.split('\n') {
+        let mut line = String::new();
+        for word in paragraph.split(' ').filter(|w| !w.is_empty()) {
+            if line.is_empty() {
+                line = word.to_string();
+            } else if line.chars().count() + 1 + word.chars().count() <= max_chars {
+                line.push(' ');
+                line.push_str(word);
+            } else {
+                lines.push(line);
+                line = word.to_string();
+            }
+        }
+        lines.push(line);
+    }
+
+    lines
+}
+
+// Adds the lines to the text element, vertically centered around y.
+// A single line is added as plain text, more lines are emitted as <tspan> elements.
+pub fn add_lines(element: TextElement, lines: &[String], x: f64, y: f64, text_size: i32) -> TextElement {
+    if lines.len() <= 1 {
+        return element.add(Text::new(escape(lines.first().map(String::as_str).unwrap_or_default())));
+    }
+
+    let step = line_height(text_size);
+    let first_y = y - step * (lines.len() - 1) as f64 / 2.0;
+    lines.iter().enumerate().fold(element, |element, (i, line)| {
+        element.add(
+            TSpan::new()
+                .set("x", x)
+                .set("y", first_y + step * i as f64)
+                .add(Text::new(escape(line)))
+        )
+    })
+}
@@ -0,0 +1,127 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub background_color: String,
+    pub font_family: String,
+    pub font_color: String,
+    pub line_color: String,
+    pub line_width: usize,
+    pub fill_color: String,
+    pub border_color: String,
+    pub class_border_width: usize,
+    pub use_case_color: String,
+    pub context_color: String
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::light()
+    }
+}
+
+impl Theme {
+    pub fn light() -> Theme {
+        Theme {
+            background_color: "white".to_string(),
+            font_family: "Arial".to_string(),
+            font_color: "black".to_string(),
+            line_color: "#000".to_string(),
+            line_width: 3,
+            fill_color: "white".to_string(),
+            border_color: "black".to_string(),
+            class_border_width: 10,
+            use_case_color: "blue".to_string(),
+            context_color: "gray".to_string()
+        }
+    }
+
+    pub fn dark() -> Theme {
+        Theme {
+            background_color: "#1e1e1e".to_string(),
+            font_family: "Arial".to_string(),
+            font_color: "#e0e0e0".to_string(),
+            line_color: "#c8c8c8".to_string(),
+            line_width: 3,
+            fill_color: "#2d2d30".to_string(),
+            border_color: "#c8c8c8".to_string(),
+            class_border_width: 10,
+            use_case_color: "#3a6ea5".to_string(),
+            context_color: "#888888".to_string()
+        }
+    }
+
+    // Black and white theme, meant for printing.
+    pub fn monochrome() -> Theme {
+        Theme {
+            background_color: "white".to_string(),
+            font_family: "Times New Roman".to_string(),
+            font_color: "black".to_string(),
+            line_color: "black".to_string(),
+            line_width: 3,
+            fill_color: "white".to_string(),
+            border_color: "black".to_string(),
+            class_border_width: 6,
+            use_case_color: "white".to_string(),
+            context_color: "black".to_string()
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Theme> {
+        match name.to_lowercase().as_str() {
+            "light" => Some(Theme::light()),
+            "dark" => Some(Theme::dark()),
+            "monochrome" => Some(Theme::monochrome()),
+            _ => None
+        }
+    }
+
+    // Overrides a single value, keys are case insensitive like in PlantUML's skinparam.
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match key.to_lowercase().as_str() {
+            "theme" => {
+                *self = Theme::preset(value).ok_or(format!("Unknown theme: {}", value))?;
+            }
+            "backgroundcolor" => self.background_color = parse_color(value),
+            "fontname" => self.font_family = value.replace('"', ""),
+            "fontcolor" => self.font_color = parse_color(value),
+            "linecolor" | "arrowcolor" => self.line_color = parse_color(value),
+            "linethickness" => self.line_width = parse_width(value)?,
+            "fillcolor" => self.fill_color = parse_color(value),
+            "bordercolor" => self.border_color = parse_color(value),
+            "classborderthickness" => self.class_border_width = parse_width(value)?,
+            "usecasecolor" => self.use_case_color = parse_color(value),
+            "contextcolor" => self.context_color = parse_color(value),
+            _ => return Err(format!("Unknown skinparam: {}", key))
+        }
+        Ok(())
+    }
+
+    // Applies the entries of a SKINPARAM rule.
+    pub fn apply(&mut self, value: Pair<Rule>) -> Result<(), String> {
+        for entry in value.into_inner() {
+            let mut inner = entry.into_inner();
+            let key = inner.next().unwrap().as_str();
+            let value = inner.next().unwrap().as_str();
+            self.set(key, value)?;
+        }
+        Ok(())
+    }
+}
+
+// Converts the color written in the diagram source (`#lightblue`, `#FF0000`, `red`) into an SVG color.
+pub fn parse_color(value: &str) -> String {
+    let color = value.trim().trim_start_matches('#');
+    let is_hex = (color.len() == 3 || color.len() == 6) && color.chars().all(|c| c.is_ascii_hexdigit());
+    if is_hex {
+        format!("#{}", color)
+    } else {
+        color.to_string()
+    }
+}
+
+fn parse_width(value: &str) -> Result<usize, String> {
+    value.parse().map_err(|_| format!("Invalid thickness: {}", value))
+}
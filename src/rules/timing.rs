@@ -0,0 +1,263 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::signal::{Signal, TimeScale};
+use crate::rules::text_utils;
+use crate::rules::theme::Theme;
+use svg::node::element::{
+    SVG, Line, Polygon, Text as TextElement
+};
+
+const MARGIN: i32 = 30;
+const TEXT_SIZE: i32 = 14;
+const ROW_GAP: i32 = 25;
+const CONSTRAINT_HEIGHT: i32 = 35;
+const AXIS_HEIGHT: i32 = 35;
+// average width between two consecutive time points
+const STEP_WIDTH: f64 = 120.0;
+
+// Duration between two time points, e.g. @100 <-> @300 : {200ms}
+pub struct TimeConstraint {
+    alias: Option<String>,
+    from: i64,
+    to: i64,
+    label: String
+}
+
+impl TimeConstraint {
+    pub fn new(value: Pair<Rule>) -> TimeConstraint {
+        let mut alias = None;
+        let mut times = Vec::new();
+        let mut label = String::new();
+        for inner_pair in value.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::identifier => alias = Some(inner_pair.as_str().to_owned()),
+                Rule::number => times.push(inner_pair.as_str().parse::<i64>().unwrap()),
+                Rule::text => label = inner_pair.as_str().trim().to_owned(),
+                Rule::colon => {}
+                _ => unreachable!()
+            }
+        }
+
+        TimeConstraint {
+            alias,
+            from: std::cmp::min(times[0], times[1]),
+            to: std::cmp::max(times[0], times[1]),
+            label
+        }
+    }
+
+    // The lifeline the constraint is drawn under, the others go below the time axis.
+    pub fn get_alias(&self) -> &Option<String> {&self.alias}
+    pub fn get_from(&self) -> i64 {self.from}
+    pub fn get_to(&self) -> i64 {self.to}
+    pub fn get_label(&self) -> &String {&self.label}
+
+    // Double headed arrow with the label above it.
+    fn draw(&self, svg: &mut SVG, y: f64, time: TimeScale, theme: &Theme) {
+        let (x1, x2) = (time.x(self.from), time.x(self.to));
+        let line = Line::new()
+            .set("x1", x1)
+            .set("y1", y)
+            .set("x2", x2)
+            .set("y2", y)
+            .set("stroke", theme.line_color.as_str())
+            .set("stroke-width", 1);
+        *svg = svg.clone().add(line);
+        for (tip, direction) in [(x1, 1.0), (x2, -1.0)] {
+            let head = Polygon::new()
+                .set("points", format!("{},{} {},{} {},{}", tip, y, tip + 8.0 * direction, y - 4.0, tip + 8.0 * direction, y + 4.0))
+                .set("fill", theme.line_color.as_str());
+            *svg = svg.clone().add(head);
+        }
+        let text = TextElement::new()
+            .set("x", (x1 + x2) / 2.0)
+            .set("y", y - 10.0)
+            .set("text-anchor", "middle")
+            .set("dominant-baseline", "central")
+            .set("fill", theme.font_color.as_str())
+            .set("font-size", TEXT_SIZE)
+            .add(svg::node::Text::new(text_utils::escape(&self.label)));
+        *svg = svg.clone().add(text);
+    }
+}
+
+pub struct Timing {
+    signals: Vec<Signal>,
+    constraints: Vec<TimeConstraint>,
+    // time points of the state changes and the constraints, sorted
+    times: Vec<i64>,
+    theme: Theme
+}
+
+impl Timing {
+    // Accepts the TIMING_DIAGRAM pair, state changes belong to the last time point above them.
+    pub fn new(value: Pair<Rule>) -> Timing {
+        let mut signals: Vec<Signal> = Vec::new();
+        let mut constraints = Vec::new();
+        let mut times = Vec::new();
+        let mut now = None;
+
+        for inner_pair in value.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::TIMING_LINE | Rule::CLOCK => {
+                    let signal = Signal::new(inner_pair);
+                    if signals.iter().any(|s| s.get_alias() == signal.get_alias()) {
+                        panic!("Duplicate name/alias found: {}", signal.get_alias());
+                    }
+                    signals.push(signal);
+                }
+                Rule::TIME_POINT => {
+                    let time = inner_pair.into_inner().next().unwrap().as_str().parse().unwrap();
+                    now = Some(time);
+                    times.push(time);
+                }
+                Rule::STATE_CHANGE => {
+                    let mut inner = inner_pair.into_inner();
+                    let alias = inner.next().unwrap().as_str();
+                    let state = text_utils::unquote(inner.next().unwrap().as_str());
+                    let time = now.unwrap_or_else(|| panic!("Time point missing before the state change of {}", alias));
+                    signals.iter_mut()
+                        .find(|s| s.get_alias() == alias)
+                        .unwrap_or_else(|| panic!("Alias not found: {}", alias))
+                        .add_change(time, state);
+                }
+                Rule::TIME_CONSTRAINT => {
+                    let constraint = TimeConstraint::new(inner_pair);
+                    if let Some(alias) = constraint.get_alias() {
+                        if !signals.iter().any(|s| s.get_alias() == alias) {
+                            panic!("Alias not found: {}", alias);
+                        }
+                    }
+                    times.push(constraint.get_from());
+                    times.push(constraint.get_to());
+                    constraints.push(constraint);
+                }
+                _ => {}
+            }
+        }
+        times.sort();
+        times.dedup();
+
+        Timing {
+            signals,
+            constraints,
+            times,
+            theme: Theme::default()
+        }
+    }
+
+    pub fn get_signals(&self) -> &Vec<Signal> {&self.signals}
+
+    pub fn get_constraints(&self) -> &Vec<TimeConstraint> {&self.constraints}
+
+    // All the time points of the diagram, sorted.
+    pub fn get_times(&self) -> &Vec<i64> {&self.times}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        for signal in &mut self.signals {
+            signal.set_theme(theme);
+        }
+    }
+
+    // The plot goes one average step past the last time point, so the last states are visible.
+    pub fn time_scale(&self) -> TimeScale {
+        let start = self.times.first().copied().unwrap_or(0);
+        let last = self.times.last().copied().unwrap_or(0);
+        let steps = std::cmp::max(self.times.len() as i64 - 1, 1);
+        let step = std::cmp::max((last - start) / steps, 1);
+        let end = last + step;
+        let left = MARGIN + self.signals.iter().map(|s| s.label_width()).max().unwrap_or(0) + 20;
+        TimeScale {
+            left,
+            start,
+            end,
+            scale: STEP_WIDTH * (steps + 1) as f64 / (end - start) as f64
+        }
+    }
+
+    fn constraints_of(&self, alias: &String) -> Vec<&TimeConstraint> {
+        self.constraints.iter()
+            .filter(|c| c.get_alias().as_ref() == Some(alias))
+            .collect()
+    }
+
+    fn global_constraints(&self) -> Vec<&TimeConstraint> {
+        self.constraints.iter()
+            .filter(|c| c.get_alias().is_none())
+            .collect()
+    }
+
+    fn row_height(&self, signal: &Signal) -> i32 {
+        signal.get_height() + ROW_GAP + self.constraints_of(signal.get_alias()).len() as i32 * CONSTRAINT_HEIGHT
+    }
+
+    fn axis_y(&self) -> i32 {
+        MARGIN + self.signals.iter().map(|s| self.row_height(s)).sum::<i32>()
+    }
+
+    fn draw_axis(&self, svg: &mut SVG, time: TimeScale) {
+        let y = self.axis_y();
+        let axis = Line::new()
+            .set("x1", time.x(time.start))
+            .set("y1", y)
+            .set("x2", time.x(time.end))
+            .set("y2", y)
+            .set("stroke", self.theme.line_color.as_str())
+            .set("stroke-width", 2);
+        *svg = svg.clone().add(axis);
+
+        for t in &self.times {
+            let x = time.x(*t);
+            // the time points are marked across all the lifelines
+            let guide = Line::new()
+                .set("x1", x)
+                .set("y1", MARGIN)
+                .set("x2", x)
+                .set("y2", y + 6)
+                .set("stroke", self.theme.line_color.as_str())
+                .set("stroke-width", 1)
+                .set("stroke-dasharray", "2 4");
+            let label = TextElement::new()
+                .set("x", x)
+                .set("y", y + 18)
+                .set("text-anchor", "middle")
+                .set("dominant-baseline", "central")
+                .set("fill", self.theme.font_color.as_str())
+                .set("font-size", TEXT_SIZE)
+                .add(svg::node::Text::new(t.to_string()));
+            *svg = svg.clone().add(guide).add(label);
+        }
+    }
+
+    pub fn draw(&self, svg: &mut SVG) {
+        let time = self.time_scale();
+        self.draw_axis(svg, time);
+
+        let mut y = MARGIN;
+        for signal in &self.signals {
+            signal.draw(svg, MARGIN, y, time);
+            let mut constraint_y = y + signal.get_height() + CONSTRAINT_HEIGHT - 5;
+            for constraint in self.constraints_of(signal.get_alias()) {
+                constraint.draw(svg, constraint_y as f64, time, &self.theme);
+                constraint_y += CONSTRAINT_HEIGHT;
+            }
+            y += self.row_height(signal);
+        }
+
+        let mut constraint_y = self.axis_y() + AXIS_HEIGHT + CONSTRAINT_HEIGHT - 5;
+        for constraint in self.global_constraints() {
+            constraint.draw(svg, constraint_y as f64, time, &self.theme);
+            constraint_y += CONSTRAINT_HEIGHT;
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        let time = self.time_scale();
+        (time.x(time.end) as i32 + MARGIN) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        (self.axis_y() + AXIS_HEIGHT + self.global_constraints().len() as i32 * CONSTRAINT_HEIGHT + MARGIN) as usize
+    }
+}
@@ -0,0 +1,170 @@
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::graph_layout::Rect;
+use crate::rules::text_utils;
+use crate::rules::theme::Theme;
+use svg::node::element::{
+    SVG, Line, Path, Polygon, Text as TextElement
+};
+
+const TEXT_SIZE: i32 = 16;
+const LABEL_MAX_WIDTH: f64 = 220.0;
+
+pub struct Transition {
+    from: String,
+    to: String,
+    event: String,
+    guard: String,
+    action: String
+}
+
+impl Transition {
+    // scope identifies the region the transition is written in, owner is the enclosing state
+    pub fn new(value: Pair<Rule>, scope: &str, owner: &str) -> Transition {
+        let mut inner = value.into_inner();
+        let from = Transition::endpoint(inner.next().unwrap(), scope, owner);
+        inner.next(); // skip arrow
+        let to = Transition::endpoint(inner.next().unwrap(), scope, owner);
+        inner.next(); // skip colon
+
+        let (event, guard, action) = match inner.next() {
+            Some(text) => Transition::parse_label(text.as_str()),
+            None => (String::new(), String::new(), String::new())
+        };
+
+        Transition {
+            from,
+            to,
+            event,
+            guard,
+            action
+        }
+    }
+
+    // Pseudo-states get ids unique for the region or state they belong to.
+    fn endpoint(value: Pair<Rule>, scope: &str, owner: &str) -> String {
+        let mut inner = value.into_inner();
+        let first = inner.next().unwrap();
+        match first.as_rule() {
+            Rule::start_state => format!("{}(*)", scope),
+            Rule::end_state => format!("{}(^)", scope),
+            Rule::history => format!("{}{}", owner, first.as_str()),
+            Rule::identifier => match inner.next() {
+                Some(history) => format!("{}{}", first.as_str(), history.as_str()),
+                None => first.as_str().to_owned()
+            },
+            _ => unreachable!()
+        }
+    }
+
+    // Splits 'event [guard] / action', every part is optional.
+    pub fn parse_label(label: &str) -> (String, String, String) {
+        let (trigger, action) = match label.split_once('/') {
+            Some((trigger, action)) => (trigger, action.trim().to_owned()),
+            None => (label, String::new())
+        };
+        let (event, guard) = match (trigger.find('['), trigger.rfind(']')) {
+            (Some(open), Some(close)) if open < close => {
+                let event = format!("{} {}", &trigger[..open], &trigger[close+1..]);
+                (event.trim().to_owned(), trigger[open+1..close].trim().to_owned())
+            }
+            _ => (trigger.trim().to_owned(), String::new())
+        };
+        (event, guard, action)
+    }
+
+    pub fn get_from(&self) -> &String {&self.from}
+
+    pub fn get_to(&self) -> &String {&self.to}
+
+    pub fn get_event(&self) -> &String {&self.event}
+
+    pub fn get_guard(&self) -> &String {&self.guard}
+
+    pub fn get_action(&self) -> &String {&self.action}
+
+    pub fn get_label(&self) -> String {
+        let mut label = self.event.clone();
+        if !self.guard.is_empty() {
+            label = format!("{} [{}]", label, self.guard);
+        }
+        if !self.action.is_empty() {
+            label = format!("{} / {}", label, self.action);
+        }
+        label.trim().to_owned()
+    }
+
+    fn draw_arrowhead(svg: &mut SVG, tip: (f64, f64), from: (f64, f64), theme: &Theme) {
+        let angle = (tip.1 - from.1).atan2(tip.0 - from.0);
+        let (length, spread) = (14.0, 0.4);
+        let left = (tip.0 - length * (angle - spread).cos(), tip.1 - length * (angle - spread).sin());
+        let right = (tip.0 - length * (angle + spread).cos(), tip.1 - length * (angle + spread).sin());
+        let head = Polygon::new()
+            .set("points", format!("{:.1},{:.1} {:.1},{:.1} {:.1},{:.1}", left.0, left.1, tip.0, tip.1, right.0, right.1))
+            .set("fill", theme.line_color.as_str())
+            .set("stroke", theme.line_color.as_str());
+        *svg = svg.clone().add(head);
+    }
+
+    // Transitions going down are straight lines, the others bend around the right side.
+    pub fn draw(&self, svg: &mut SVG, from: Rect, to: Rect, theme: &Theme) {
+        let (start, end, control);
+        let (from_x, from_y) = from.center();
+        let (to_x, to_y) = to.center();
+
+        if from == to {
+            let right = (from.x + from.width) as f64;
+            start = (right, from_y - from.height as f64 / 4.0);
+            end = (right, from_y + from.height as f64 / 4.0);
+            control = Some((right + 70.0, from_y));
+        } else if to_y > from_y + 1.0 {
+            start = from.border_point((to_x, to_y));
+            end = to.border_point((from_x, from_y));
+            control = None;
+        } else {
+            start = ((from.x + from.width) as f64, from_y);
+            end = ((to.x + to.width) as f64, to_y);
+            control = Some((f64::max(start.0, end.0) + 80.0, (start.1 + end.1) / 2.0));
+        }
+
+        let (label_x, label_y);
+        match control {
+            Some(c) => {
+                let path = Path::new()
+                    .set("d", format!("M {:.1} {:.1} Q {:.1} {:.1} {:.1} {:.1}", start.0, start.1, c.0, c.1, end.0, end.1))
+                    .set("fill", "none")
+                    .set("stroke", theme.line_color.as_str())
+                    .set("stroke-width", 2);
+                *svg = svg.clone().add(path);
+                Transition::draw_arrowhead(svg, end, c, theme);
+                label_x = (start.0 + 2.0 * c.0 + end.0) / 4.0 + 5.0;
+                label_y = (start.1 + 2.0 * c.1 + end.1) / 4.0;
+            }
+            None => {
+                let line = Line::new()
+                    .set("x1", start.0)
+                    .set("y1", start.1)
+                    .set("x2", end.0)
+                    .set("y2", end.1)
+                    .set("stroke", theme.line_color.as_str())
+                    .set("stroke-width", 2);
+                *svg = svg.clone().add(line);
+                Transition::draw_arrowhead(svg, end, start, theme);
+                label_x = (start.0 + end.0) / 2.0 + 8.0;
+                label_y = (start.1 + end.1) / 2.0;
+            }
+        }
+
+        let label = self.get_label();
+        if !label.is_empty() {
+            let lines = text_utils::wrap(&label, LABEL_MAX_WIDTH, TEXT_SIZE);
+            let text = TextElement::new()
+                .set("x", label_x)
+                .set("y", label_y)
+                .set("dominant-baseline", "central")
+                .set("fill", theme.font_color.as_str())
+                .set("font-size", TEXT_SIZE);
+            *svg = svg.clone().add(text_utils::add_lines(text, &lines, label_x, label_y, TEXT_SIZE));
+        }
+    }
+}
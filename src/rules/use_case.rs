@@ -1,20 +1,29 @@
 use pest::iterators::Pair;
 use crate::grammar_parser::Rule;
-use svg::node::{
-    element::{
-        SVG, Ellipse, Text as TextElement
-    },
-    Text
+use crate::rules::text_utils;
+use crate::rules::theme::{self, Theme};
+use svg::node::element::{
+    SVG, Ellipse, Line, Text as TextElement
 };
 
+const LABEL_MAX_WIDTH: f64 = 200.0;
+const TEXT_SIZE: i32 = 20;
+const POINTS_TEXT_SIZE: i32 = 16;
+// smallest ellipse, longer labels make it bigger
+pub const MIN_WIDTH: i32 = 100;
+pub const MIN_HEIGHT: i32 = 50;
+
 #[derive(Debug, PartialEq, Eq)]
 pub struct UseCase {
     label: String,
     alias: String,
+    color: Option<String>,
+    extension_points: Vec<String>,
+    theme: Theme,
     x: i32,
     y: i32,
-    width_number: i32,
-    width: i32
+    width: i32,
+    height: i32
 }
 
 impl UseCase {
@@ -28,37 +37,62 @@ impl UseCase {
 
         match l.as_rule() {
             Rule::label => {
-                label = l.as_str().replace("\"", "").to_owned();
-                alias = String::from(l.as_str().replace("\"", "").to_owned());
+                label = text_utils::unquote(l.as_str());
+                alias = label.clone();
             },
             Rule::ALIAS => {
                 let mut inner2 = l.into_inner();
-                label = inner2.next().unwrap().as_str().to_owned().replace("\"", "");
+                label = text_utils::unquote(inner2.next().unwrap().as_str());
 
                 inner2.next(); // skip 'as'
                 alias = inner2.next().unwrap().as_str().to_owned().replace("\"", "");
             }
             _ => unreachable!()
         }
+        let mut color = None;
+        let mut extension_points = Vec::new();
+        for pair in inner {
+            match pair.as_rule() {
+                Rule::color => color = Some(theme::parse_color(pair.as_str())),
+                Rule::EXTENSION_POINTS => {
+                    extension_points = pair.into_inner()
+                        .map(|point| text_utils::unquote(point.as_str()))
+                        .collect();
+                }
+                _ => unreachable!()
+            }
+        }
         let x = -1;
         let y = -1;
-        let width_number = 1;
-        let width = 100;
 
-        UseCase {
+        let mut use_case = UseCase {
             label,
             alias,
+            color,
+            extension_points,
+            theme: Theme::default(),
             x,
             y,
-            width_number,
-            width
-        }
+            width: MIN_WIDTH,
+            height: MIN_HEIGHT
+        };
+        // the size is known before drawing so the use cases can be laid out
+        use_case.fit(MIN_WIDTH, MIN_HEIGHT);
+        use_case
     }
 
     pub fn get_use_case_label(&self) -> &String {&self.label}
 
     pub fn get_use_case_alias(&self) -> &String {&self.alias}
 
+    pub fn get_color(&self) -> &Option<String> {&self.color}
+
+    pub fn get_extension_points(&self) -> &Vec<String> {&self.extension_points}
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+    }
+
     pub fn get_x(&self) -> i32 {
         self.x
     }
@@ -71,50 +105,121 @@ impl UseCase {
         self.width
     }
 
-    pub fn get_width_number(&self) -> i32 {
-        self.width_number
+    pub fn get_height(&self) -> i32 {
+        self.height
     }
 
-    pub fn set_width_number(&mut self, width: i32) {
-        self.width_number = width;
+    pub fn print(&self) {
+        tracing::info!("Use Case label: {:?} Use Case alias: {:?} Extension points: {:?} x: {} y: {} width: {} height: {}",
+                 self.label, self.alias, self.extension_points, self.x, self.y, self.width, self.height);
     }
 
-    pub fn print(&self) {
-        tracing::info!("Use Case label: {:?} Use Case alias: {:?} x: {} y: {} width_number: {}",
-                 self.label, self.alias, self.x, self.y, self.width_number);
+    fn lines(&self) -> Vec<String> {
+        text_utils::wrap(&self.label, LABEL_MAX_WIDTH, TEXT_SIZE)
+    }
+
+    // Height of the label and of the compartment of the extension points.
+    fn text_heights(&self) -> (f64, f64) {
+        let points = self.points_lines();
+        let label_height = text_utils::text_height(self.lines().len(), TEXT_SIZE);
+        let points_height = if points.is_empty() { 0.0 } else { 10.0 + text_utils::text_height(points.len(), POINTS_TEXT_SIZE) };
+        (label_height, points_height)
+    }
+
+    // Sets the size of the ellipse holding the text, at least width x height, and returns its radii.
+    fn fit(&mut self, width: i32, height: i32) -> (f64, f64) {
+        let lines = self.lines();
+        let points = self.points_lines();
+        let text_width = lines.iter()
+            .map(|line| text_utils::text_width(line, TEXT_SIZE))
+            .chain(points.iter().map(|line| text_utils::text_width(line, POINTS_TEXT_SIZE)))
+            .fold(0.0, f64::max);
+        let (label_height, points_height) = self.text_heights();
+        let text_height = label_height + points_height;
+
+        let mut ellipse_width = f64::max(width as f64, text_width + 20.0); // Add some padding
+        let mut ellipse_height = height as f64;
+        if lines.len() > 1 || !points.is_empty() {
+            // the corners of the wrapped text block must stay inside the ellipse
+            ellipse_width = f64::max(ellipse_width, text_width * std::f64::consts::SQRT_2 + 20.0);
+            ellipse_height = f64::max(ellipse_height, text_height * std::f64::consts::SQRT_2 + 10.0);
+        }
+        self.width = ellipse_width as i32;
+        self.height = ellipse_height as i32;
+        (ellipse_width / 2.0, (self.height / 2) as f64)
     }
 
 
     pub fn draw(&mut self, svg: &mut SVG, x: i32, y: i32, width: i32, height: i32) {
         self.x = x; // middle of the ellipse
         self.y = y; // middle of the ellipse
-        let text_size = 20;
-        let text_element = TextElement::new()
+        let text_size = TEXT_SIZE;
+        let lines = self.lines();
+        let points = self.points_lines();
+        let (rx, ry) = self.fit(width, height);
+
+        // the label moves up to make room for the compartment of the extension points
+        let (label_height, points_height) = self.text_heights();
+        let top = y as f64 - (label_height + points_height) / 2.0;
+        let label_y = if points.is_empty() { (y + text_size / 3) as f64 } else { top + label_height / 2.0 };
+
+        let mut text_element = TextElement::new()
             .set("x", (x).to_string())
-            .set("y", (y + text_size / 3).to_string())
+            .set("y", label_y.to_string())
             .set("text-anchor", "middle")
             .set("dominant-baseline", "central")
-            .set("fill", "black")
-            .set("font-family", "Arial")
-            .set("font-size", text_size)
-            .add(Text::new(self.label.clone().as_str()));
-
-        let text_width = self.label.len() as f64 * (text_size as f64 * 0.6); // Adjust the multiplier as needed
-
-        let ellipse_width = f64::max(width as f64, text_width + 20.0); // Add some padding
-        self.width = ellipse_width as i32;
+            .set("fill", self.theme.font_color.as_str())
+            .set("font-size", text_size);
+        text_element = text_utils::add_lines(text_element, &lines, x as f64, label_y, text_size);
 
         let ellipse = Ellipse::new()
             .set("cx", (x).to_string())
             .set("cy", (y).to_string())
-            .set("rx", (ellipse_width / 2.0).to_string()) // Adjusted width
-            .set("ry", (height / 2).to_string())
-            .set("fill", "blue") // Blue color
+            .set("rx", rx.to_string()) // Adjusted width
+            .set("ry", ry.to_string())
+            .set("fill", self.color.clone().unwrap_or(self.theme.use_case_color.clone()))
             .set("fill-opacity", "0.2") // Very transparent
-            .set("stroke", "black")
+            .set("stroke", self.theme.border_color.as_str())
             .set("stroke-width", "2");
 
         *svg = svg.clone().add(ellipse);
         *svg = svg.clone().add(text_element);
+
+        if !points.is_empty() {
+            let separator_y = top + label_height + 5.0;
+            // the separator ends on the outline of the ellipse
+            let dy = (separator_y - y as f64) / ry;
+            let half_chord = rx * f64::max(1.0 - dy * dy, 0.0).sqrt();
+            let separator = Line::new()
+                .set("x1", x as f64 - half_chord)
+                .set("y1", separator_y)
+                .set("x2", x as f64 + half_chord)
+                .set("y2", separator_y)
+                .set("stroke", self.theme.border_color.as_str())
+                .set("stroke-width", "2");
+
+            let points_y = separator_y + 5.0 + text_utils::text_height(points.len(), POINTS_TEXT_SIZE) / 2.0;
+            let mut points_element = TextElement::new()
+                .set("x", x)
+                .set("y", points_y)
+                .set("text-anchor", "middle")
+                .set("dominant-baseline", "central")
+                .set("fill", self.theme.font_color.as_str())
+                .set("font-size", POINTS_TEXT_SIZE);
+            points_element = text_utils::add_lines(points_element, &points, x as f64, points_y, POINTS_TEXT_SIZE);
+
+            *svg = svg.clone().add(separator);
+            *svg = svg.clone().add(points_element);
+        }
+    }
+
+    // The title of the compartment followed by the extension points, empty without them.
+    fn points_lines(&self) -> Vec<String> {
+        if self.extension_points.is_empty() {
+            return Vec::new();
+        }
+        std::iter::once("extension points".to_string())
+            .chain(self.extension_points.iter().cloned())
+            .collect()
     }
 }
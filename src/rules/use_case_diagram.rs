@@ -0,0 +1,326 @@
+use std::collections::HashSet;
+use pest::iterators::Pair;
+use crate::grammar_parser::Rule;
+use crate::rules::actor::{Actor, Side};
+use crate::rules::context::Context;
+use crate::rules::graph_layout::Rect;
+use crate::rules::link::{Link, Stereotype};
+use crate::rules::use_case::UseCase;
+use crate::rules::theme::Theme;
+use svg::node::element::SVG;
+
+const MARGIN: i32 = 40;
+const ACTOR_SIZE: i32 = 20;
+// room for the labels of the links between the actors and the contexts
+const ACTOR_GAP_X: i32 = 120;
+const ACTOR_GAP_Y: i32 = ACTOR_SIZE;
+// generalizations between the actors need room for the arrow
+const GENERALIZATION_GAP_Y: i32 = 4 * ACTOR_SIZE;
+// the title of the context is drawn above its frame
+const TITLE_HEIGHT: i32 = 30;
+const CONTEXT_GAP: i32 = 40 + TITLE_HEIGHT;
+
+pub struct UseCaseDiagram {
+    actors: Vec<Actor>,
+    contexts: Vec<Context>,
+    links: Vec<Link>,
+    sides: Vec<Side>,
+    // middles of the heads of the actors and top left corners of the contexts
+    actor_positions: Vec<(i32, i32)>,
+    context_positions: Vec<(i32, i32)>,
+    width: i32,
+    height: i32,
+    theme: Theme
+}
+
+impl UseCaseDiagram {
+    // Accepts the USE_CASE_DIAGRAM pair.
+    pub fn new(value: Pair<Rule>) -> UseCaseDiagram {
+        let mut actors = Vec::new();
+        let mut contexts = Vec::new();
+        let mut free_use_cases = Vec::new();
+        let mut links = Vec::new();
+
+        for inner_pair in value.into_inner() {
+            match inner_pair.as_rule() {
+                Rule::ACTOR => actors.push(Actor::new(inner_pair)),
+                Rule::CONTEXT => contexts.push(Context::new(inner_pair)),
+                Rule::USE_CASE => free_use_cases.push(UseCase::new(inner_pair)),
+                Rule::USE_CASE_LINK => links.push(Link::new(inner_pair)),
+                _ => {}
+            }
+        }
+        // the use cases outside of the contexts are laid out below them
+        if !free_use_cases.is_empty() {
+            contexts.push(Context::unframed(free_use_cases));
+        }
+
+        let mut ids = HashSet::new();
+        let names = actors.iter().map(|actor| actor.get_actor_alias())
+            .chain(contexts.iter().filter(|context| context.is_framed()).map(|context| context.get_context_label()))
+            .chain(contexts.iter().flat_map(|context| context.get_use_cases()).map(|use_case| use_case.get_use_case_alias()));
+        for name in names {
+            if !ids.insert(name.clone()) {
+                panic!("Duplicate name/alias found: {}", name);
+            }
+        }
+        for link in &links {
+            for id in [link.get_left_id(), link.get_right_id()] {
+                if !ids.contains(id) {
+                    panic!("Alias not found: {}", id);
+                }
+            }
+        }
+
+        let mut diagram = UseCaseDiagram {
+            actors,
+            contexts,
+            links,
+            sides: Vec::new(),
+            actor_positions: Vec::new(),
+            context_positions: Vec::new(),
+            width: 0,
+            height: 0,
+            theme: Theme::default()
+        };
+        diagram.layout();
+        diagram
+    }
+
+    pub fn get_actors(&self) -> &Vec<Actor> {&self.actors}
+
+    pub fn get_contexts(&self) -> &Vec<Context> {&self.contexts}
+
+    pub fn get_links(&self) -> &Vec<Link> {&self.links}
+
+    pub fn get_sides(&self) -> &Vec<Side> {&self.sides}
+
+    // Middle of the head of the actor.
+    pub fn get_actor_position(&self, id: &str) -> Option<(i32, i32)> {
+        self.actors.iter()
+            .position(|actor| actor.get_actor_alias() == id)
+            .map(|index| self.actor_positions[index])
+    }
+
+    // Middle of the use case in the diagram.
+    pub fn get_use_case_position(&self, id: &str) -> Option<(i32, i32)> {
+        self.contexts.iter().zip(&self.context_positions).find_map(|(context, (x, y))| {
+            context.get_position(id).map(|(dx, dy)| (x + dx, y + dy))
+        })
+    }
+
+    fn is_actor(&self, id: &str) -> bool {
+        self.actors.iter().any(|actor| actor.get_actor_alias() == id)
+    }
+
+    fn layout(&mut self) {
+        let edges: Vec<(String, String)> = self.links.iter().map(|link| {
+            let (from, to) = link.get_layout_ids();
+            (from.clone(), to.clone())
+        }).collect();
+        let actor_ids: Vec<String> = self.actors.iter().map(|actor| actor.get_actor_alias().clone()).collect();
+        for context in &mut self.contexts {
+            context.layout(&edges, &actor_ids);
+        }
+
+        // primary actors stay on the left, the secondary ones go to the right of the contexts
+        let sides: Vec<Option<Side>> = self.actors.iter().map(|actor| self.actor_side(actor)).collect();
+        // actors linked only with other actors, like in generalizations, take their side
+        self.sides = self.actors.iter().zip(&sides).map(|(actor, side)| side.unwrap_or_else(|| {
+            let alias = actor.get_actor_alias();
+            self.links.iter()
+                .filter_map(|link| {
+                    let other = if link.get_left_id() == alias {
+                        link.get_right_id()
+                    } else if link.get_right_id() == alias {
+                        link.get_left_id()
+                    } else {
+                        return None;
+                    };
+                    let index = self.actors.iter().position(|a| a.get_actor_alias() == other)?;
+                    sides[index]
+                })
+                .next()
+                .unwrap_or(Side::Left)
+        })).collect();
+        let column_width = |side: Side| self.actors.iter().zip(&self.sides)
+            .filter(|(_, s)| **s == side)
+            .map(|(actor, _)| actor.get_width(ACTOR_SIZE))
+            .max();
+        let left_width = column_width(Side::Left);
+        let right_width = column_width(Side::Right);
+
+        let contexts_x = MARGIN + left_width.map(|w| w + ACTOR_GAP_X).unwrap_or(0);
+        let contexts_width = self.contexts.iter().map(|context| context.get_width()).max().unwrap_or(0);
+        let mut y = MARGIN + TITLE_HEIGHT;
+        self.context_positions = self.contexts.iter().map(|context| {
+            let position = (contexts_x + (contexts_width - context.get_width()) / 2, y);
+            y += context.get_height() + CONTEXT_GAP;
+            position
+        }).collect();
+        let mut bottom = if self.contexts.is_empty() { MARGIN } else { y - CONTEXT_GAP };
+
+        let left_x = MARGIN + left_width.unwrap_or(0) / 2;
+        let right_x = contexts_x + contexts_width + ACTOR_GAP_X + right_width.unwrap_or(0) / 2;
+        self.width = match right_width {
+            Some(width) => contexts_x + contexts_width + ACTOR_GAP_X + width + MARGIN,
+            None => contexts_x + contexts_width + MARGIN
+        };
+
+        let generalizations = self.links.iter()
+            .any(|link| self.is_actor(link.get_left_id()) && self.is_actor(link.get_right_id()));
+        let gap = if generalizations { GENERALIZATION_GAP_Y } else { ACTOR_GAP_Y };
+
+        // the actors are level with the middle of their use cases, in that order, without overlapping
+        self.actor_positions = vec![(0, 0); self.actors.len()];
+        for (side, x) in [(Side::Left, left_x), (Side::Right, right_x)] {
+            let mut column: Vec<(usize, Option<i32>)> = (0..self.actors.len())
+                .filter(|index| self.sides[*index] == side)
+                .map(|index| (index, self.linked_middle(&self.actors[index])))
+                .collect();
+            column.sort_by_key(|(_, middle)| middle.unwrap_or(i32::MAX));
+
+            let mut next = MARGIN + ACTOR_SIZE;
+            for (index, middle) in column {
+                // the middle of the actor is below its head
+                let head = middle.map(|m| m - 2 * ACTOR_SIZE - 20).unwrap_or(next);
+                let head = std::cmp::max(head, next);
+                self.actor_positions[index] = (x, head);
+                // the head is ACTOR_SIZE below the top of the actor
+                let height = self.actors[index].get_height(ACTOR_SIZE);
+                next = head + height + gap;
+                bottom = std::cmp::max(bottom, head - ACTOR_SIZE + height);
+            }
+        }
+        self.height = bottom + MARGIN;
+    }
+
+    // Average height of the middles of the use cases linked with the actor.
+    fn linked_middle(&self, actor: &Actor) -> Option<i32> {
+        let alias = actor.get_actor_alias();
+        let middles: Vec<i32> = self.links.iter().filter_map(|link| {
+            let other = if link.get_left_id() == alias {
+                link.get_right_id()
+            } else if link.get_right_id() == alias {
+                link.get_left_id()
+            } else {
+                return None;
+            };
+            self.get_use_case_position(other).map(|(_, y)| y)
+        }).collect();
+        if middles.is_empty() {
+            return None;
+        }
+        Some(middles.iter().sum::<i32>() / middles.len() as i32)
+    }
+
+    // Actors without a declared side go to the right when they are external systems,
+    // when they are on the right of all their links to use cases
+    // or when their use cases are mostly in the right half of the contexts.
+    // None when the actor isn't linked with any use case.
+    fn actor_side(&self, actor: &Actor) -> Option<Side> {
+        if let Some(side) = actor.get_side() {
+            return Some(side);
+        }
+        if actor.is_system() {
+            return Some(Side::Right);
+        }
+        let alias = actor.get_actor_alias();
+        let mut always_on_right = true;
+        let mut positions = Vec::new();
+        for link in &self.links {
+            let other = if link.get_left_id() == alias {
+                link.get_right_id()
+            } else if link.get_right_id() == alias {
+                link.get_left_id()
+            } else {
+                continue;
+            };
+            for context in &self.contexts {
+                if let Some((x, _)) = context.get_position(other) {
+                    always_on_right &= link.get_right_id() == alias;
+                    positions.push(x as f64 / context.get_width() as f64);
+                }
+            }
+        }
+        if positions.is_empty() {
+            return None;
+        }
+        let position = positions.iter().sum::<f64>() / positions.len() as f64;
+        Some(if always_on_right || position > 0.5 { Side::Right } else { Side::Left })
+    }
+
+    // Box taken by the drawn actor or use case with the given alias.
+    fn bounds(&self, id: &str) -> Option<Rect> {
+        if let Some(actor) = self.actors.iter().find(|actor| actor.get_actor_alias() == id) {
+            return Some(actor.get_bounds(ACTOR_SIZE));
+        }
+        self.contexts.iter()
+            .flat_map(|context| context.get_use_cases())
+            .find(|use_case| use_case.get_use_case_alias() == id)
+            .map(|use_case| Rect::new(
+                use_case.get_x() - use_case.get_width() / 2,
+                use_case.get_y() - use_case.get_height() / 2,
+                use_case.get_width(),
+                use_case.get_height()
+            ))
+    }
+
+    pub fn set_theme(&mut self, theme: &Theme) {
+        self.theme = theme.clone();
+        for context in &mut self.contexts {
+            context.set_theme(theme);
+        }
+        for actor in &mut self.actors {
+            actor.set_theme(theme);
+        }
+        for link in &mut self.links {
+            link.set_theme(theme);
+        }
+    }
+
+    pub fn draw(&mut self, svg: &mut SVG) {
+        for (context, (x, y)) in self.contexts.iter_mut().zip(&self.context_positions) {
+            context.draw(svg, *x, *y);
+        }
+        for (actor, (x, y)) in self.actors.iter_mut().zip(&self.actor_positions) {
+            actor.draw(svg, *x, *y, ACTOR_SIZE);
+        }
+
+        for link in &self.links {
+            let (from_id, to_id) = link.get_layout_ids();
+            // links to the contexts aren't drawn
+            if let (Some(from), Some(to)) = (self.bounds(from_id), self.bounds(to_id)) {
+                let (x1, y1) = anchor(&from, to.center(), !self.is_actor(from_id));
+                let (x2, y2) = anchor(&to, from.center(), !self.is_actor(to_id));
+                if *link.get_stereotype() == Stereotype::Extend {
+                    link.draw(svg, x2, y2, x1, y1);
+                } else {
+                    link.draw(svg, x1, y1, x2, y2);
+                }
+            }
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width as usize
+    }
+
+    pub fn height(&self) -> usize {
+        self.height as usize
+    }
+}
+
+// Point where the line from the center towards the given point leaves the element,
+// use cases are the ellipses inscribed in their bounds.
+fn anchor(bounds: &Rect, toward: (f64, f64), ellipse: bool) -> (i32, i32) {
+    let (cx, cy) = bounds.center();
+    let (dx, dy) = (toward.0 - cx, toward.1 - cy);
+    if !ellipse || (dx == 0.0 && dy == 0.0) {
+        let (x, y) = bounds.border_point(toward);
+        return (x as i32, y as i32);
+    }
+    let (rx, ry) = (bounds.width as f64 / 2.0, bounds.height as f64 / 2.0);
+    let scale = 1.0 / ((dx / rx).powi(2) + (dy / ry).powi(2)).sqrt();
+    ((cx + dx * scale) as i32, (cy + dy * scale) as i32)
+}
@@ -2,12 +2,22 @@ use std::collections::{HashMap, HashSet};
 use std::{str, fs};
 use std::process::Command;
 use pest::Parser;
+use pest::iterators::Pair;
 use crate::rules::link::Link;
 use crate::rules::structs::Class;
 use crate::grammar_parser::{GrammarParser, Rule};
-use crate::rules::actor::Actor;
-use crate::rules::context::Context;
+use crate::rules::use_case_diagram::UseCaseDiagram;
 use crate::rules::activity::Activity;
+use crate::rules::sequence::Sequence;
+use crate::rules::state_machine::StateMachine;
+use crate::rules::component_diagram::ComponentDiagram;
+use crate::rules::deployment::Deployment;
+use crate::rules::object_diagram::ObjectDiagram;
+use crate::rules::er_diagram::ErDiagram;
+use crate::rules::gantt::Gantt;
+use crate::rules::mindmap::Mindmap;
+use crate::rules::timing::Timing;
+use crate::rules::theme::Theme;
 use svg::node::element::{Rectangle, SVG};
 
 pub struct UmlParser {
@@ -16,10 +26,31 @@ pub struct UmlParser {
 
 enum DiagramType {
     ClassDiagram,
-    UseCaseDiagram,
-    ActivityDiagram
+    // every other diagram is laid out when it is built
+    Diagram(Box<dyn Diagram>)
 }
 
+trait Diagram {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn set_theme(&mut self, theme: &Theme);
+    fn draw(&mut self, svg: &mut SVG);
+}
+
+macro_rules! impl_diagram {
+    ($($diagram:ty),*) => {
+        $(impl Diagram for $diagram {
+            fn width(&self) -> usize {<$diagram>::width(self)}
+            fn height(&self) -> usize {<$diagram>::height(self)}
+            fn set_theme(&mut self, theme: &Theme) {<$diagram>::set_theme(self, theme)}
+            fn draw(&mut self, svg: &mut SVG) {<$diagram>::draw(self, svg)}
+        })*
+    };
+}
+
+impl_diagram!(UseCaseDiagram, Activity, Sequence, StateMachine, ComponentDiagram, Deployment,
+    ObjectDiagram, ErDiagram, Gantt, Mindmap, Timing);
+
 
 impl UmlParser {
     fn add_alias(list: &mut HashSet<String>, alias: String) {
@@ -29,32 +60,37 @@ impl UmlParser {
             list.insert(alias);
         }
     }
-    fn check_alias(list: &HashSet<String>, alias: String) {
-        if !list.contains(&alias) {
-            panic!("Alias not found: {}", alias);
+
+    fn apply_skinparams(diagram: &Pair<Rule>, theme: &mut Theme) {
+        for inner_pair in diagram.clone().into_inner() {
+            if inner_pair.as_rule() == Rule::SKINPARAM {
+                theme.apply(inner_pair).unwrap_or_else(|e| panic!("{}", e));
+            }
         }
     }
+
+    // The part of the diagram after its start and skinparams.
+    fn body(diagram: Pair<Rule>, rule: Rule) -> Pair<Rule> {
+        diagram.into_inner().find(|p| p.as_rule() == rule).unwrap()
+    }
+
     pub fn parse(value: &str) -> String {
-        let initial_height:usize = 500;
-        let initial_width:usize = 500;
-        let mut width = initial_width as usize;
-        let mut height = initial_height as usize;
+        UmlParser::parse_with_theme(value, Theme::default())
+    }
+
+    // Values from the skinparam blocks of the diagram override the given theme.
+    pub fn parse_with_theme(value: &str, mut theme: Theme) -> String {
+        let width;
+        let height;
 
         let mut svg;
-        let mut diagram = DiagramType::UseCaseDiagram;
+        let mut diagram = DiagramType::ClassDiagram;
 
-        let mut contexts: Vec<Context> = Vec::new();
-        let mut actors: Vec<Actor> = Vec::new();
         let mut links: Vec<Link> = Vec::new();
         let mut classes: Vec<Class> = Vec::new();
         let mut aliases: HashSet<String> = HashSet::new();
 
-        let mut activities = Vec::new();
-
-        let _actor_length;
-        let _context_length;
         let _links_length;
-        let mut _use_cases_len = 0;
         let _classes_length;
 
         let program = GrammarParser::parse(Rule::PROGRAM, value)
@@ -65,9 +101,10 @@ impl UmlParser {
             match pair.as_rule() {
                 Rule::CLASS_DIAGRAM => {
                     diagram = DiagramType::ClassDiagram;
+                    UmlParser::apply_skinparams(&pair, &mut theme);
                     for inner_pair in pair.into_inner() {
                         match inner_pair.as_rule() {
-                            Rule::start_class => {}
+                            Rule::start_class | Rule::SKINPARAM => {}
                             Rule::CLASS => {
                                 let class = Class::new(inner_pair, false);
                                 UmlParser::add_alias(&mut aliases, class.get_name().clone());
@@ -87,45 +124,51 @@ impl UmlParser {
                     }
                 }
                 Rule::USE_CASE_DIAGRAM => {
-                    diagram = DiagramType::UseCaseDiagram;
-                    for inner_pair in pair.into_inner() {
-                        match inner_pair.as_rule() {
-                            Rule::start_use_case => {}
-
-                            Rule::CONTEXT => {
-                                let context = Context::new(inner_pair);
-                                UmlParser::add_alias(&mut aliases, context.get_context_label().clone());
-                                _use_cases_len += context.get_use_cases().len();
-                                contexts.push(context);
-                            }
-
-                            Rule::ACTOR => {
-                                let actor = Actor::new(inner_pair);
-                                UmlParser::add_alias(&mut aliases, actor.get_actor_alias().clone());
-                                actors.push(actor);
-                            }
-                            Rule::LINK => {
-                                let link = Link::new(inner_pair);
-                                links.push(link);
-                            }
-                            _ => unreachable!()
-                        }
-                    }
+                    UmlParser::apply_skinparams(&pair, &mut theme);
+                    diagram = DiagramType::Diagram(Box::new(UseCaseDiagram::new(pair)));
                 }
                 Rule::ACTIVITY_DIAGRAM => {
-                    diagram = DiagramType::ActivityDiagram;
-                    for inner_pair in pair.into_inner(){
-                        match inner_pair.as_rule() {
-                            Rule::start_activity => {}
-                            Rule::ACTIVITY_BODY => {
-                                let activity = Activity::new(inner_pair);
-                                width = activity.width();
-                                height = activity.height();
-                                activities.push(activity);
-                            }
-                            _ => unreachable!()
-                        }
-                    }
+                    UmlParser::apply_skinparams(&pair, &mut theme);
+                    let body = UmlParser::body(pair, Rule::ACTIVITY_BODY);
+                    diagram = DiagramType::Diagram(Box::new(Activity::new(body)));
+                }
+                Rule::SEQUENCE_DIAGRAM => {
+                    UmlParser::apply_skinparams(&pair, &mut theme);
+                    let body = UmlParser::body(pair, Rule::SEQUENCE_BODY);
+                    diagram = DiagramType::Diagram(Box::new(Sequence::new(body)));
+                }
+                Rule::STATE_DIAGRAM => {
+                    UmlParser::apply_skinparams(&pair, &mut theme);
+                    let body = UmlParser::body(pair, Rule::STATE_REGION);
+                    diagram = DiagramType::Diagram(Box::new(StateMachine::new(body)));
+                }
+                Rule::COMPONENT_DIAGRAM => {
+                    UmlParser::apply_skinparams(&pair, &mut theme);
+                    diagram = DiagramType::Diagram(Box::new(ComponentDiagram::new(pair)));
+                }
+                Rule::DEPLOYMENT_DIAGRAM => {
+                    UmlParser::apply_skinparams(&pair, &mut theme);
+                    diagram = DiagramType::Diagram(Box::new(Deployment::new(pair)));
+                }
+                Rule::OBJECT_DIAGRAM => {
+                    UmlParser::apply_skinparams(&pair, &mut theme);
+                    diagram = DiagramType::Diagram(Box::new(ObjectDiagram::new(pair)));
+                }
+                Rule::ER_DIAGRAM => {
+                    UmlParser::apply_skinparams(&pair, &mut theme);
+                    diagram = DiagramType::Diagram(Box::new(ErDiagram::new(pair)));
+                }
+                Rule::GANTT_DIAGRAM => {
+                    UmlParser::apply_skinparams(&pair, &mut theme);
+                    diagram = DiagramType::Diagram(Box::new(Gantt::new(pair)));
+                }
+                Rule::MINDMAP_DIAGRAM => {
+                    UmlParser::apply_skinparams(&pair, &mut theme);
+                    diagram = DiagramType::Diagram(Box::new(Mindmap::new(pair)));
+                }
+                Rule::TIMING_DIAGRAM => {
+                    UmlParser::apply_skinparams(&pair, &mut theme);
+                    diagram = DiagramType::Diagram(Box::new(Timing::new(pair)));
                 }
                 Rule::end_uml => {}
                 _ => unreachable!()
@@ -142,11 +185,12 @@ impl UmlParser {
                 width = class_size * 5;
                 height = class_size * ((_classes_length + 1) / 2);
 
-                svg = SVG::new().set("viewBox", format!("0 0 {} {}", width, height));
+                svg = SVG::new().set("viewBox", format!("0 0 {} {}", width, height))
+                    .set("font-family", theme.font_family.as_str());
                 let rect = Rectangle::new()
                     .set("width", "100%")
                     .set("height", "100%")
-                    .set("fill", "white");
+                    .set("fill", theme.background_color.as_str());
                 svg = svg.clone().add(rect);
                 let mut y_column1 = 25; // Y coordinate of classes that appear in the first column
                 let mut y_column2 = 50; // Y coordinate of classes that appear in the second column
@@ -154,12 +198,14 @@ impl UmlParser {
                 let mut index_class_links: HashMap<String, i32> = HashMap::new(); // number of a link in a list of linked links to the class
                 let mut max_index_class_links: HashMap<String, i32> = HashMap::new(); // number of links linked to the class
                 for class in &mut classes {
+                    class.set_theme(&theme);
                     max_index_class_links.insert(class.get_name().to_string(), 0);
                     index_class_links.insert(class.get_name().to_string(), 0);
                 }
 
                 // count the number of links linked to the classes
-                for link in &links {
+                for link in &mut links {
+                    link.set_theme(&theme);
                     if let Some(value) = max_index_class_links.get_mut(link.get_left_id()) {
                         *value += 1;
                     }
@@ -217,147 +263,19 @@ impl UmlParser {
                     link_index += 1;
                 }
             }
-            DiagramType::UseCaseDiagram => {
-                _context_length = contexts.len();
-                _actor_length = actors.len();
-
-                let actor_size: i32 = 20;
-                let contest_height = 350;
-                let mut contest_width ;
-                height = std::cmp::max((contest_height + 100) * _context_length,
-                                           std::cmp::max(initial_height as usize, (_actor_length * actor_size as usize * 8) as usize));
-
-                let all_actors_height = (8 * actor_size as i32 * _actor_length as i32) as i32;
-                let x_actor: i32 = 75;
-                let mut y_actor = (initial_height as i32 - all_actors_height) / 2 + actor_size as i32;
-                let mut y_context = std::cmp::min(y_actor - actor_size, 100); // display first context a little above first actor
-
-                let mut _left_id: String; // left id in link
-                let mut _right_id: String; // right id in link
-                let mut left_x: i32 = -1; // x coordinate of left side of link
-                let mut left_y: i32 = -1; // y coordinate of left side of link
-                let mut right_x: i32 = -1; // x coordinate of right side of link
-                let mut right_y: i32 = -1; // y coordinate of right side of link
-
-                let mut max_width; // number of dependent use_cases in a context
-                let mut change; // auxiliary variable
-                let mut index;
-                let mut used_links: HashMap<i32, i32> = (1..=links.len()).map(|key| (key as i32, 0)).collect();
-
-                // calculate the maximum width of connected use_cases and column of dependent use_cases
-                // context
-                // |----------------------------------|
-                // u1 ---link1---> u2 ---link2---> u3
-                // u4 ---link3---> u5
-                // u6
-                // |----------------------------------|
-
-                for context in &mut contexts{
-                    max_width = 1;
-                    let mut modifications = Vec::new();
-                    for use_case in context.get_use_cases(){
-                        UmlParser::add_alias(&mut aliases, use_case.get_use_case_alias().clone());
-                        contest_width = 1;
-                        _left_id = use_case.get_use_case_alias().clone();
-                        loop {
-                            change = false;
-                            index = 0;
-                            for link in &mut links{
-                                if let Some(value) = used_links.get_mut(&index) {
-                                    if link.get_left_id().as_str() == _left_id && *value == 0{
-                                        contest_width += 1;
-                                        *value = 1;
-                                        modifications.push((link.get_right_id().clone(), contest_width));
-                                        change = true;
-                                        _left_id = link.get_right_id().clone();
-                                    }
-                                }
-                                index += 1;
-                            }
-                            if change{
-                                if contest_width > max_width{
-                                    max_width = contest_width;
-                                }
-                                _left_id = use_case.get_use_case_alias().clone();
-                                contest_width = 1;
-                                continue;
-                            }
-                            break;
-                        }
-                    }
-                    // assign column to the use cases
-                    for use_case2 in context.get_use_cases_mut() {
-                        for (right_id, width) in &modifications {
-                            if use_case2.get_use_case_alias().as_str() == right_id && use_case2.get_width_number() < *width {
-                                use_case2.set_width_number(*width);
-                            }
-                        }
-                    }
-                    width = std::cmp::max(width, (initial_width + 350 * (max_width - 1) as usize) as usize); // set width of svg viewBox according to widest context
-                    // change the width according to the width of use_cases in the context, 200 value is temporary
-                    context.set_width_number(max_width);
-                }
-                for link in &mut links {
-                    UmlParser::check_alias(&aliases, link.get_left_id().clone());
-                    UmlParser::check_alias(&aliases, link.get_right_id().clone());
-                }
-
-
-                // create ready svg
-                svg = SVG::new().set("viewBox", format!("0 0 {} {}", width + 10, height))
-                    .set("style", "background-color: green");
+            DiagramType::Diagram(mut diagram) => {
+                width = diagram.width();
+                height = diagram.height();
+                svg = SVG::new()
+                    .set("viewBox", format!("0 0 {} {}", width, height))
+                    .set("font-family", theme.font_family.as_str());
                 let rect = Rectangle::new()
                     .set("width", "100%")
                     .set("height", "100%")
-                    .set("fill", "white");
-                svg = svg.clone().add(rect);
-
-                for context in &mut contexts {
-                    context.draw(&mut svg, 2 * x_actor, y_context, 350 * context.get_width_number(), 350);
-                    y_context += 50 + contest_height as i32;
-                }
-
-                for actor in &mut actors {
-                    actor.draw(&mut svg, x_actor, y_actor, actor_size);
-                    y_actor += 8 * actor_size;
-                }
-
-                for link in links {
-                    let _left_id = link.get_left_id().clone();
-                    let _right_id = link.get_right_id().clone();
-
-                    // match _left_id to possible actor
-                    for actor in &actors {
-                        if actor.get_actor_alias().as_str() == _left_id {
-                            left_x = actor.get_x() + actor_size;
-                            left_y = actor.get_y() + 2 * actor_size;
-                        }
-                    }
-                    // match _left_id or _right_id to possible use_case
-                    for context in &contexts {
-                        for use_case in context.get_use_cases() {
-                            if use_case.get_use_case_alias().as_str() == _left_id {
-                                left_x = use_case.get_x() + use_case.get_width() / 2;
-                                left_y = use_case.get_y();
-                            }
-                            if use_case.get_use_case_alias().as_str() == _right_id {
-                                right_x = use_case.get_x() - use_case.get_width() / 2;
-                                right_y = use_case.get_y();
-                            }
-                        }
-                    }
-                    // check if _left_id and _right_id exists
-                    if !(left_x == -1 || left_y == -1 || right_x == -1 || right_y == -1) {
-                        link.draw(&mut svg, left_x, left_y, right_x, right_y);
-                    }
-                }
-            }
-            DiagramType::ActivityDiagram => {
-                svg = SVG::new()
-                    .set("viewBox", format!("0 0 {} {}", width, height));
-                for activity in activities {
-                    activity.draw(&mut svg);
-                }
+                    .set("fill", theme.background_color.as_str());
+                svg = svg.add(rect);
+                diagram.set_theme(&theme);
+                diagram.draw(&mut svg);
             }
         }
         svg::save("image.svg", &svg).unwrap();
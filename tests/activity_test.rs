@@ -1,5 +1,8 @@
+mod common;
+
 #[cfg(test)]
 mod activity_test {
+    use crate::common;
     use pest::Parser;
     use svg::node::element::SVG;
     use uml_composer::{rules::activity::Activity, grammar_parser::{GrammarParser, Rule}};
@@ -71,7 +74,7 @@ if condition1 {
                             Rule::ACTIVITY_BODY => {
                                 Activity::new(inner_pair).draw(&mut svg);
                                 let response = svg.to_string();
-                                assert_eq!(response.contains("<text dominant-baseline=\"central\" fill=\"black\" font-size=\"28\" text-anchor=\"middle\" x=\"350\" y=\"415\">\nstepnew\n</text>"), true)
+                                assert_eq!(response.contains("<text dominant-baseline=\"central\" fill=\"black\" font-size=\"28\" text-anchor=\"middle\" x=\"349\" y=\"415\">\nstepnew\n</text>"), true)
                             }
                             _ => unreachable!()
                         }
@@ -83,4 +86,298 @@ if condition1 {
         }
     }
 
+    #[test]
+    fn draws_loops() {
+        let input="@startuml activity
+
+(*) --> connect
+while unavailable {
+    --> wait
+    --> reconnect
+}
+repeat {
+    --> poll
+} while \"no response\"
+--> done -->(^)
+
+";
+        let activity = Activity::new(common::parse_body(Rule::ACTIVITY_DIAGRAM, Rule::ACTIVITY_BODY, input));
+        // the loop nodes are counted together with their bodies
+        assert_eq!(activity.nodes_count(), 9);
+        assert_eq!(activity.height(), 1350);
+
+        let mut svg = SVG::new();
+        activity.draw(&mut svg);
+        let response = svg.to_string();
+        assert_eq!(response.matches("<polyline").count(), 3);
+        assert!(response.contains("\nunavailable\n"));
+        assert!(response.contains("\nno response\n"));
+    }
+
+    #[test]
+    fn draws_fork() {
+        let input="@startuml activity
+
+(*) --> order
+fork {
+    --> pack
+} and {
+    --> bill
+    --> mail
+} and {
+    --> notify
+}
+--> ship -->(^)
+
+";
+        let activity = Activity::new(common::parse_body(Rule::ACTIVITY_DIAGRAM, Rule::ACTIVITY_BODY, input));
+        assert_eq!(activity.nodes_count(), 9);
+        assert_eq!(activity.height(), 1090);
+        assert_eq!(activity.width(), 600);
+
+        let mut svg = SVG::new();
+        activity.draw(&mut svg);
+        let response = svg.to_string();
+        // the fork and the join bar
+        assert_eq!(response.matches("height=\"12\"").count(), 2);
+        assert!(response.contains("\nmail\n"));
+    }
+
+    #[test]
+    fn draws_elseif() {
+        let input="@startuml activity
+
+(*) --> check
+if \"in stock\" {
+    --> ship
+} elseif ordered {
+    --> wait : later
+    --> ship
+} else {
+    --> cancel
+}
+if paid {
+    --> invoice
+}
+--> done -->(^)
+
+";
+        let activity = Activity::new(common::parse_body(Rule::ACTIVITY_DIAGRAM, Rule::ACTIVITY_BODY, input));
+        assert_eq!(activity.nodes_count(), 11);
+        assert_eq!(activity.height(), 1480);
+        assert_eq!(activity.width(), 906);
+
+        let mut svg = SVG::new();
+        activity.draw(&mut svg);
+        let response = svg.to_string();
+        assert!(response.contains("\n[in stock]\n"));
+        assert!(response.contains("\n[ordered] later\n"));
+        assert!(response.contains("\n[else]\n"));
+        assert!(response.contains("\n[paid]\n"));
+        // the guards are only on the edges
+        assert!(!response.contains("\nin stock\n"));
+        // the decisions and the merge nodes
+        assert_eq!(response.matches("<polygon fill=\"white\"").count(), 4);
+    }
+
+    #[test]
+    fn draws_swimlanes() {
+        let input="@startuml activity
+
+|Sales|
+(*) --> order
+|Warehouse|
+--> pack
+partition Billing {
+    --> bill
+}
+|Sales|
+--> close -->(^)
+
+";
+        let activity = Activity::new(common::parse_body(Rule::ACTIVITY_DIAGRAM, Rule::ACTIVITY_BODY, input));
+        assert_eq!(activity.nodes_count(), 6);
+        assert_eq!(activity.width(), 638);
+        assert_eq!(activity.height(), 910);
+
+        let mut svg = SVG::new();
+        activity.draw(&mut svg);
+        let response = svg.to_string();
+        assert!(response.contains("\nWarehouse\n"));
+        assert!(response.contains("\nBilling\n"));
+        // the arrows between the lanes
+        assert_eq!(response.matches("<polyline").count(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Swimlanes can be switched only in the main flow: Sales")]
+    fn rejects_swimlane_in_branch() {
+        let input="@startuml activity
+
+(*) --> order
+if paid {
+    |Sales|
+    --> ship
+}
+--> close -->(^)
+
+";
+        Activity::new(common::parse_body(Rule::ACTIVITY_DIAGRAM, Rule::ACTIVITY_BODY, input));
+    }
+
+    #[test]
+    fn draws_final_nodes() {
+        let input="@startuml activity
+
+(*) --> check
+if valid {
+    --> process
+} elseif retry {
+    --> log
+    end
+} else {
+    kill
+}
+fork {
+    --> notify
+    detach
+} and {
+    --> archive
+}
+--> done
+stop
+
+";
+        let activity = Activity::new(common::parse_body(Rule::ACTIVITY_DIAGRAM, Rule::ACTIVITY_BODY, input));
+        assert_eq!(activity.nodes_count(), 13);
+
+        let mut svg = SVG::new();
+        activity.draw(&mut svg);
+        let response = svg.to_string();
+        // the edges into the branches and the one merge edge of the branch which goes on
+        assert_eq!(response.matches("<polyline").count(), 4);
+        // the crosses of the flow final and the kill
+        assert_eq!(response.matches("stroke-width=\"4\"").count(), 4);
+        // the activity final
+        assert_eq!(response.matches("r=\"20\"").count(), 1);
+    }
+
+    #[test]
+    fn flow_ends_in_repeat_and_fork() {
+        let input="@startuml activity
+
+(*) --> check
+repeat {
+    --> poll
+    stop
+} while again
+fork {
+    --> notify
+    stop
+} and {
+    --> archive
+    end
+}
+--> done -->(^)
+
+";
+        let activity = Activity::new(common::parse_body(Rule::ACTIVITY_DIAGRAM, Rule::ACTIVITY_BODY, input));
+        let mut svg = SVG::new();
+        activity.draw(&mut svg);
+        let response = svg.to_string();
+        // the fork bar without the join bar
+        assert_eq!(response.matches("height=\"12\"").count(), 1);
+        // no arrows come out of the repeat and the fork, only done goes on to the end
+        assert_eq!(response.matches("marker-end").count(), 8);
+    }
+
+    #[test]
+    fn draws_signals_and_objects() {
+        let input="@startuml activity
+
+(*) --> receive order
+--> [Order] : created
+--> sender
+--> send invoice
+--> after \"2 days\"
+--> (A) -->(^)
+
+";
+        let activity = Activity::new(common::parse_body(Rule::ACTIVITY_DIAGRAM, Rule::ACTIVITY_BODY, input));
+        assert_eq!(activity.nodes_count(), 8);
+
+        let mut svg = SVG::new();
+        activity.draw(&mut svg);
+        let response = svg.to_string();
+        // the signals and the hourglass
+        assert_eq!(response.matches("<polygon fill=\"white\"").count(), 3);
+        assert!(response.contains("\nOrder\n"));
+        assert!(response.contains("\nsender\n"));
+        assert!(response.contains("\n2 days\n"));
+        assert!(response.contains("\nA\n"));
+    }
+
+    #[test]
+    fn draws_goto() {
+        let input="@startuml activity
+
+(*) --> \"Validate order\" as validate
+if valid {
+    --> ship
+} else {
+    --> fix
+    --> goto validate
+}
+--> done -->(^)
+
+";
+        let activity = Activity::new(common::parse_body(Rule::ACTIVITY_DIAGRAM, Rule::ACTIVITY_BODY, input));
+        // the track of the jump is left of the flow
+        assert_eq!(activity.width(), 543);
+
+        let mut svg = SVG::new();
+        activity.draw(&mut svg);
+        let response = svg.to_string();
+        // the edges into the branches, the merge of the valid one and the jump back
+        assert_eq!(response.matches("<polyline").count(), 4);
+        assert!(response.contains("\nValidate order\n"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Alias not found: retry")]
+    fn rejects_goto_without_label() {
+        let input="@startuml activity
+
+(*) --> check
+--> goto retry
+
+";
+        Activity::new(common::parse_body(Rule::ACTIVITY_DIAGRAM, Rule::ACTIVITY_BODY, input));
+    }
+
+    #[test]
+    fn sizes_nodes_by_content() {
+        let short = Activity::new(common::parse_body(Rule::ACTIVITY_DIAGRAM, Rule::ACTIVITY_BODY, "@startuml activity
+
+(*) --> a
+if ok {
+    --> b
+}
+--> c -->(^)
+
+"));
+        let long = Activity::new(common::parse_body(Rule::ACTIVITY_DIAGRAM, Rule::ACTIVITY_BODY, "@startuml activity
+
+(*) --> \"Collect all of the documents required\"
+if \"all of the documents are signed\" {
+    --> b
+}
+--> c -->(^)
+
+"));
+        // the guard is written beside its edge
+        assert!(long.width() > short.width());
+        // the wrapped name makes the first step taller
+        assert!(long.height() > short.height());
+    }
 }
@@ -2,7 +2,7 @@
 mod actor_test {
     use pest::Parser;
     use svg::node::element::SVG;
-    use uml_composer::{rules::actor::Actor, grammar_parser::{GrammarParser, Rule}};
+    use uml_composer::{rules::{actor::{Actor, Side}, use_case_diagram::UseCaseDiagram}, grammar_parser::{GrammarParser, Rule}};
 
     #[test]
     fn actor_with_alias() {
@@ -39,5 +39,125 @@ mod actor_test {
         assert!(svg.to_string().contains(&format!("<circle cx=\"{}\" cy=\"{}\" fill=\"none\" r=\"{}\"", x, y, r)));
     }
 
+    #[test]
+    fn actor_with_unicode_name() {
+        let input: &str = "actor \"Użytkownik\" as użytkownik \n";
+        let actor_pair = GrammarParser::parse(Rule::ACTOR, input)
+            .unwrap().next().unwrap();
+        let mut actor = Actor::new(actor_pair);
+        assert_eq!(actor.get_actor_label().to_owned(), String::from("Użytkownik"));
+        assert_eq!(actor.get_actor_alias().to_owned(), String::from("użytkownik"));
+
+        let mut svg = SVG::new();
+        actor.draw(&mut svg, 100, 100, 20);
+        assert!(svg.to_string().contains("Użytkownik\n</text>"));
+    }
+
+    #[test]
+    fn actor_on_the_right() {
+        let input: &str = "actor Clerk as c right\n";
+        let actor_pair = GrammarParser::parse(Rule::ACTOR, input)
+            .unwrap().next().unwrap();
+        let actor = Actor::new(actor_pair);
+        assert_eq!(actor.get_actor_alias().to_owned(), String::from("c"));
+        assert_eq!(actor.get_side(), Some(Side::Right));
+        assert!(!actor.is_system());
+    }
+
+    #[test]
+    fn system_actor_is_a_box() {
+        let input: &str = "actor \"Payment gateway\" as pg <<system>> #FFAA00\n";
+        let actor_pair = GrammarParser::parse(Rule::ACTOR, input)
+            .unwrap().next().unwrap();
+        let mut actor = Actor::new(actor_pair);
+        assert!(actor.is_system());
+        assert_eq!(actor.get_side(), None);
+        assert!(actor.get_color().is_some());
+
+        let mut svg = SVG::new();
+        actor.draw(&mut svg, 100, 100, 20);
+        let response = svg.to_string();
+        assert!(!response.contains("<circle"));
+        assert!(response.contains("\n«system»\n</text>"));
+        assert!(response.contains("\nPayment gateway\n</text>"));
+        let bounds = actor.get_bounds(20);
+        assert!(response.contains(&format!("<rect fill=\"none\" height=\"{}\"", bounds.height)));
+        assert_eq!(bounds.x + bounds.width / 2, 100);
+    }
+
+    #[test]
+    fn actors_with_wrapped_names_do_not_overlap() {
+        let input: &str = "@startuml usecase\nactor \"A very very long actor name here\" as a1\nactor \"A very very long actor name here\" as a2\n";
+        let diagram_pair = GrammarParser::parse(Rule::USE_CASE_DIAGRAM, input)
+            .unwrap().next().unwrap();
+        let diagram = UseCaseDiagram::new(diagram_pair);
+        let actor = &diagram.get_actors()[0];
+        assert!(actor.get_height(20) > 6 * 20 + 40);
+
+        let (_, first) = diagram.get_actor_position("a1").unwrap();
+        let (_, second) = diagram.get_actor_position("a2").unwrap();
+        assert!(second - first > actor.get_height(20));
+        assert!(diagram.height() as i32 > second - 20 + actor.get_height(20));
+    }
+
+    #[test]
+    fn secondary_actor_moves_right() {
+        let input: &str = "@startuml usecase
+actor Customer as c
+actor Bank as b
+context Shop {
+    usecase Checkout as uc1
+    usecase Charge as uc2
 }
+c -- uc1
+uc1 --> uc2
+uc2 -- b
+";
+        let diagram_pair = GrammarParser::parse(Rule::USE_CASE_DIAGRAM, input)
+            .unwrap().next().unwrap();
+        let diagram = UseCaseDiagram::new(diagram_pair);
+        assert_eq!(diagram.get_sides().to_owned(), vec![Side::Left, Side::Right]);
+        let (customer_x, _) = diagram.get_actor_position("c").unwrap();
+        let (bank_x, _) = diagram.get_actor_position("b").unwrap();
+        let (charge_x, _) = diagram.get_use_case_position("uc2").unwrap();
+        assert!(customer_x < charge_x);
+        assert!(bank_x > charge_x);
+    }
 
+    #[test]
+    fn actor_linked_to_actor_takes_its_side() {
+        let input: &str = "@startuml usecase
+actor Customer as c
+actor Bank as b <<system>>
+actor \"Central bank\" as cb
+context Shop {
+    usecase Checkout as uc1
+}
+c -- uc1
+uc1 -- b
+cb --|> b
+";
+        let diagram_pair = GrammarParser::parse(Rule::USE_CASE_DIAGRAM, input)
+            .unwrap().next().unwrap();
+        let diagram = UseCaseDiagram::new(diagram_pair);
+        assert_eq!(diagram.get_sides().to_owned(), vec![Side::Left, Side::Right, Side::Right]);
+    }
+
+    #[test]
+    fn actor_declared_after_contexts() {
+        let input: &str = "@startuml usecase
+context Shop {
+    usecase Checkout as uc1
+}
+actor Customer as c
+c -- uc1
+";
+        let diagram_pair = GrammarParser::parse(Rule::USE_CASE_DIAGRAM, input)
+            .unwrap().next().unwrap();
+        let diagram = UseCaseDiagram::new(diagram_pair);
+        assert_eq!(diagram.get_sides().to_owned(), vec![Side::Left]);
+        let (customer_x, _) = diagram.get_actor_position("c").unwrap();
+        let (checkout_x, _) = diagram.get_use_case_position("uc1").unwrap();
+        assert!(customer_x < checkout_x);
+    }
+}
@@ -0,0 +1,20 @@
+// Not every test uses all the helpers.
+#![allow(dead_code)]
+
+use pest::Parser;
+use pest::iterators::Pair;
+use uml_composer::grammar_parser::{GrammarParser, Rule};
+
+// Parses the input as a whole diagram, e.g. Rule::GANTT_DIAGRAM.
+pub fn parse_diagram(rule: Rule, input: &str) -> Pair<'_, Rule> {
+    GrammarParser::parse(rule, input)
+        .unwrap_or_else(|e| panic!("{}", e))
+        .next().unwrap()
+}
+
+// Parses the diagram and returns its part matching the body rule, e.g. Rule::ACTIVITY_BODY.
+pub fn parse_body(rule: Rule, body: Rule, input: &str) -> Pair<'_, Rule> {
+    parse_diagram(rule, input).into_inner()
+        .find(|p| p.as_rule() == body)
+        .unwrap()
+}
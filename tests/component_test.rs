@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod component_test {
+    use pest::Parser;
+    use svg::node::element::SVG;
+    use uml_composer::{rules::{component::{Component, InterfaceKind}, component_diagram::ComponentDiagram, subsystem::Element}, grammar_parser::{GrammarParser, Rule}};
+
+    #[test]
+    fn parse_into_component() {
+        let input: &str = "component \"Order Service\" as orders #lightblue {\n\tprovides IOrders\n\trequires IPayments\n\tport api\n}\n";
+        let component_pair = GrammarParser::parse(Rule::COMPONENT_BOX, input)
+            .unwrap().next().unwrap();
+        let component = Component::new(component_pair);
+        assert_eq!(component.get_label().to_owned(), String::from("Order Service"));
+        assert_eq!(component.get_alias().to_owned(), String::from("orders"));
+        assert_eq!(component.get_color().to_owned(), Some(String::from("lightblue")));
+        assert_eq!(component.get_interfaces().to_owned(), vec![
+            (String::from("IOrders"), InterfaceKind::Provided),
+            (String::from("IPayments"), InterfaceKind::Required)
+        ]);
+        assert_eq!(component.get_ports().to_owned(), vec![String::from("api")]);
+        assert!(component.contains(&String::from("api")));
+        assert!(component.get_width() > 140);
+    }
+
+    #[test]
+    fn draw_component_diagram() {
+        let input: &str = "@startuml component
+component Gateway {
+    port http
+}
+subsystem Backend {
+    component orders {
+        provides IOrders
+        requires IPayments
+    }
+    subsystem \"Payment Provider\" {
+        component payments {
+            provides IPayments
+        }
+    }
+}
+http ..> IOrders : calls
+";
+        let diagram = GrammarParser::parse(Rule::COMPONENT_DIAGRAM, input)
+            .unwrap_or_else(|e| panic!("{}", e))
+            .next().unwrap();
+        let diagram = ComponentDiagram::new(diagram);
+        let elements = diagram.get_root().get_elements();
+        assert_eq!(elements.len(), 2);
+        match &elements[1] {
+            Element::Subsystem(subsystem) => {
+                assert_eq!(subsystem.get_label().to_owned(), String::from("Backend"));
+                assert_eq!(subsystem.get_elements().len(), 2);
+            }
+            _ => panic!("Expected a subsystem")
+        }
+        assert_eq!(diagram.get_root().components().len(), 3);
+        assert_eq!(diagram.assemblies(), vec![(String::from("orders"), String::from("payments"), String::from("IPayments"))]);
+
+        let mut svg = SVG::new();
+        diagram.draw(&mut svg);
+        let result = svg.to_string();
+        assert!(result.contains(">\nPayment Provider\n</text>"));
+        assert!(result.contains(">\ncalls\n</text>"));
+        assert!(result.contains("stroke-dasharray=\"8 8\""));
+        assert!(result.contains("<circle"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Alias not found: db")]
+    fn link_to_unknown_component() {
+        let input: &str = "@startuml component\ncomponent app\napp ..> db\n";
+        let diagram = GrammarParser::parse(Rule::COMPONENT_DIAGRAM, input)
+            .unwrap_or_else(|e| panic!("{}", e))
+            .next().unwrap();
+        ComponentDiagram::new(diagram);
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate name/alias found: B")]
+    fn interface_named_like_component() {
+        let input: &str = "@startuml component\ncomponent A {\n\tprovides B\n}\ncomponent B\n";
+        let diagram = GrammarParser::parse(Rule::COMPONENT_DIAGRAM, input)
+            .unwrap_or_else(|e| panic!("{}", e))
+            .next().unwrap();
+        ComponentDiagram::new(diagram);
+    }
+}
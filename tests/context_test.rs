@@ -2,7 +2,7 @@
 mod context_test {
     use pest::Parser;
     use svg::node::element::SVG;
-    use uml_composer::{rules::context::Context, grammar_parser::{GrammarParser, Rule}};
+    use uml_composer::{rules::{context::Context, use_case::UseCase}, grammar_parser::{GrammarParser, Rule}};
 
     #[test]
     fn context_with_use_case() {
@@ -20,13 +20,42 @@ mod context_test {
         let mut svg = SVG::new();
         let x = 50;
         let y = 100;
-        let width = 200;
-        let height = 200;
-        context.draw(&mut svg, x, y, width, height);
-        assert!(svg.to_string().contains(&format!("height=\"{}\"", height)));
-        assert!(svg.to_string().contains(&format!("width=\"{}\" x=\"{}\" y=\"{}\"/>", width, x, y)));
+        context.draw(&mut svg, x, y);
+        assert!(svg.to_string().contains(&format!("height=\"{}\"", context.get_height())));
+        assert!(svg.to_string().contains(&format!("width=\"{}\" x=\"{}\" y=\"{}\"/>", context.get_width(), x, y)));
     }
-}
 
+    #[test]
+    fn free_use_cases_have_no_frame() {
+        let use_cases = ["usecase Browse\n", "usecase Search\n"].iter()
+            .map(|input| UseCase::new(GrammarParser::parse(Rule::USE_CASE, input).unwrap().next().unwrap()))
+            .collect();
+        let mut context = Context::unframed(use_cases);
+        assert!(!context.is_framed());
+        assert_eq!(context.get_use_cases().len(), 2);
+
+        let mut svg = SVG::new();
+        context.draw(&mut svg, 50, 100);
+        assert!(!svg.to_string().contains("<rect"));
+        assert_eq!(svg.to_string().matches("<ellipse").count(), 2);
+    }
 
+    #[test]
+    fn linked_use_cases_are_laid_out_in_columns() {
+        let input: &str = "context Shop { \n usecase Buy as uc1 \n usecase Pay as uc2 \n usecase Browse as uc3 \n} \n";
+        let context_pair = GrammarParser::parse(Rule::CONTEXT, input)
+            .unwrap().next().unwrap();
+        let mut context = Context::new(context_pair);
+        let stacked_height = context.get_height();
+        context.layout(&[(String::from("uc1"), String::from("uc2"))], &[]);
 
+        let (buy_x, buy_y) = context.get_position("uc1").unwrap();
+        let (pay_x, pay_y) = context.get_position("uc2").unwrap();
+        let (browse_x, browse_y) = context.get_position("uc3").unwrap();
+        assert!(pay_x > buy_x);
+        assert_eq!(pay_y, buy_y);
+        assert!(browse_y > buy_y);
+        assert!(browse_x < pay_x);
+        assert!(context.get_height() < stacked_height);
+    }
+}
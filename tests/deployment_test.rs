@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod deployment_test {
+    use pest::Parser;
+    use svg::node::element::SVG;
+    use uml_composer::{rules::{deployment::Deployment, deployment_node::{DeploymentKind, DeploymentNode}, link::LinkType}, grammar_parser::{GrammarParser, Rule}};
+
+    #[test]
+    fn parse_into_deployment_node() {
+        let input: &str = "node \"Web Server\" as web #lightgray {\n\tenvironment Tomcat {\n\t\tartifact \"shop.war\" as war\n\t}\n}\n";
+        let node_pair = GrammarParser::parse(Rule::DEPLOYMENT_ELEMENT, input)
+            .unwrap().next().unwrap();
+        let node = DeploymentNode::new(node_pair);
+        assert_eq!(node.get_label().to_owned(), String::from("Web Server"));
+        assert_eq!(node.get_alias().to_owned(), String::from("web"));
+        assert_eq!(node.get_kind(), DeploymentKind::Node);
+
+        let environment = &node.get_children()[0];
+        assert_eq!(environment.get_kind(), DeploymentKind::Environment);
+        assert_eq!(environment.get_children()[0].get_kind(), DeploymentKind::Artifact);
+        assert!(node.contains(&String::from("war")));
+    }
+
+    #[test]
+    fn draw_deployment_diagram() {
+        let input: &str = "@startuml deployment
+node web {
+    artifact app
+}
+node \"Database Server\" as dbserver {
+    environment PostgreSQL as pg
+}
+web -- pg : JDBC
+";
+        let diagram = GrammarParser::parse(Rule::DEPLOYMENT_DIAGRAM, input)
+            .unwrap_or_else(|e| panic!("{}", e))
+            .next().unwrap();
+        let deployment = Deployment::new(diagram);
+        assert_eq!(deployment.get_root().get_children().len(), 2);
+        assert_eq!(*deployment.get_links()[0].get_link_type(), LinkType::SolidLine);
+        // the server with the database goes below the one connecting to it
+        assert!(deployment.height() as i32 > 2 * deployment.get_root().get_children()[0].get_height());
+
+        let mut svg = SVG::new();
+        deployment.draw(&mut svg);
+        let result = svg.to_string();
+        assert!(result.contains(">\nJDBC\n</text>"));
+        assert!(result.contains(">\n«executionEnvironment»\n</text>"));
+        assert!(result.contains(">\n«artifact»\n</text>"));
+        assert!(result.contains("<polygon"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Artifact app can't contain other elements")]
+    fn artifact_with_children() {
+        let input: &str = "artifact app {\n\tnode inner\n}\n";
+        let node_pair = GrammarParser::parse(Rule::DEPLOYMENT_ELEMENT, input)
+            .unwrap().next().unwrap();
+        DeploymentNode::new(node_pair);
+    }
+}
@@ -0,0 +1,94 @@
+mod common;
+
+#[cfg(test)]
+mod er_test {
+    use crate::common;
+    use pest::Parser;
+    use svg::node::element::SVG;
+    use uml_composer::{rules::{entity::Entity, er_diagram::ErDiagram, relationship::{Cardinality, Relationship}}, grammar_parser::{GrammarParser, Rule}};
+
+    #[test]
+    fn parse_into_entity() {
+        let input: &str = "entity customer #lightyellow {\n\tid : INT PK\n\tname : VARCHAR(255)\n\tregion_id : INT FK\n}\n";
+        let entity_pair = GrammarParser::parse(Rule::ENTITY, input)
+            .unwrap().next().unwrap();
+        let entity = Entity::new(entity_pair);
+        assert_eq!(entity.get_name().to_owned(), String::from("customer"));
+        assert_eq!(entity.get_color().to_owned(), Some(String::from("lightyellow")));
+        assert_eq!(entity.get_columns().len(), 3);
+        assert!(entity.get_columns()[0].is_primary_key());
+        assert_eq!(entity.get_columns()[1].get_kind().to_owned(), String::from("VARCHAR(255)"));
+        assert!(entity.get_columns()[2].is_foreign_key());
+        assert_eq!(entity.get_height(), 200);
+    }
+
+    #[test]
+    fn column_type_with_spaces_in_parentheses() {
+        let input: &str = "entity invoice {\n\ttotal : DECIMAL(10, 2)\n\tcustomer_id : INT FK\n}\n";
+        let entity_pair = GrammarParser::parse(Rule::ENTITY, input)
+            .unwrap().next().unwrap();
+        let entity = Entity::new(entity_pair);
+        assert_eq!(entity.get_columns()[0].get_kind().to_owned(), String::from("DECIMAL(10, 2)"));
+        assert!(entity.get_columns()[1].is_foreign_key());
+    }
+
+    #[test]
+    fn parse_into_relationship() {
+        let input: &str = "customer ||..o{ order : places\n";
+        let relationship_pair = GrammarParser::parse(Rule::RELATIONSHIP, input)
+            .unwrap().next().unwrap();
+        let relationship = Relationship::new(relationship_pair);
+        assert_eq!(relationship.get_left_id().to_owned(), String::from("customer"));
+        assert_eq!(relationship.get_right_id().to_owned(), String::from("order"));
+        assert_eq!(relationship.get_left(), Cardinality::ExactlyOne);
+        assert_eq!(relationship.get_right(), Cardinality::ZeroOrMany);
+        assert!(!relationship.is_identifying());
+        assert_eq!(relationship.get_label().to_owned(), String::from("places"));
+    }
+
+    #[test]
+    fn draw_er_diagram() {
+        let input: &str = "@startuml er
+entity customer {
+    id : INT PK
+    name : VARCHAR(255)
+}
+entity order {
+    id : INT PK
+    customer_id : INT FK
+}
+entity product {
+    id : INT PK
+}
+customer ||--o{ order : places
+order }|--|| product
+";
+        let diagram = ErDiagram::new(common::parse_diagram(Rule::ER_DIAGRAM, input));
+        assert_eq!(diagram.get_entities().len(), 3);
+        assert_eq!(diagram.get_relationships().len(), 2);
+        // related tables are laid out one below the other
+        let positions = diagram.get_positions();
+        assert!(positions[0].1 < positions[1].1);
+        assert!(positions[1].1 < positions[2].1);
+
+        let mut svg = SVG::new();
+        diagram.draw(&mut svg);
+        let output = svg.to_string();
+        assert!(output.contains("PK id : INT"));
+        assert!(output.contains("FK customer_id : INT"));
+        assert!(output.contains("places"));
+        assert!(output.contains("<circle"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Alias not found: invoice")]
+    fn relationship_to_unknown_entity() {
+        let input: &str = "@startuml er
+entity customer {
+    id : INT PK
+}
+customer ||--o{ invoice
+";
+        ErDiagram::new(common::parse_diagram(Rule::ER_DIAGRAM, input));
+    }
+}
@@ -0,0 +1,122 @@
+mod common;
+
+#[cfg(test)]
+mod gantt_test {
+    use crate::common;
+    use svg::node::element::SVG;
+    use uml_composer::{rules::{calendar::{Calendar, Date}, gantt::Gantt, task::Anchor}, grammar_parser::Rule};
+
+    fn date(value: &str) -> Date {
+        Date::parse(value).unwrap()
+    }
+
+    #[test]
+    fn date_math() {
+        let leap_day = date("2024-02-28").add_days(1);
+        assert_eq!(leap_day.to_string(), String::from("2024-02-29"));
+        assert_eq!(leap_day.add_days(1).to_string(), String::from("2024-03-01"));
+        assert_eq!(date("1999-12-31").add_days(1).to_string(), String::from("2000-01-01"));
+        assert_eq!(date("2024-01-01").days_until(date("2025-01-01")), 366);
+        // 2024-01-01 was a monday
+        assert_eq!(date("2024-01-01").weekday(), 0);
+        assert_eq!(date("2024-01-07").weekday(), 6);
+        assert!(Date::parse("2023-02-29").is_err());
+
+        let mut calendar = Calendar::default();
+        calendar.close_weekday(5);
+        calendar.close_weekday(6);
+        // friday plus two working days skips the weekend
+        assert_eq!(calendar.add_working_days(date("2024-01-05"), 2), date("2024-01-09"));
+        assert_eq!(calendar.next_open(date("2024-01-06")), date("2024-01-08"));
+    }
+
+    #[test]
+    fn schedule_tasks() {
+        let input: &str = "@startuml gantt
+project starts 2024-01-01
+saturday are closed
+sunday are closed
+2024-01-10 is closed
+[Design] lasts 5 days
+[Coding] starts at [Design]'s end and lasts 4 days
+[Coding] on {Alice} {Bob}
+[Tests] starts 2024-01-20
+[Tests] lasts 2 days
+[Release] happens at [Tests]'s end
+";
+        let gantt = Gantt::new(common::parse_diagram(Rule::GANTT_DIAGRAM, input));
+        let tasks = gantt.get_tasks();
+        assert_eq!(tasks.len(), 4);
+        assert_eq!(tasks[0].get_begin(), date("2024-01-01"));
+        assert_eq!(tasks[0].get_end(), date("2024-01-06"));
+        assert_eq!(*tasks[1].get_anchor(), Anchor::End(String::from("Design")));
+        // starts after the weekend and skips the closed day
+        assert_eq!(tasks[1].get_begin(), date("2024-01-08"));
+        assert_eq!(tasks[1].get_end(), date("2024-01-13"));
+        assert_eq!(tasks[1].get_resources().to_owned(), vec![String::from("Alice"), String::from("Bob")]);
+        // the saturday the task should start on is closed
+        assert_eq!(tasks[2].get_begin(), date("2024-01-22"));
+        assert!(tasks[3].is_milestone());
+        assert_eq!(tasks[3].get_begin(), date("2024-01-24"));
+    }
+
+    #[test]
+    fn draw_timeline() {
+        let input: &str = "@startuml gantt
+project starts 2024-01-30
+[Migration] lasts 3 days
+[Review] happens at [Migration]'s end
+";
+        let gantt = Gantt::new(common::parse_diagram(Rule::GANTT_DIAGRAM, input));
+        let mut svg = SVG::new();
+        gantt.draw(&mut svg);
+        let output = svg.to_string();
+        assert!(output.contains("January 2024"));
+        assert!(output.contains("February 2024"));
+        assert!(output.contains("Migration"));
+        assert!(output.contains("<polygon"));
+        assert_eq!(gantt.height(), 170);
+    }
+
+    #[test]
+    #[should_panic(expected = "Circular dependency found")]
+    fn circular_dependency() {
+        let input: &str = "@startuml gantt
+project starts 2024-01-01
+[A] starts at [B]'s end
+[B] starts at [A]'s end
+";
+        Gantt::new(common::parse_diagram(Rule::GANTT_DIAGRAM, input));
+    }
+
+    #[test]
+    #[should_panic(expected = "Duration too long: 3000000000 days")]
+    fn duration_too_long() {
+        let input: &str = "@startuml gantt
+project starts 2024-01-01
+[A] lasts 3000000000 days
+";
+        Gantt::new(common::parse_diagram(Rule::GANTT_DIAGRAM, input));
+    }
+
+    #[test]
+    #[should_panic(expected = "Chart too long: 7306 days")]
+    fn chart_too_long() {
+        let input: &str = "@startuml gantt
+project starts 2024-01-01
+[A] starts 2044-01-01
+";
+        Gantt::new(common::parse_diagram(Rule::GANTT_DIAGRAM, input));
+    }
+
+    #[test]
+    #[should_panic(expected = "Closed range ends before it starts: 2024-01-10 to 2024-01-05")]
+    fn closed_range_backwards() {
+        let input: &str = "@startuml gantt
+project starts 2024-01-01
+2024-01-10 to 2024-01-05 are closed
+[A] lasts 5 days
+";
+        Gantt::new(common::parse_diagram(Rule::GANTT_DIAGRAM, input));
+    }
+}
@@ -2,7 +2,7 @@
 mod link_test {
     use pest::Parser;
     use svg::node::element::SVG;
-    use uml_composer::{rules::link::{Link, ArrowType, LinkType}, grammar_parser::{GrammarParser, Rule}};
+    use uml_composer::{rules::link::{Link, ArrowType, LinkType, Stereotype}, grammar_parser::{GrammarParser, Rule}};
 
     const END_SVG: &str = "</svg>";
 
@@ -320,4 +320,105 @@ mod link_test {
 
     }
 
+    #[test]
+    fn parse_into_link_with_multiline_label() {
+        let input: &str = "a -- b : \"lorem\\nipsum\" \n";
+        let link_pair = GrammarParser::parse(Rule::LINK, input)
+            .unwrap().next().unwrap();
+        let link = Link::new(link_pair);
+        assert_eq!(link.get_label().to_owned(), String::from("lorem\nipsum"));
+
+        let mut svg = SVG::new();
+        link.draw(&mut svg, 20, 100, 220, 100);
+        assert!(svg.to_string().contains("<tspan x=\"120\" y=\"73.4\">\nlorem\n</tspan>"));
+        assert!(svg.to_string().contains("<tspan x=\"120\" y=\"95\">\nipsum\n</tspan>"));
+    }
+
+    #[test]
+    fn parse_into_link_with_punctuation_in_label() {
+        let input: &str = "klient --> płatność : \"Pay (card/cash) & \\\"<more>\\\"\" \n";
+        let link_pair = GrammarParser::parse(Rule::LINK, input)
+            .unwrap().next().unwrap();
+        let link = Link::new(link_pair);
+        assert_eq!(link.get_left_id().to_owned(), String::from("klient"));
+        assert_eq!(link.get_right_id().to_owned(), String::from("płatność"));
+        assert_eq!(link.get_label().to_owned(), String::from("Pay (card/cash) & \"<more>\""));
+
+        let mut svg = SVG::new();
+        link.draw(&mut svg, 20, 20, 220, 220);
+        assert!(svg.to_string().contains("\nPay (card/cash) &amp; \"&lt;more&gt;\"\n</text>"));
+    }
+
+    #[test]
+    fn parse_into_include_link() {
+        let input: &str = "uc1 .> uc3 : <<include>> \n";
+        let link_pair = GrammarParser::parse(Rule::USE_CASE_LINK, input)
+            .unwrap().next().unwrap();
+        let link = Link::new(link_pair);
+        assert_eq!(*link.get_link_type(), LinkType::DashedArrow);
+        assert_eq!(*link.get_stereotype(), Stereotype::Include);
+        assert_eq!(link.get_label().to_owned(), String::from(""));
+        assert_eq!(link.get_layout_ids(), (&String::from("uc1"), &String::from("uc3")));
+
+        let mut svg = SVG::new();
+        link.draw(&mut svg, 20, 100, 220, 100);
+        assert!(svg.to_string().contains("\n«include»\n</text>"));
+        assert!(svg.to_string().contains("stroke-dasharray=\"8 8\""));
+        // the arrowhead is open
+        assert_eq!(svg.to_string().matches("<line").count(), 3);
+    }
+
+    #[test]
+    fn parse_into_extend_link_with_extension_point() {
+        let input: &str = "uc2 --> uc1 : «extend» payment \n";
+        let link_pair = GrammarParser::parse(Rule::USE_CASE_LINK, input)
+            .unwrap().next().unwrap();
+        let link = Link::new(link_pair);
+        assert_eq!(*link.get_link_type(), LinkType::DashedArrow);
+        assert_eq!(*link.get_stereotype(), Stereotype::Extend);
+        assert_eq!(link.get_label().to_owned(), String::from("payment"));
+        // the base use case is laid out first
+        assert_eq!(link.get_layout_ids(), (&String::from("uc1"), &String::from("uc2")));
+
+        let mut svg = SVG::new();
+        link.draw(&mut svg, 220, 100, 20, 100);
+        assert!(svg.to_string().contains("\n«extend»\n</tspan>"));
+        assert!(svg.to_string().contains("\npayment\n</tspan>"));
+    }
+
+    #[test]
+    fn parse_into_generalization_link() {
+        let input: &str = "Admin --|> User \n";
+        let link_pair = GrammarParser::parse(Rule::USE_CASE_LINK, input)
+            .unwrap().next().unwrap();
+        let link = Link::new(link_pair);
+        assert_eq!(*link.get_link_type(), LinkType::Generalization);
+        assert_eq!(link.get_left_id().to_owned(), String::from("Admin"));
+        assert_eq!(link.get_right_id().to_owned(), String::from("User"));
+
+        let mut svg = SVG::new();
+        link.draw(&mut svg, 20, 100, 220, 100);
+        // the hollow triangle is drawn over the end of the line
+        assert!(svg.to_string().ends_with("<polygon fill=\"white\" points=\"220,100 198,90 198,110\" stroke=\"#000\" stroke-width=\"3\"/>\n</svg>"));
+    }
+
+    #[test]
+    fn parse_into_reversed_generalization_link() {
+        let input: &str = "User <|-- Admin \n";
+        let link_pair = GrammarParser::parse(Rule::USE_CASE_LINK, input)
+            .unwrap().next().unwrap();
+        let link = Link::new(link_pair);
+        assert_eq!(*link.get_link_type(), LinkType::Generalization);
+        // the specialized element goes first
+        assert_eq!(link.get_left_id().to_owned(), String::from("Admin"));
+        assert_eq!(link.get_right_id().to_owned(), String::from("User"));
+    }
+
+    #[test]
+    fn class_diagram_rejects_use_case_links() {
+        for link in ["A .> B", "A --|> B", "A <|-- B", "A --> B : <<include>>"] {
+            let input = format!("@startuml class\nclass A {{\n}}\nclass B {{\n}}\n{}\n", link);
+            assert!(GrammarParser::parse(Rule::CLASS_DIAGRAM, &input).is_err(), "{}", link);
+        }
+    }
 }
@@ -0,0 +1,64 @@
+mod common;
+
+#[cfg(test)]
+mod mindmap_test {
+    use crate::common;
+    use pest::Parser;
+    use svg::node::element::SVG;
+    use uml_composer::{rules::{idea::{Idea, Side}, mindmap::Mindmap}, grammar_parser::{GrammarParser, Rule}};
+
+    #[test]
+    fn parse_into_idea() {
+        let input: &str = "*** #pink Ship it\n";
+        let idea_pair = GrammarParser::parse(Rule::IDEA, input)
+            .unwrap().next().unwrap();
+        let idea = Idea::new(idea_pair);
+        assert_eq!(idea.get_level(), 3);
+        assert_eq!(idea.get_side(), Side::Auto);
+        assert_eq!(idea.get_color().to_owned(), Some(String::from("pink")));
+        assert_eq!(idea.get_label().to_owned(), String::from("Ship it"));
+    }
+
+    #[test]
+    fn balanced_branches() {
+        let input: &str = "@startuml mindmap
+* Release
+** Scope
+*** Features
+*** Bugs
+** Testing
+** Docs
+-- Risks
+";
+        let mindmap = Mindmap::new(common::parse_diagram(Rule::MINDMAP_DIAGRAM, input));
+        assert_eq!(mindmap.get_ideas().len(), 7);
+        assert_eq!(mindmap.get_parent(3), Some(1));
+        assert_eq!(mindmap.get_parent(5), Some(0));
+        // the explicit '-' goes left, the first branch right and the next ones balance the sides
+        assert_eq!(mindmap.get_side(6), Side::Left);
+        assert_eq!(mindmap.get_side(1), Side::Right);
+        assert_eq!(mindmap.get_side(4), Side::Left);
+        assert_eq!(mindmap.get_side(3), Side::Right);
+
+        let rects = mindmap.get_rects();
+        assert!(rects[1].x > rects[0].x + rects[0].width);
+        assert!(rects[4].x + rects[4].width < rects[0].x);
+        assert!(rects[2].y < rects[3].y);
+
+        let mut svg = SVG::new();
+        mindmap.draw(&mut svg);
+        let output = svg.to_string();
+        assert!(output.contains("Features"));
+        assert_eq!(output.matches("<path").count(), 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "Mind map can have only one root: Other")]
+    fn two_roots() {
+        let input: &str = "@startuml mindmap
+* Release
+* Other
+";
+        Mindmap::new(common::parse_diagram(Rule::MINDMAP_DIAGRAM, input));
+    }
+}
@@ -0,0 +1,91 @@
+mod common;
+
+#[cfg(test)]
+mod object_test {
+    use crate::common;
+    use pest::Parser;
+    use svg::node::element::SVG;
+    use uml_composer::{rules::{object::Object, object_diagram::ObjectDiagram}, grammar_parser::{GrammarParser, Rule}};
+
+    #[test]
+    fn parse_into_object() {
+        let input: &str = "object alice : Employee #lightblue {\n\tname = \"Alice\"\n\tsalary = 4200.50\n}\n";
+        let object_pair = GrammarParser::parse(Rule::OBJECT, input)
+            .unwrap().next().unwrap();
+        let object = Object::new(object_pair);
+        assert_eq!(object.get_name().to_owned(), String::from("alice"));
+        assert_eq!(object.get_class().to_owned(), String::from("Employee"));
+        assert_eq!(object.get_color().to_owned(), Some(String::from("lightblue")));
+        assert_eq!(object.get_slots().len(), 2);
+        assert_eq!(object.get_slots()[0].get_value().to_owned(), String::from("\"Alice\""));
+        assert_eq!(object.get_slots()[1].get_value().to_owned(), String::from("4200.50"));
+        assert_eq!(object.get_height(), 150);
+    }
+
+    #[test]
+    fn slots_on_object_line() {
+        let input: &str = "object alice : Employee { name = \"Smith, Alice\", salary = 4200.50 }\n";
+        let object_pair = GrammarParser::parse(Rule::OBJECT, input)
+            .unwrap().next().unwrap();
+        let object = Object::new(object_pair);
+        assert_eq!(object.get_slots().len(), 2);
+        assert_eq!(object.get_slots()[0].get_name().to_owned(), String::from("name"));
+        assert_eq!(object.get_slots()[0].get_value().to_owned(), String::from("\"Smith, Alice\""));
+        assert_eq!(object.get_slots()[1].get_value().to_owned(), String::from("4200.50"));
+    }
+
+    #[test]
+    fn draw_object_diagram() {
+        let input: &str = "@startuml object
+object alice : Employee {
+    name = \"Alice\"
+}
+object acme : Company
+object bob
+alice -- acme : \"works for\"
+";
+        let diagram = ObjectDiagram::new(common::parse_diagram(Rule::OBJECT_DIAGRAM, input));
+        assert_eq!(diagram.get_objects().len(), 3);
+        assert_eq!(diagram.get_links().len(), 1);
+
+        let mut svg = SVG::new();
+        diagram.draw(&mut svg);
+        let result = svg.to_string();
+        assert!(result.contains("text-decoration=\"underline\""));
+        assert!(result.contains(">\nalice : Employee\n</text>"));
+        assert!(result.contains(">\nbob\n</text>"));
+        assert!(result.contains(">\nname = \"Alice\"\n</text>"));
+        assert!(result.contains(">\nworks for\n</text>"));
+    }
+
+    #[test]
+    fn slots_match_class_attributes() {
+        let input: &str = "@startuml object
+class Employee {
+    attributes {
+        - name : String
+    }
+}
+object alice : Employee {
+    name = \"Alice\"
+}
+";
+        assert_eq!(ObjectDiagram::new(common::parse_diagram(Rule::OBJECT_DIAGRAM, input)).get_objects().len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown attribute age of class Employee in object alice")]
+    fn slot_missing_in_class() {
+        let input: &str = "@startuml object
+class Employee {
+    attributes {
+        - name : String
+    }
+}
+object alice : Employee {
+    age = 42
+}
+";
+        ObjectDiagram::new(common::parse_diagram(Rule::OBJECT_DIAGRAM, input));
+    }
+}
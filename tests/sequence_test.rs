@@ -0,0 +1,115 @@
+mod common;
+
+#[cfg(test)]
+mod sequence_test {
+    use crate::common;
+    use pest::Parser;
+    use svg::node::element::SVG;
+    use uml_composer::{rules::{message::{Message, MessageType}, sequence::{Sequence, Statement}}, grammar_parser::{GrammarParser, Rule}};
+
+    #[test]
+    fn parse_into_message() {
+        let input: &str = "Alice ->> Bob : \"Hello, Bob\"\n";
+        let message_pair = GrammarParser::parse(Rule::MESSAGE, input)
+            .unwrap().next().unwrap();
+        let message = Message::new(message_pair);
+        assert_eq!(message.get_from().to_owned(), String::from("Alice"));
+        assert_eq!(message.get_to().to_owned(), String::from("Bob"));
+        assert_eq!(message.get_message_type(), MessageType::Asynchronous);
+        assert_eq!(message.get_label().to_owned(), String::from("Hello, Bob"));
+        assert!(!message.is_self_message());
+    }
+
+    #[test]
+    fn implicit_participants() {
+        let input: &str = "@startuml sequence\nparticipant \"Web Server\" as web\nuser --> web : request\nweb --> db : query\ndb ..> web\n";
+        let sequence = Sequence::new(common::parse_body(Rule::SEQUENCE_DIAGRAM, Rule::SEQUENCE_BODY, input));
+        let aliases: Vec<String> = sequence.get_participants().iter()
+            .map(|p| p.get_alias().clone())
+            .collect();
+        assert_eq!(aliases, vec![String::from("web"), String::from("user"), String::from("db")]);
+        assert_eq!(sequence.get_participants()[0].get_label().to_owned(), String::from("Web Server"));
+        assert_eq!(sequence.get_statements().len(), 3);
+
+        let positions = sequence.positions();
+        assert!(positions[0] < positions[1] && positions[1] < positions[2]);
+    }
+
+    #[test]
+    fn fragments_and_lifelines() {
+        let input: &str = "@startuml sequence
+actor User
+participant Shop
+User --> Shop : order
+activate Shop
+alt \"in stock\" {
+    create Invoice
+    Shop --> Invoice : new
+    Shop ..> User : confirmation
+} else {
+    Shop ..> User : \"out of stock\"
+}
+deactivate Shop
+destroy Invoice
+";
+        let mut sequence = Sequence::new(common::parse_body(Rule::SEQUENCE_DIAGRAM, Rule::SEQUENCE_BODY, input));
+        assert_eq!(sequence.get_participants().len(), 3);
+        assert!(sequence.get_participants()[0].is_actor());
+        match &sequence.get_statements()[2] {
+            Statement::Fragment(fragment) => {
+                assert_eq!(fragment.get_kind().to_owned(), String::from("alt"));
+                assert_eq!(fragment.get_sections().len(), 2);
+                assert_eq!(fragment.get_sections()[0].get_guard().to_owned(), String::from("in stock"));
+                assert_eq!(fragment.get_sections()[0].get_statements().len(), 3);
+            }
+            _ => panic!("Expected a fragment")
+        }
+
+        let mut svg = SVG::new();
+        sequence.draw(&mut svg);
+        let result = svg.to_string();
+        assert!(result.contains(">\nalt\n</text>"));
+        assert!(result.contains(">\n[in stock]\n</text>"));
+        assert!(result.contains(">\nout of stock\n</text>"));
+        assert!(result.contains("stroke-dasharray=\"8 8\""));
+    }
+
+    #[test]
+    fn autonumber_messages() {
+        let input: &str = "@startuml sequence\nautonumber 10\nA --> B : first\nB --> A : second\nA --> A : third\n";
+        let mut sequence = Sequence::new(common::parse_body(Rule::SEQUENCE_DIAGRAM, Rule::SEQUENCE_BODY, input));
+        let mut svg = SVG::new();
+        sequence.draw(&mut svg);
+        let result = svg.to_string();
+        assert!(result.contains(">\n10 first\n</text>"));
+        assert!(result.contains(">\n11 second\n</text>"));
+        assert!(result.contains(">\n12 third\n</text>"));
+        assert!(sequence.width() > sequence.positions()[1] as usize);
+    }
+
+    #[test]
+    fn sequence_without_participants() {
+        let mut sequence = Sequence::new(common::parse_body(Rule::SEQUENCE_DIAGRAM, Rule::SEQUENCE_BODY, "@startuml sequence\nautonumber\n"));
+        assert!(sequence.get_participants().is_empty());
+        assert!(sequence.width() > 0);
+        let mut svg = SVG::new();
+        sequence.draw(&mut svg);
+        assert!(sequence.height() > 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Autonumber start too large: 99999999999999999999")]
+    fn autonumber_start_too_large() {
+        Sequence::new(common::parse_body(Rule::SEQUENCE_DIAGRAM, Rule::SEQUENCE_BODY, "@startuml sequence\nautonumber 99999999999999999999\nA --> B : first\n"));
+    }
+
+    #[test]
+    fn keywords_need_whitespace() {
+        for (rule, input) in [(Rule::LIFELINE, "activateA\n"), (Rule::LIFELINE, "destroyA\n"), (Rule::PARTICIPANT, "participantA\n")] {
+            assert!(GrammarParser::parse(rule, input).is_err(), "{}", input);
+        }
+        let lifeline = GrammarParser::parse(Rule::LIFELINE, "activate A\n")
+            .unwrap().next().unwrap();
+        assert_eq!(lifeline.into_inner().nth(1).unwrap().as_str(), "A");
+    }
+}
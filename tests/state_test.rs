@@ -0,0 +1,90 @@
+mod common;
+
+#[cfg(test)]
+mod state_test {
+    use crate::common;
+    use pest::Parser;
+    use svg::node::element::SVG;
+    use uml_composer::{rules::{graph_layout, state::StateKind, state_machine::StateMachine, transition::Transition}, grammar_parser::{GrammarParser, Rule}};
+
+    #[test]
+    fn parse_transition_label() {
+        let input: &str = "Idle --> Running : start [battery > 10] / led on\n";
+        let transition_pair = GrammarParser::parse(Rule::TRANSITION, input)
+            .unwrap().next().unwrap();
+        let transition = Transition::new(transition_pair, "/0", "");
+        assert_eq!(transition.get_from().to_owned(), String::from("Idle"));
+        assert_eq!(transition.get_to().to_owned(), String::from("Running"));
+        assert_eq!(transition.get_event().to_owned(), String::from("start"));
+        assert_eq!(transition.get_guard().to_owned(), String::from("battery > 10"));
+        assert_eq!(transition.get_action().to_owned(), String::from("led on"));
+        assert_eq!(transition.get_label(), String::from("start [battery > 10] / led on"));
+
+        assert_eq!(Transition::parse_label("[empty]"), (String::new(), String::from("empty"), String::new()));
+        assert_eq!(Transition::parse_label("/ reset"), (String::new(), String::new(), String::from("reset")));
+    }
+
+    #[test]
+    fn layered_layout() {
+        // 0 -> 1 -> 2 -> 1 is a cycle, the last edge goes back up
+        let edges = vec![(0, 1), (1, 2), (2, 1), (0, 3)];
+        let sizes = vec![(30, 30), (100, 50), (100, 50), (100, 50)];
+        assert_eq!(graph_layout::back_edges(4, &edges), vec![false, false, true, false]);
+
+        let layout = graph_layout::layout(&sizes, &edges, 60, 80);
+        assert_eq!(layout.layers, vec![0, 1, 2, 1]);
+        assert_eq!(layout.width, 260);
+        assert_eq!(layout.height, 30 + 80 + 50 + 80 + 50);
+        assert!(layout.positions[1].1 < layout.positions[2].1);
+    }
+
+    #[test]
+    fn composite_states_and_pseudo_states() {
+        let input: &str = "@startuml state
+(*) --> Idle
+state Idle {
+    entry / led off
+}
+state \"Running\" as Running #lightgreen {
+    do / blink
+    (*) --> Heating
+    Heating --> Holding : ready
+    --
+    (*) --> Stirring
+    Stirring --> [H]
+}
+state check <<choice>>
+Idle --> check : start
+check --> Running[H*] : [resume]
+check --> Running : [fresh]
+Running --> Idle : stop
+Running --> (^)
+";
+        let state_machine = StateMachine::new(common::parse_body(Rule::STATE_DIAGRAM, Rule::STATE_REGION, input));
+        let states = state_machine.get_root().get_states();
+        let aliases: Vec<&String> = states.iter().map(|s| s.get_alias()).collect();
+        assert_eq!(aliases, vec!["/0(*)", "Idle", "Running", "check", "/0(^)"]);
+        assert_eq!(states[0].get_kind(), StateKind::Initial);
+        assert_eq!(states[1].get_actions(), &vec![String::from("entry / led off")]);
+        assert_eq!(states[3].get_kind(), StateKind::Choice);
+        assert_eq!(states[4].get_kind(), StateKind::Final);
+
+        let running = &states[2];
+        assert!(running.is_composite());
+        assert_eq!(running.get_regions().len(), 2);
+        let first: Vec<&String> = running.get_regions()[0].get_states().iter().map(|s| s.get_alias()).collect();
+        assert_eq!(first, vec!["Running/0(*)", "Heating", "Holding", "Running[H*]"]);
+        assert_eq!(running.get_regions()[0].get_states()[3].get_kind(), StateKind::DeepHistory);
+        assert_eq!(running.get_regions()[1].get_states()[1].get_kind(), StateKind::History);
+        assert!(running.get_width() > running.get_regions()[0].get_width() + running.get_regions()[1].get_width());
+
+        let mut svg = SVG::new();
+        state_machine.draw(&mut svg);
+        let result = svg.to_string();
+        assert!(result.contains(">\nentry / led off\n</text>"));
+        assert!(result.contains(">\n[resume]\n</text>"));
+        assert!(result.contains(">\nH*\n</text>"));
+        assert!(result.contains("fill=\"lightgreen\""));
+        assert!(result.contains("stroke-dasharray=\"8 8\""));
+    }
+}
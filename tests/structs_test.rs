@@ -156,4 +156,20 @@ mod structs_test {
         assert_eq!(new_class.get_x().to_owned(), x);
         assert_eq!(new_class.get_y().to_owned(), y);
     }
+
+    #[test]
+    fn draw_class_with_unicode_components() {
+        let mut svg = SVG::new();
+        let input: &str = "class Zamówienie {\n\tattributes {\n\t\t- źródło : Łańcuch\n\t}\n}\n";
+        let class = GrammarParser::parse(Rule::CLASS, input)
+            .unwrap().next().unwrap();
+        let mut new_class = Class::new(class.clone(), false);
+        assert_eq!(new_class.get_name().to_owned(), String::from("Zamówienie"));
+        assert_eq!(new_class.get_attributes()[0].get_name().to_owned(), String::from("źródło"));
+        assert_eq!(new_class.get_attributes()[0].get_kind().to_owned(), String::from("Łańcuch"));
+
+        new_class.draw(&mut svg, 10, 20);
+        // "- źródło : Łańcuch" has 18 characters
+        assert_eq!(new_class.get_width().to_owned(), 18*15);
+    }
 }
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod theme_test {
+    use pest::Parser;
+    use svg::node::element::SVG;
+    use uml_composer::{rules::{theme::{Theme, parse_color}, structs::Class, use_case::UseCase}, grammar_parser::{GrammarParser, Rule}};
+
+    #[test]
+    fn presets() {
+        assert_eq!(Theme::default(), Theme::light());
+        assert_eq!(Theme::preset("Dark"), Some(Theme::dark()));
+        assert_eq!(Theme::preset("monochrome"), Some(Theme::monochrome()));
+        assert_eq!(Theme::preset("neon"), None);
+    }
+
+    #[test]
+    fn parse_colors() {
+        assert_eq!(parse_color("#lightblue"), String::from("lightblue"));
+        assert_eq!(parse_color("#FF0000"), String::from("#FF0000"));
+        assert_eq!(parse_color("#abc"), String::from("#abc"));
+        assert_eq!(parse_color("red"), String::from("red"));
+    }
+
+    #[test]
+    fn set_values() {
+        let mut theme = Theme::light();
+        theme.set("BackgroundColor", "#eeeeee").unwrap();
+        theme.set("fontName", "Courier").unwrap();
+        theme.set("classBorderThickness", "4").unwrap();
+        assert_eq!(theme.background_color, String::from("#eeeeee"));
+        assert_eq!(theme.font_family, String::from("Courier"));
+        assert_eq!(theme.class_border_width, 4);
+
+        theme.set("theme", "dark").unwrap();
+        assert_eq!(theme, Theme::dark());
+
+        assert!(theme.set("shadowing", "true").is_err());
+        assert!(theme.set("lineThickness", "thick").is_err());
+    }
+
+    #[test]
+    fn apply_skinparam_block() {
+        let input: &str = "skinparam {\n\ttheme monochrome\n\tfontColor #333333\n\tuseCaseColor #lightgreen\n}\n";
+        let skinparam = GrammarParser::parse(Rule::SKINPARAM, input)
+            .unwrap().next().unwrap();
+        let mut theme = Theme::light();
+        theme.apply(skinparam).unwrap();
+        assert_eq!(theme.font_color, String::from("#333333"));
+        assert_eq!(theme.use_case_color, String::from("lightgreen"));
+        assert_eq!(theme.font_family, Theme::monochrome().font_family);
+    }
+
+    #[test]
+    fn apply_single_skinparam() {
+        let input: &str = "skinparam lineColor navy\n";
+        let skinparam = GrammarParser::parse(Rule::SKINPARAM, input)
+            .unwrap().next().unwrap();
+        let mut theme = Theme::light();
+        theme.apply(skinparam).unwrap();
+        assert_eq!(theme.line_color, String::from("navy"));
+    }
+
+    #[test]
+    fn draw_class_with_dark_theme_and_own_color() {
+        let input: &str = "class Invoice #lightblue {\n\tattributes {\n\t\t- id : int\n\t}\n}\n";
+        let class = GrammarParser::parse(Rule::CLASS, input)
+            .unwrap().next().unwrap();
+        let mut new_class = Class::new(class, false);
+        assert_eq!(new_class.get_name().to_owned(), String::from("Invoice"));
+        assert_eq!(new_class.get_color().to_owned(), Some(String::from("lightblue")));
+
+        let mut svg = SVG::new();
+        new_class.set_theme(&Theme::dark());
+        new_class.draw(&mut svg, 10, 20);
+        assert!(svg.to_string().contains("<rect fill=\"lightblue\" height=\"100\" stroke=\"#c8c8c8\" stroke-width=\"10\""));
+        assert!(svg.to_string().contains("fill=\"#e0e0e0\" font-size=\"28\""));
+    }
+
+    #[test]
+    fn draw_use_case_with_own_color() {
+        let input: &str = "usecase \"Pay\" as uc1 #FFAA00\n";
+        let use_case_pair = GrammarParser::parse(Rule::USE_CASE, input)
+            .unwrap().next().unwrap();
+        let mut use_case = UseCase::new(use_case_pair);
+        assert_eq!(use_case.get_use_case_alias().to_owned(), String::from("uc1"));
+
+        let mut svg = SVG::new();
+        use_case.draw(&mut svg, 100, 100, 100, 50);
+        assert!(svg.to_string().contains("fill=\"#FFAA00\""));
+    }
+}
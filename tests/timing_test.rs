@@ -0,0 +1,99 @@
+mod common;
+
+#[cfg(test)]
+mod timing_test {
+    use crate::common;
+    use pest::Parser;
+    use svg::node::element::SVG;
+    use uml_composer::{rules::{signal::{Signal, SignalKind}, timing::Timing}, grammar_parser::{GrammarParser, Rule}};
+
+    #[test]
+    fn parse_into_clock() {
+        let input: &str = "clock clk with period 50 pulse 10 offset 5\n";
+        let clock_pair = GrammarParser::parse(Rule::CLOCK, input)
+            .unwrap().next().unwrap();
+        let clock = Signal::new(clock_pair);
+        assert_eq!(clock.get_alias().to_owned(), String::from("clk"));
+        assert_eq!(clock.get_kind(), SignalKind::Clock { period: 50, pulse: 10, offset: 5 });
+    }
+
+    #[test]
+    fn state_changes_at_time_points() {
+        let input: &str = "@startuml timing
+robust \"Web Browser\" as WB
+concise \"Web User\" as WU
+clock clk with period 100
+@0
+WU is Idle
+WB is Idle
+@100
+WU is Waiting
+WB is Processing
+@300
+WB is Waiting
+WB@100 <-> @300 : {200ms}
+@0 <-> @100 : {setup}
+";
+        let timing = Timing::new(common::parse_diagram(Rule::TIMING_DIAGRAM, input));
+        let signals = timing.get_signals();
+        assert_eq!(signals.len(), 3);
+        assert_eq!(signals[0].get_kind(), SignalKind::Robust);
+        assert_eq!(signals[1].get_kind(), SignalKind::Concise);
+        assert_eq!(signals[0].get_changes().len(), 3);
+        assert_eq!(signals[0].states(), vec![String::from("Idle"), String::from("Processing"), String::from("Waiting")]);
+        assert_eq!(signals[1].get_changes()[1], (100, String::from("Waiting")));
+        assert_eq!(timing.get_times().to_owned(), vec![0, 100, 300]);
+        assert_eq!(timing.get_constraints().len(), 2);
+        assert_eq!(timing.get_constraints()[0].get_alias().to_owned(), Some(String::from("WB")));
+
+        // the plot goes one average step past the last time point
+        let time = timing.time_scale();
+        assert!(time.x(100) < time.x(300));
+        assert_eq!(time.end, 450);
+
+        let mut svg = SVG::new();
+        timing.draw(&mut svg);
+        let output = svg.to_string();
+        assert!(output.contains("Web Browser"));
+        assert!(output.contains("Processing"));
+        assert!(output.contains("{200ms}"));
+        assert!(output.contains("<polyline"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Alias not found: DB")]
+    fn state_change_of_unknown_lifeline() {
+        let input: &str = "@startuml timing
+robust Server
+@0
+DB is Idle
+";
+        Timing::new(common::parse_diagram(Rule::TIMING_DIAGRAM, input));
+    }
+
+    #[test]
+    #[should_panic(expected = "Pulse of clock clk must be positive and shorter than its period")]
+    fn clock_pulse_as_long_as_period() {
+        let input: &str = "clock clk with period 50 pulse 50\n";
+        let clock_pair = GrammarParser::parse(Rule::CLOCK, input)
+            .unwrap().next().unwrap();
+        Signal::new(clock_pair);
+    }
+
+    #[test]
+    fn fast_clock_skips_narrow_cycles() {
+        let input: &str = "@startuml timing
+clock clk with period 2
+concise Server
+@0
+Server is Idle
+@2000000
+Server is Busy
+";
+        let timing = Timing::new(common::parse_diagram(Rule::TIMING_DIAGRAM, input));
+        let mut svg = SVG::new();
+        timing.draw(&mut svg);
+        // a couple of hundred pixels can't show a million cycles
+        assert!(svg.to_string().len() < 100_000);
+    }
+}
@@ -2,7 +2,7 @@
 mod use_case_test {
     use pest::Parser;
     use svg::node::element::SVG;
-    use uml_composer::{rules::use_case::UseCase, grammar_parser::{GrammarParser, Rule}};
+    use uml_composer::{rules::{use_case::UseCase, use_case_diagram::UseCaseDiagram}, grammar_parser::{GrammarParser, Rule}};
 
     #[test]
     fn use_case_with_alias() {
@@ -41,6 +41,72 @@ mod use_case_test {
         assert!(svg.to_string().contains(&format!("<ellipse cx=\"{}\" cy=\"{}\"", x, y)));
     }
 
-}
+    #[test]
+    fn use_case_with_multiline_label() {
+        let input: &str = "usecase \"Order food\\nat the counter\" as uc1 \n";
+        let use_case_pair = GrammarParser::parse(Rule::USE_CASE, input)
+            .unwrap().next().unwrap();
+        let mut use_case = UseCase::new(use_case_pair);
+        assert_eq!(use_case.get_use_case_label().to_owned(), String::from("Order food\nat the counter"));
+
+        let mut svg = SVG::new();
+        use_case.draw(&mut svg, 100, 100, 100, 50);
+        assert!(svg.to_string().contains("<tspan x=\"100\" y=\"94\">\nOrder food\n</tspan>"));
+        assert!(svg.to_string().contains("<tspan x=\"100\" y=\"118\">\nat the counter\n</tspan>"));
+        assert!(use_case.get_height() > 50);
+    }
+
+    #[test]
+    fn use_case_label_is_wrapped() {
+        let input: &str = "usecase \"Prepare a very long order for the whole family\"\n";
+        let use_case_pair = GrammarParser::parse(Rule::USE_CASE, input)
+            .unwrap().next().unwrap();
+        let mut use_case = UseCase::new(use_case_pair);
+
+        let mut svg = SVG::new();
+        use_case.draw(&mut svg, 100, 100, 100, 50);
+        assert_eq!(svg.to_string().matches("<tspan").count(), 3);
+    }
 
+    #[test]
+    fn use_case_with_extension_points() {
+        let input: &str = "usecase \"Checkout\" as uc1 {\n    payment\n    \"gift wrapping\"\n}\n";
+        let use_case_pair = GrammarParser::parse(Rule::USE_CASE, input)
+            .unwrap().next().unwrap();
+        let mut use_case = UseCase::new(use_case_pair);
+        assert_eq!(use_case.get_use_case_alias().to_owned(), String::from("uc1"));
+        assert_eq!(*use_case.get_extension_points(), vec![String::from("payment"), String::from("gift wrapping")]);
 
+        let mut svg = SVG::new();
+        use_case.draw(&mut svg, 100, 100, 100, 50);
+        assert!(svg.to_string().contains("\nextension points\n</tspan>"));
+        assert!(svg.to_string().contains("\ngift wrapping\n</tspan>"));
+        // the separator of the compartment
+        assert_eq!(svg.to_string().matches("<line").count(), 1);
+        assert!(use_case.get_height() > 50);
+    }
+
+    #[test]
+    fn diagram_with_only_free_use_cases() {
+        let input: &str = "@startuml usecase
+usecase \"Place order\" as uc1
+usecase \"Pay\" as uc2
+uc1 --> uc2
+";
+        let diagram_pair = GrammarParser::parse(Rule::USE_CASE_DIAGRAM, input)
+            .unwrap().next().unwrap();
+        let mut diagram = UseCaseDiagram::new(diagram_pair);
+        assert!(diagram.get_actors().is_empty());
+        assert_eq!(diagram.get_links().len(), 1);
+        let (order_x, _) = diagram.get_use_case_position("uc1").unwrap();
+        let (pay_x, _) = diagram.get_use_case_position("uc2").unwrap();
+        assert!(pay_x > order_x);
+
+        let mut svg = SVG::new();
+        diagram.draw(&mut svg);
+        let response = svg.to_string();
+        assert_eq!(response.matches("<ellipse").count(), 2);
+        assert!(response.contains("\nPlace order\n"));
+        assert!(diagram.width() as i32 > pay_x);
+    }
+}